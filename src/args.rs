@@ -1,3 +1,9 @@
+use crate::diagnostic;
+use crate::runner;
+use crate::test_quarantine;
+use crate::test_state;
+use crate::wes;
+
 use std::path::PathBuf;
 use structopt::{clap, StructOpt};
 use url::Url;
@@ -14,8 +20,8 @@ pub enum Args {
     #[structopt(setting(clap::AppSettings::ColoredHelp))]
     /// Generate a template file for the Yevis metadata file.
     MakeTemplate {
-        /// Remote location of a primary workflow document.
-        workflow_location: Url,
+        /// Location of a primary workflow document (remote URL, or local file path in a clone of a GitHub repository).
+        workflow_location: String,
 
         /// GitHub Personal Access Token.
         #[structopt(long = "gh-token")]
@@ -29,6 +35,11 @@ pub enum Args {
         #[structopt(long)]
         use_commit_url: bool,
 
+        /// List all files in the directory of the primary workflow as `workflow.files`,
+        /// instead of only the files that the workflow depends on.
+        #[structopt(long)]
+        list_all_files: bool,
+
         /// Verbose mode.
         #[structopt(short, long)]
         verbose: bool,
@@ -45,6 +56,51 @@ pub enum Args {
         #[structopt(long = "gh-token")]
         github_token: Option<String>,
 
+        /// Validate without network access.
+        /// The license is checked against the bundled SPDX license list, and URLs are not pinned to commits.
+        #[structopt(long)]
+        offline: bool,
+
+        /// Path to an additional license list in SPDX `licenses.json` format (used with `--offline`).
+        #[structopt(long, parse(from_os_str), requires = "offline")]
+        license_list: Option<PathBuf>,
+
+        /// Format of the validation report.
+        #[structopt(long, default_value = "text", possible_values = diagnostic::ReportFormat::VARIANTS)]
+        report_format: diagnostic::ReportFormat,
+
+        /// Path to write the validation report to (default: stdout for `json` and `sarif`).
+        #[structopt(long, parse(from_os_str))]
+        report_output: Option<PathBuf>,
+
+        /// Verbose mode.
+        #[structopt(short, long)]
+        verbose: bool,
+    },
+
+    #[structopt(setting(clap::AppSettings::ColoredHelp))]
+    /// Print the JSON Schema of the Yevis metadata file (for editors and other tools).
+    Schema {
+        /// Path to write the JSON Schema to (default: stdout).
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Verbose mode.
+        #[structopt(short, long)]
+        verbose: bool,
+    },
+
+    #[structopt(setting(clap::AppSettings::ColoredHelp))]
+    /// Rewrite the Yevis metadata files in the current format version and show the diff.
+    Migrate {
+        /// Path to the Yevis metadata files to be rewritten in place.
+        #[structopt(parse(from_os_str), default_value = "yevis-metadata.yml")]
+        metadata_locations: Vec<PathBuf>,
+
+        /// Show the diff without rewriting the files.
+        #[structopt(long)]
+        dry_run: bool,
+
         /// Verbose mode.
         #[structopt(short, long)]
         verbose: bool,
@@ -66,9 +122,48 @@ pub enum Args {
         #[structopt(short, long)]
         wes_location: Option<Url>,
 
-        /// Location of the Docker host.
-        #[structopt(short, long, default_value = "unix:///var/run/docker.sock")]
-        docker_host: Url,
+        /// Location of the Docker host (the socket of the container runtime).
+        /// If not specified, the default socket of the container runtime (e.g., `unix:///var/run/docker.sock`) is used.
+        #[structopt(short, long)]
+        docker_host: Option<Url>,
+
+        /// Container runtime used to start `sapporo-service`.
+        /// If not specified, the first of docker, podman and nerdctl found in PATH is used.
+        #[structopt(long, possible_values = wes::instance::RuntimeType::VARIANTS)]
+        container_runtime: Option<wes::instance::RuntimeType>,
+
+        /// Maximum number of test runs submitted to the WES at the same time.
+        #[structopt(short = "j", long, default_value = "4")]
+        parallel: usize,
+
+        /// Timeout of each test case in seconds. The run is canceled when the timeout passes.
+        /// `timeout` in `workflow.testing[]` takes precedence.
+        #[structopt(long)]
+        timeout: Option<u64>,
+
+        /// Where the test runs are executed.
+        /// `local` runs the engine CLIs (e.g., `cwltool`, `nextflow`) installed on the host directly, without WES or Docker.
+        #[structopt(long, default_value = "wes", possible_values = runner::RunnerType::VARIANTS)]
+        runner: runner::RunnerType,
+
+        /// Print the stdout and stderr of the test runs (including `task_logs`) while they are in progress.
+        /// Each line is prefixed by the test ID, and the number of lines is limited when several tests run at once.
+        #[structopt(long)]
+        follow: bool,
+
+        /// Re-attach to the WES runs saved in the state file by a previous `yevis test`.
+        /// Running and completed runs are reused, and failed or missing runs are submitted again.
+        #[structopt(long)]
+        resume: bool,
+
+        /// Path to the state file, where the WES runs are saved to be resumed.
+        #[structopt(long, parse(from_os_str), default_value = test_state::DEFAULT_STATE_FILE)]
+        state_file: PathBuf,
+
+        /// Path to the quarantine file of the registry, listing the test cases known to be flaky.
+        /// Their failures are reported, but do not fail the test.
+        #[structopt(long, parse(from_os_str), default_value = test_quarantine::DEFAULT_QUARANTINE_FILE)]
+        quarantine_file: PathBuf,
 
         /// Get modified files from a GitHub Pull Request.
         /// This option is used for pull request events in the the CI environment.
@@ -80,6 +175,14 @@ pub enum Args {
         #[structopt(short, long)]
         fetch_ro_crate: bool,
 
+        /// Path to write the test report to in JUnit XML format.
+        #[structopt(long, parse(from_os_str))]
+        junit_report: Option<PathBuf>,
+
+        /// Path to write the test report to in JSON format.
+        #[structopt(long, parse(from_os_str))]
+        json_report: Option<PathBuf>,
+
         /// Verbose mode.
         #[structopt(short, long)]
         verbose: bool,
@@ -105,9 +208,24 @@ pub enum Args {
         #[structopt(short, long)]
         wes_location: Option<Url>,
 
-        /// Location of the Docker host.
-        #[structopt(short, long, default_value = "unix:///var/run/docker.sock")]
-        docker_host: Url,
+        /// Location of the Docker host (the socket of the container runtime).
+        /// If not specified, the default socket of the container runtime (e.g., `unix:///var/run/docker.sock`) is used.
+        #[structopt(short, long)]
+        docker_host: Option<Url>,
+
+        /// Container runtime used to start `sapporo-service`.
+        /// If not specified, the first of docker, podman and nerdctl found in PATH is used.
+        #[structopt(long, possible_values = wes::instance::RuntimeType::VARIANTS)]
+        container_runtime: Option<wes::instance::RuntimeType>,
+
+        /// Maximum number of test runs submitted to the WES at the same time.
+        #[structopt(short = "j", long, default_value = "4")]
+        parallel: usize,
+
+        /// Timeout of each test case in seconds. The run is canceled when the timeout passes.
+        /// `timeout` in `workflow.testing[]` takes precedence.
+        #[structopt(long)]
+        timeout: Option<u64>,
 
         /// Verbose mode.
         #[structopt(short, long)]
@@ -138,9 +256,24 @@ pub enum Args {
         #[structopt(short, long)]
         wes_location: Option<Url>,
 
-        /// Location of Docker host.
-        #[structopt(short, long, default_value = "unix:///var/run/docker.sock")]
-        docker_host: Url,
+        /// Location of the Docker host (the socket of the container runtime).
+        /// If not specified, the default socket of the container runtime (e.g., `unix:///var/run/docker.sock`) is used.
+        #[structopt(short, long)]
+        docker_host: Option<Url>,
+
+        /// Container runtime used to start `sapporo-service`.
+        /// If not specified, the first of docker, podman and nerdctl found in PATH is used.
+        #[structopt(long, possible_values = wes::instance::RuntimeType::VARIANTS)]
+        container_runtime: Option<wes::instance::RuntimeType>,
+
+        /// Maximum number of test runs submitted to the WES at the same time.
+        #[structopt(short = "j", long, default_value = "4")]
+        parallel: usize,
+
+        /// Timeout of each test case in seconds. The run is canceled when the timeout passes.
+        /// `timeout` in `workflow.testing[]` takes precedence.
+        #[structopt(long)]
+        timeout: Option<u64>,
 
         /// Get modified files from GitHub Pull Request.
         /// This option is used for pull request events in the CI environment.
@@ -208,6 +341,8 @@ impl Args {
         match self {
             Args::MakeTemplate { verbose, .. } => *verbose,
             Args::Validate { verbose, .. } => *verbose,
+            Args::Schema { verbose, .. } => *verbose,
+            Args::Migrate { verbose, .. } => *verbose,
             Args::Test { verbose, .. } => *verbose,
             Args::PullRequest { verbose, .. } => *verbose,
             Args::Publish { verbose, .. } => *verbose,
@@ -219,6 +354,8 @@ impl Args {
         match self {
             Args::MakeTemplate { github_token, .. } => github_token.clone(),
             Args::Validate { github_token, .. } => github_token.clone(),
+            Args::Schema { .. } => None,
+            Args::Migrate { .. } => None,
             Args::Test { github_token, .. } => github_token.clone(),
             Args::PullRequest { github_token, .. } => github_token.clone(),
             Args::Publish { github_token, .. } => github_token.clone(),
@@ -0,0 +1,36 @@
+pub mod api;
+
+use crate::env;
+
+use anyhow::{ensure, Result};
+use serde_json::Value;
+use url::Url;
+
+/// The token is read from the environment variable `BITBUCKET_TOKEN` if it is set.
+pub fn get_request(url: &Url, query: &[(&str, &str)]) -> Result<Value> {
+    let client = reqwest::blocking::Client::new();
+    let mut request = client
+        .get(url.as_str())
+        .header(reqwest::header::USER_AGENT, "yevis")
+        .query(query);
+    if let Some(token) = env::bitbucket_token() {
+        request = request.header(reqwest::header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let response = request.send()?;
+    let status = response.status();
+    let res_body = response.json::<Value>()?;
+    ensure!(
+        status != reqwest::StatusCode::UNAUTHORIZED,
+        "Failed to authenticate with Bitbucket. Please check the environment variable `BITBUCKET_TOKEN`."
+    );
+    ensure!(
+        status.is_success(),
+        "Failed to get request to {}. Response: {}",
+        url,
+        match res_body.get("error").and_then(|e| e.get("message")) {
+            Some(message) => message.as_str().unwrap_or_else(|| status.as_str()),
+            None => status.as_str(),
+        }
+    );
+    Ok(res_body)
+}
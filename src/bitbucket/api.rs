@@ -0,0 +1,187 @@
+use crate::bitbucket;
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+fn repo_api_url(workspace: impl AsRef<str>, name: impl AsRef<str>) -> String {
+    format!(
+        "https://api.bitbucket.org/2.0/repositories/{}/{}",
+        workspace.as_ref(),
+        name.as_ref()
+    )
+}
+
+/// https://developer.atlassian.com/cloud/bitbucket/rest/api-group-repositories/#api-repositories-workspace-repo-slug-get
+pub fn get_repos(workspace: impl AsRef<str>, name: impl AsRef<str>) -> Result<Value> {
+    let url = Url::parse(&repo_api_url(workspace, name))?;
+    bitbucket::get_request(&url, &[])
+}
+
+pub fn get_default_branch(
+    workspace: impl AsRef<str>,
+    name: impl AsRef<str>,
+    memo: Option<&mut HashMap<String, String>>,
+) -> Result<String> {
+    let key = format!("bitbucket.org/{}/{}", workspace.as_ref(), name.as_ref());
+
+    if let Some(ref memo) = memo {
+        if let Some(default_branch) = memo.get(&key) {
+            return Ok(default_branch.to_string());
+        }
+    }
+
+    let res = get_repos(&workspace, &name)?;
+    let err_message = "Failed to parse the response to get the default branch";
+    let default_branch = res
+        .get("mainbranch")
+        .ok_or_else(|| anyhow!(err_message))?
+        .get("name")
+        .ok_or_else(|| anyhow!(err_message))?
+        .as_str()
+        .ok_or_else(|| anyhow!(err_message))?
+        .to_string();
+    if let Some(memo) = memo {
+        memo.insert(key, default_branch.clone());
+    }
+
+    Ok(default_branch)
+}
+
+/// https://developer.atlassian.com/cloud/bitbucket/rest/api-group-refs/#api-repositories-workspace-repo-slug-refs-branches-name-get
+pub fn get_branch(
+    workspace: impl AsRef<str>,
+    name: impl AsRef<str>,
+    branch_name: impl AsRef<str>,
+) -> Result<Value> {
+    let url = Url::parse(&format!(
+        "{}/refs/branches/{}",
+        repo_api_url(workspace, name),
+        branch_name.as_ref()
+    ))?;
+    bitbucket::get_request(&url, &[])
+}
+
+pub fn get_latest_commit_sha(
+    workspace: impl AsRef<str>,
+    name: impl AsRef<str>,
+    branch_name: impl AsRef<str>,
+    memo: Option<&mut HashMap<String, String>>,
+) -> Result<String> {
+    let key = format!(
+        "bitbucket.org/{}/{}/{}",
+        workspace.as_ref(),
+        name.as_ref(),
+        branch_name.as_ref()
+    );
+
+    if let Some(ref memo) = memo {
+        if let Some(latest_commit_hash) = memo.get(&key) {
+            return Ok(latest_commit_hash.to_string());
+        }
+    }
+
+    let res = get_branch(&workspace, &name, &branch_name)?;
+    let err_message = "Failed to parse the response to get a latest commit sha";
+    let latest_commit_hash = res
+        .get("target")
+        .ok_or_else(|| anyhow!(err_message))?
+        .get("hash")
+        .ok_or_else(|| anyhow!(err_message))?
+        .as_str()
+        .ok_or_else(|| anyhow!(err_message))?
+        .to_string();
+    if let Some(memo) = memo {
+        memo.insert(key, latest_commit_hash.clone());
+    }
+
+    Ok(latest_commit_hash)
+}
+
+/// https://developer.atlassian.com/cloud/bitbucket/rest/api-group-source/#api-repositories-workspace-repo-slug-src-commit-path-get
+///
+/// Return: (path, is_dir) of the entries directly under `path`
+pub fn get_src(
+    workspace: impl AsRef<str>,
+    name: impl AsRef<str>,
+    path: impl AsRef<Path>,
+    commit: impl AsRef<str>,
+) -> Result<Vec<(PathBuf, bool)>> {
+    let mut url = Url::parse(&format!(
+        "{}/src/{}/",
+        repo_api_url(workspace, name),
+        commit.as_ref()
+    ))?;
+    if !path.as_ref().as_os_str().is_empty() {
+        url = url.join(&format!("{}/", path.as_ref().display()))?;
+    }
+    let err_message = "Failed to parse the response to get the file list.";
+    let mut entries: Vec<(PathBuf, bool)> = Vec::new();
+    loop {
+        let res = bitbucket::get_request(&url, &[("pagelen", "100")])?;
+        let values = match res.get("values").and_then(|v| v.as_array()) {
+            Some(values) => values,
+            None => bail!(err_message),
+        };
+        for value in values {
+            let path = PathBuf::from(
+                value
+                    .get("path")
+                    .ok_or_else(|| anyhow!(err_message))?
+                    .as_str()
+                    .ok_or_else(|| anyhow!(err_message))?,
+            );
+            let r#type = value
+                .get("type")
+                .ok_or_else(|| anyhow!(err_message))?
+                .as_str()
+                .ok_or_else(|| anyhow!(err_message))?;
+            entries.push((path, r#type == "commit_directory"));
+        }
+        match res.get("next").and_then(|v| v.as_str()) {
+            Some(next) => url = Url::parse(next)?,
+            None => break,
+        }
+    }
+    Ok(entries)
+}
+
+/// if called - path: src
+/// return: src/main.rs, src/lib.rs, src/test.rs
+pub fn get_file_list_recursive(
+    workspace: impl AsRef<str>,
+    name: impl AsRef<str>,
+    path: impl AsRef<Path>,
+    commit: impl AsRef<str>,
+) -> Result<Vec<PathBuf>> {
+    let mut file_list: Vec<PathBuf> = Vec::new();
+    for (path, is_dir) in get_src(workspace.as_ref(), name.as_ref(), path, commit.as_ref())? {
+        if is_dir {
+            let mut sub_file_list =
+                get_file_list_recursive(workspace.as_ref(), name.as_ref(), path, commit.as_ref())?;
+            file_list.append(&mut sub_file_list);
+        } else {
+            file_list.push(path);
+        }
+    }
+    Ok(file_list)
+}
+
+/// Bitbucket has no API to get the README, so look for it in the root directory.
+pub fn get_readme_path(
+    workspace: impl AsRef<str>,
+    name: impl AsRef<str>,
+    commit: impl AsRef<str>,
+) -> Result<Option<PathBuf>> {
+    let mut readmes = get_src(workspace, name, "", commit)?
+        .into_iter()
+        .filter(|(path, is_dir)| {
+            !is_dir && path.to_string_lossy().to_lowercase().starts_with("readme")
+        })
+        .map(|(path, _)| path)
+        .collect::<Vec<_>>();
+    readmes.sort();
+    Ok(readmes.into_iter().next())
+}
@@ -0,0 +1,317 @@
+use crate::orcid;
+
+use anyhow::{anyhow, bail, Result};
+use colored::Colorize;
+use log::{debug, warn};
+use regex::Regex;
+use serde_json::Value;
+use std::path::Path;
+
+/// Citation files looked up in the root of the workflow repository, in this order.
+pub const CITATION_FILES: &[&str] = &["CITATION.cff", "codemeta.json", ".zenodo.json"];
+
+/// An author listed in a citation file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CitationAuthor {
+    /// `Family, Given`, as in Zenodo
+    pub name: String,
+    pub affiliation: Option<String>,
+    /// Validated ORCID iD, e.g., `0000-0002-1825-0097`
+    pub orcid: Option<String>,
+    pub github_account: Option<String>,
+}
+
+impl CitationAuthor {
+    /// An invalid ORCID iD is dropped with a warning.
+    pub fn new(
+        name: String,
+        affiliation: Option<String>,
+        orcid: Option<&str>,
+        github_account: Option<String>,
+    ) -> Self {
+        let orcid = orcid
+            .map(orcid::normalize)
+            .and_then(|orcid| match orcid::validate(&orcid) {
+                Ok(()) => Some(orcid),
+                Err(e) => {
+                    warn!("{}: {} of {}, skipped", "Warning".yellow(), e, name);
+                    None
+                }
+            });
+        Self {
+            name,
+            affiliation,
+            orcid,
+            github_account,
+        }
+    }
+}
+
+/// Read the authors from the first citation file found in the repository.
+/// `read`: read a file by the path relative to the repository root
+pub fn find_authors(read: impl Fn(&Path) -> Result<String>) -> Vec<CitationAuthor> {
+    for file_name in CITATION_FILES {
+        let content = match read(Path::new(file_name)) {
+            Ok(content) => content,
+            Err(e) => {
+                debug!("Failed to read {}: {}", file_name, e);
+                continue;
+            }
+        };
+        match parse_authors(file_name, content) {
+            Ok(authors) => {
+                debug!("Found {} authors in {}", authors.len(), file_name);
+                return authors;
+            }
+            Err(e) => warn!(
+                "{}: Failed to read the authors from {}: {}",
+                "Warning".yellow(),
+                file_name,
+                e
+            ),
+        }
+    }
+    vec![]
+}
+
+pub fn parse_authors(
+    file_name: impl AsRef<str>,
+    content: impl AsRef<str>,
+) -> Result<Vec<CitationAuthor>> {
+    // Even json can be read with yaml reader
+    let value: Value = serde_yaml::from_str(content.as_ref())?;
+    match file_name.as_ref() {
+        "CITATION.cff" => parse_cff(&value),
+        "codemeta.json" => parse_codemeta(&value),
+        ".zenodo.json" => parse_zenodo_json(&value),
+        file_name => bail!("Unsupported citation file: {}", file_name),
+    }
+}
+
+fn as_string(value: Option<&Value>) -> Option<String> {
+    value
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// `Family, Given`, or either of them if the other is missing.
+fn join_name(family: Option<String>, given: Option<String>) -> Option<String> {
+    match (family, given) {
+        (Some(family), Some(given)) => Some(format!("{}, {}", family, given)),
+        (Some(name), None) | (None, Some(name)) => Some(name),
+        (None, None) => None,
+    }
+}
+
+/// https://github.com/citation-file-format/citation-file-format/blob/main/schema-guide.md
+fn parse_cff(value: &Value) -> Result<Vec<CitationAuthor>> {
+    let authors = value
+        .get("authors")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("No `authors` in CITATION.cff"))?;
+    let github_account_re = Regex::new(r"^[A-Za-z0-9][A-Za-z0-9-]*$").unwrap();
+    Ok(authors
+        .iter()
+        .filter_map(|author| {
+            let family = match (
+                as_string(author.get("name-particle")),
+                as_string(author.get("family-names")),
+            ) {
+                (Some(particle), Some(family)) => Some(format!("{} {}", particle, family)),
+                (_, family) => family,
+            };
+            // an entity has `name` instead of `family-names` and `given-names`
+            let name = join_name(family, as_string(author.get("given-names")))
+                .or_else(|| as_string(author.get("name")))?;
+            let github_account =
+                as_string(author.get("alias")).filter(|alias| github_account_re.is_match(alias));
+            Some(CitationAuthor::new(
+                name,
+                as_string(author.get("affiliation")),
+                author.get("orcid").and_then(|v| v.as_str()),
+                github_account,
+            ))
+        })
+        .collect())
+}
+
+/// https://codemeta.github.io/terms/
+fn parse_codemeta(value: &Value) -> Result<Vec<CitationAuthor>> {
+    let authors = match value.get("author") {
+        Some(Value::Array(authors)) => authors.clone(),
+        Some(author @ Value::Object(_)) => vec![author.clone()],
+        _ => bail!("No `author` in codemeta.json"),
+    };
+    let github_re = Regex::new(r"^https?://github\.com/([A-Za-z0-9-]+)/?$").unwrap();
+    Ok(authors
+        .iter()
+        .filter_map(|author| {
+            let name = join_name(
+                as_string(author.get("familyName")),
+                as_string(author.get("givenName")),
+            )
+            .or_else(|| as_string(author.get("name")))?;
+            let ids = ["@id", "identifier", "url"]
+                .iter()
+                .filter_map(|key| as_string(author.get(key)))
+                .collect::<Vec<_>>();
+            let orcid = ids.iter().find(|id| id.contains("orcid.org"));
+            let github_account = ids
+                .iter()
+                .find_map(|id| github_re.captures(id).map(|caps| caps[1].to_string()));
+            let affiliation = match author.get("affiliation") {
+                Some(Value::Array(affiliations)) => affiliations.first().cloned(),
+                affiliation => affiliation.cloned(),
+            }
+            .and_then(|affiliation| match affiliation {
+                Value::String(_) => as_string(Some(&affiliation)),
+                _ => as_string(affiliation.get("name")),
+            });
+            Some(CitationAuthor::new(
+                name,
+                affiliation,
+                orcid.map(|orcid| orcid.as_str()),
+                github_account,
+            ))
+        })
+        .collect())
+}
+
+/// https://developers.zenodo.org/#representation
+fn parse_zenodo_json(value: &Value) -> Result<Vec<CitationAuthor>> {
+    let creators = value
+        .get("creators")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("No `creators` in .zenodo.json"))?;
+    Ok(creators
+        .iter()
+        .filter_map(|creator| {
+            Some(CitationAuthor::new(
+                as_string(creator.get("name"))?,
+                as_string(creator.get("affiliation")),
+                creator.get("orcid").and_then(|v| v.as_str()),
+                None,
+            ))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cff() -> Result<()> {
+        let content = r#"
+cff-version: 1.2.0
+message: "If you use this software, please cite it as below."
+title: yevis-cli
+authors:
+  - family-names: Druskat
+    given-names: Stephan
+    orcid: "https://orcid.org/0000-0003-4925-7248"
+    affiliation: "German Aerospace Center"
+    alias: sdruskat
+  - family-names: Beethoven
+    name-particle: van
+    given-names: Ludwig
+    orcid: "https://orcid.org/0000-0002-1825-0098"
+  - name: "The Research Software project"
+"#;
+        let authors = parse_authors("CITATION.cff", content)?;
+        assert_eq!(
+            authors,
+            vec![
+                CitationAuthor {
+                    name: "Druskat, Stephan".to_string(),
+                    affiliation: Some("German Aerospace Center".to_string()),
+                    orcid: Some("0000-0003-4925-7248".to_string()),
+                    github_account: Some("sdruskat".to_string()),
+                },
+                CitationAuthor {
+                    name: "van Beethoven, Ludwig".to_string(),
+                    affiliation: None,
+                    orcid: None,
+                    github_account: None,
+                },
+                CitationAuthor {
+                    name: "The Research Software project".to_string(),
+                    affiliation: None,
+                    orcid: None,
+                    github_account: None,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_codemeta() -> Result<()> {
+        let content = r#"{
+  "@context": "https://doi.org/10.5063/schema/codemeta-2.0",
+  "@type": "SoftwareSourceCode",
+  "author": [
+    {
+      "@type": "Person",
+      "@id": "https://orcid.org/0000-0002-1825-0097",
+      "givenName": "Josiah",
+      "familyName": "Carberry",
+      "affiliation": {"@type": "Organization", "name": "Brown University"}
+    },
+    {
+      "@type": "Person",
+      "givenName": "Jane",
+      "familyName": "Doe",
+      "url": "https://github.com/janedoe",
+      "affiliation": "Example University"
+    }
+  ]
+}"#;
+        let authors = parse_authors("codemeta.json", content)?;
+        assert_eq!(
+            authors,
+            vec![
+                CitationAuthor {
+                    name: "Carberry, Josiah".to_string(),
+                    affiliation: Some("Brown University".to_string()),
+                    orcid: Some("0000-0002-1825-0097".to_string()),
+                    github_account: None,
+                },
+                CitationAuthor {
+                    name: "Doe, Jane".to_string(),
+                    affiliation: Some("Example University".to_string()),
+                    orcid: None,
+                    github_account: Some("janedoe".to_string()),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_zenodo_json() -> Result<()> {
+        let content = r#"{
+  "creators": [
+    {"name": "Carberry, Josiah", "affiliation": "Brown University", "orcid": "0000-0002-1825-0097"}
+  ]
+}"#;
+        let authors = parse_authors(".zenodo.json", content)?;
+        assert_eq!(authors.len(), 1);
+        assert_eq!(authors[0].orcid, Some("0000-0002-1825-0097".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_authors() -> Result<()> {
+        let authors = find_authors(|path| match path.to_str() {
+            Some("codemeta.json") => Ok("{}".to_string()),
+            Some(".zenodo.json") => Ok(r#"{"creators": [{"name": "Doe, Jane"}]}"#.to_string()),
+            _ => bail!("No such file"),
+        });
+        assert_eq!(authors.len(), 1);
+        assert_eq!(authors[0].name, "Doe, Jane");
+        Ok(())
+    }
+}
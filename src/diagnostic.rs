@@ -0,0 +1,216 @@
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use serde_with::skip_serializing_none;
+use std::fmt;
+use std::str::FromStr;
+
+/// A problem found in a metadata file. Every problem is an error.
+/// `path` is the YAML path of the field, e.g., `workflow.testing[1].files[0].url`.
+/// An empty `path` means the document itself.
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Diagnostic {
+    pub path: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl Diagnostic {
+    pub fn new(path: impl AsRef<str>, message: impl fmt::Display) -> Self {
+        Self {
+            path: path.as_ref().to_string(),
+            message: message.to_string(),
+            line: None,
+            column: None,
+        }
+    }
+
+    /// `position`: (line, column), both 1-based
+    pub fn with_position(mut self, position: Option<(usize, usize)>) -> Self {
+        if let Some((line, column)) = position {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+        self
+    }
+
+    /// `workflow.testing[1].files[0].url` -> `workflow.testing[].files[].url`
+    pub fn rule_id(&self) -> String {
+        let index_re = Regex::new(r"\[\d+\]").unwrap();
+        match self.path.as_str() {
+            "" => "document".to_string(),
+            path => index_re.replace_all(path, "[]").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.as_str() {
+            "" => write!(f, "{}", self.message),
+            path => write!(f, "`{}`: {}", path, self.message),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.0.push(diagnostic);
+    }
+
+    pub fn error(&mut self, path: impl AsRef<str>, message: impl fmt::Display) {
+        self.0.push(Diagnostic::new(path, message));
+    }
+}
+
+/// Diagnostics of one metadata file.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct FileReport {
+    pub location: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl FileReport {
+    /// `yevis-metadata.yml:12:7`, in the style of compiler messages
+    pub fn location_of(&self, diagnostic: &Diagnostic) -> String {
+        match (diagnostic.line, diagnostic.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", self.location, line, column),
+            (Some(line), None) => format!("{}:{}", self.location, line),
+            _ => self.location.clone(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl ReportFormat {
+    pub const VARIANTS: &'static [&'static str] = &["text", "json", "sarif"];
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "sarif" => Ok(ReportFormat::Sarif),
+            _ => bail!("Unsupported report format: {}", s),
+        }
+    }
+}
+
+pub fn to_json(reports: &[FileReport], skipped_checks: &[&str]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&json!({
+        "files": reports,
+        "skipped_checks": skipped_checks,
+    }))?)
+}
+
+/// https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+/// The output can be uploaded to GitHub code scanning (github/codeql-action/upload-sarif).
+pub fn to_sarif(reports: &[FileReport], skipped_checks: &[&str]) -> Result<String> {
+    let mut rule_ids: Vec<String> = vec![];
+    let mut results: Vec<Value> = vec![];
+    for report in reports {
+        for diagnostic in &report.diagnostics {
+            let rule_id = diagnostic.rule_id();
+            if !rule_ids.contains(&rule_id) {
+                rule_ids.push(rule_id.clone());
+            }
+            let mut region = json!({ "startLine": diagnostic.line.unwrap_or(1) });
+            if let Some(column) = diagnostic.column {
+                region["startColumn"] = json!(column);
+            }
+            results.push(json!({
+                "ruleId": rule_id,
+                "level": "error",
+                "message": { "text": diagnostic.to_string() },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": report.location.trim_start_matches("./") },
+                        "region": region,
+                    },
+                    "logicalLocations": [{
+                        "fullyQualifiedName": diagnostic.path,
+                        "kind": "member",
+                    }],
+                }],
+            }));
+        }
+    }
+    let rules = rule_ids
+        .iter()
+        .map(|id| json!({ "id": id, "shortDescription": { "text": format!("Yevis metadata check: {}", id) } }))
+        .collect::<Vec<_>>();
+    let notifications = skipped_checks
+        .iter()
+        .map(|c| json!({ "level": "note", "message": { "text": format!("Skipped: {}", c) } }))
+        .collect::<Vec<_>>();
+    Ok(serde_json::to_string_pretty(&json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/sapporo-wes/yevis-cli",
+                    "rules": rules,
+                }
+            },
+            "invocations": [{
+                "executionSuccessful": true,
+                "toolExecutionNotifications": notifications,
+            }],
+            "results": results,
+        }],
+    }))?)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_id() -> Result<()> {
+        let diagnostic = Diagnostic::new("workflow.testing[1].files[0].url", "Invalid URL");
+        assert_eq!(diagnostic.rule_id(), "workflow.testing[].files[].url");
+        let diagnostic = Diagnostic::new("", "Failed to read");
+        assert_eq!(diagnostic.rule_id(), "document");
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_sarif() -> Result<()> {
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.error("authors[0].orcid", "`authors[].orcid` is not valid");
+        diagnostics.error("authors[1].orcid", "`authors[].orcid` is not valid");
+        let reports = vec![FileReport {
+            location: "./yevis-metadata.yml".to_string(),
+            diagnostics: diagnostics.0,
+        }];
+        let sarif: Value = serde_json::from_str(&to_sarif(&reports, &[])?)?;
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 1);
+        assert_eq!(run["results"].as_array().unwrap().len(), 2);
+        assert_eq!(run["results"][0]["level"], "error");
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "yevis-metadata.yml"
+        );
+        Ok(())
+    }
+}
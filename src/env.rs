@@ -36,6 +36,18 @@ pub fn github_token(arg_token: &Option<impl AsRef<str>>) -> Result<String> {
     }
 }
 
+/// Optional, only needed for private GitLab projects.
+pub fn gitlab_token() -> Option<String> {
+    dotenv().ok();
+    env::var("GITLAB_TOKEN").ok()
+}
+
+/// Optional, only needed for private Bitbucket repositories.
+pub fn bitbucket_token() -> Option<String> {
+    dotenv().ok();
+    env::var("BITBUCKET_TOKEN").ok()
+}
+
 pub fn sapporo_run_dir() -> Result<String> {
     dotenv().ok();
     match env::var("SAPPORO_RUN_DIR") {
@@ -51,6 +63,72 @@ pub fn sapporo_run_dir() -> Result<String> {
     }
 }
 
+/// Image of sapporo-service with a tag or digest, e.g., `ghcr.io/sapporo-wes/sapporo-service:1.4.0`.
+pub fn sapporo_image() -> Option<String> {
+    dotenv().ok();
+    env::var("SAPPORO_IMAGE").ok()
+}
+
+/// Port of sapporo-service published on the host.
+pub fn sapporo_port() -> Result<Option<u16>> {
+    dotenv().ok();
+    match env::var("SAPPORO_PORT") {
+        Ok(port) => Ok(Some(port.parse().map_err(|_| {
+            anyhow!(
+                "Invalid port in the environment variable `SAPPORO_PORT`: {}",
+                port
+            )
+        })?)),
+        Err(_) => Ok(None),
+    }
+}
+
+pub fn sapporo_container_name() -> Option<String> {
+    dotenv().ok();
+    env::var("SAPPORO_CONTAINER_NAME").ok()
+}
+
+/// Extra arguments of the `sapporo` command, separated by whitespace.
+pub fn sapporo_extra_args() -> Vec<String> {
+    dotenv().ok();
+    match env::var("SAPPORO_EXTRA_ARGS") {
+        Ok(args) => args.split_whitespace().map(|arg| arg.to_string()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Use the sapporo-service image in the local cache without pulling it.
+/// `true` or `1` to enable, and `false`, `0` or empty to disable.
+pub fn sapporo_offline() -> Result<bool> {
+    dotenv().ok();
+    match env::var("SAPPORO_OFFLINE") {
+        Ok(offline) => match offline.to_lowercase().as_str() {
+            "true" | "1" => Ok(true),
+            "false" | "0" | "" => Ok(false),
+            _ => bail!(
+                "Invalid boolean in the environment variable `SAPPORO_OFFLINE`: {}",
+                offline
+            ),
+        },
+        Err(_) => Ok(false),
+    }
+}
+
+pub fn local_run_dir() -> Result<String> {
+    dotenv().ok();
+    match env::var("YEVIS_LOCAL_RUN_DIR") {
+        Ok(run_dir) => Ok(run_dir),
+        Err(_) => {
+            let cwd = env::current_dir()?;
+            Ok(cwd
+                .join("local-run")
+                .to_str()
+                .ok_or_else(|| anyhow!("Could not convert current directory to string."))?
+                .to_string())
+        }
+    }
+}
+
 pub fn in_ci() -> bool {
     dotenv().ok();
     env::var("CI").is_ok()
@@ -245,6 +245,36 @@ pub fn get_file_list_recursive(
     }
 }
 
+/// https://docs.github.com/en/rest/git/trees#get-a-tree
+///
+/// return: paths of all files in the repository
+pub fn get_tree_recursive(
+    gh_token: impl AsRef<str>,
+    owner: impl AsRef<str>,
+    name: impl AsRef<str>,
+    commit: impl AsRef<str>,
+) -> Result<Vec<PathBuf>> {
+    let url = Url::parse(&format!(
+        "https://api.github.com/repos/{}/{}/git/trees/{}",
+        owner.as_ref(),
+        name.as_ref(),
+        commit.as_ref()
+    ))?;
+    let res = gh::get_request(gh_token, &url, &[("recursive", "1")])?;
+    let err_message = "Failed to parse the response to get the tree.";
+    let tree = res
+        .get("tree")
+        .ok_or_else(|| anyhow!(err_message))?
+        .as_array()
+        .ok_or_else(|| anyhow!(err_message))?;
+    Ok(tree
+        .iter()
+        .filter(|entry| entry.get("type").and_then(|t| t.as_str()) == Some("blob"))
+        .filter_map(|entry| entry.get("path").and_then(|p| p.as_str()))
+        .map(PathBuf::from)
+        .collect())
+}
+
 pub fn exists_branch(
     gh_token: impl AsRef<str>,
     owner: impl AsRef<str>,
@@ -0,0 +1,166 @@
+use crate::gitlab;
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// `project` is the full path of the project, e.g., `group/subgroup/project`.
+/// It is used as the URL-encoded project ID.
+fn project_api_url(host: impl AsRef<str>, project: impl AsRef<str>) -> String {
+    format!(
+        "https://{}/api/v4/projects/{}",
+        host.as_ref(),
+        project.as_ref().replace('/', "%2F")
+    )
+}
+
+/// https://docs.gitlab.com/ee/api/projects.html#get-single-project
+pub fn get_project(host: impl AsRef<str>, project: impl AsRef<str>) -> Result<Value> {
+    let url = Url::parse(&project_api_url(host, project))?;
+    gitlab::get_request(&url, &[])
+}
+
+pub fn get_default_branch(
+    host: impl AsRef<str>,
+    project: impl AsRef<str>,
+    memo: Option<&mut HashMap<String, String>>,
+) -> Result<String> {
+    let key = format!("{}/{}", host.as_ref(), project.as_ref());
+
+    if let Some(ref memo) = memo {
+        if let Some(default_branch) = memo.get(&key) {
+            return Ok(default_branch.to_string());
+        }
+    }
+
+    let res = get_project(&host, &project)?;
+    let err_message = "Failed to parse the response to get the default branch";
+    let default_branch = res
+        .get("default_branch")
+        .ok_or_else(|| anyhow!(err_message))?
+        .as_str()
+        .ok_or_else(|| anyhow!(err_message))?
+        .to_string();
+    if let Some(memo) = memo {
+        memo.insert(key, default_branch.clone());
+    }
+
+    Ok(default_branch)
+}
+
+/// https://docs.gitlab.com/ee/api/branches.html#get-single-repository-branch
+pub fn get_branch(
+    host: impl AsRef<str>,
+    project: impl AsRef<str>,
+    branch_name: impl AsRef<str>,
+) -> Result<Value> {
+    let url = Url::parse(&format!(
+        "{}/repository/branches/{}",
+        project_api_url(host, project),
+        branch_name.as_ref().replace('/', "%2F")
+    ))?;
+    gitlab::get_request(&url, &[])
+}
+
+pub fn get_latest_commit_sha(
+    host: impl AsRef<str>,
+    project: impl AsRef<str>,
+    branch_name: impl AsRef<str>,
+    memo: Option<&mut HashMap<String, String>>,
+) -> Result<String> {
+    let key = format!(
+        "{}/{}/{}",
+        host.as_ref(),
+        project.as_ref(),
+        branch_name.as_ref()
+    );
+
+    if let Some(ref memo) = memo {
+        if let Some(latest_commit_hash) = memo.get(&key) {
+            return Ok(latest_commit_hash.to_string());
+        }
+    }
+
+    let res = get_branch(&host, &project, &branch_name)?;
+    let err_message = "Failed to parse the response to get a latest commit sha";
+    let latest_commit_hash = res
+        .get("commit")
+        .ok_or_else(|| anyhow!(err_message))?
+        .get("id")
+        .ok_or_else(|| anyhow!(err_message))?
+        .as_str()
+        .ok_or_else(|| anyhow!(err_message))?
+        .to_string();
+    if let Some(memo) = memo {
+        memo.insert(key, latest_commit_hash.clone());
+    }
+
+    Ok(latest_commit_hash)
+}
+
+/// Return: the web URL of the README in the default branch, if any.
+pub fn get_readme_url(host: impl AsRef<str>, project: impl AsRef<str>) -> Result<Option<Url>> {
+    let res = get_project(host, project)?;
+    match res.get("readme_url").and_then(|v| v.as_str()) {
+        Some(readme_url) => Ok(Some(Url::parse(readme_url)?)),
+        None => Ok(None),
+    }
+}
+
+/// https://docs.gitlab.com/ee/api/repositories.html#list-repository-tree
+///
+/// if called - path: src
+/// return: src/main.rs, src/lib.rs, src/test.rs
+pub fn get_file_list_recursive(
+    host: impl AsRef<str>,
+    project: impl AsRef<str>,
+    path: impl AsRef<Path>,
+    commit: impl AsRef<str>,
+) -> Result<Vec<PathBuf>> {
+    let url = Url::parse(&format!(
+        "{}/repository/tree",
+        project_api_url(host, project)
+    ))?;
+    let path = path.as_ref().to_string_lossy();
+    let per_page = 100;
+    let err_message = "Failed to parse the response to get the file list.";
+    let mut file_list: Vec<PathBuf> = Vec::new();
+    for page in 1.. {
+        let res = gitlab::get_request(
+            &url,
+            &[
+                ("path", &path),
+                ("ref", commit.as_ref()),
+                ("recursive", "true"),
+                ("per_page", &per_page.to_string()),
+                ("page", &page.to_string()),
+            ],
+        )?;
+        let entries = match res.as_array() {
+            Some(entries) => entries,
+            None => bail!(err_message),
+        };
+        for entry in entries {
+            let r#type = entry
+                .get("type")
+                .ok_or_else(|| anyhow!(err_message))?
+                .as_str()
+                .ok_or_else(|| anyhow!(err_message))?;
+            if r#type == "blob" {
+                file_list.push(PathBuf::from(
+                    entry
+                        .get("path")
+                        .ok_or_else(|| anyhow!(err_message))?
+                        .as_str()
+                        .ok_or_else(|| anyhow!(err_message))?,
+                ));
+            }
+        }
+        if entries.len() < per_page {
+            break;
+        }
+    }
+    Ok(file_list)
+}
@@ -1,3 +1,6 @@
+pub mod dependency;
+pub mod testing;
+
 use crate::metadata;
 use crate::metadata::types::LanguageType;
 use crate::remote;
@@ -11,12 +14,16 @@ use url::Url;
 
 pub fn inspect_wf_type_version(wf_loc: &Url) -> Result<metadata::types::Language> {
     let wf_content = remote::fetch_raw_content(wf_loc)?;
+    Ok(inspect_wf_content(&wf_content))
+}
+
+pub fn inspect_wf_content(wf_content: impl AsRef<str>) -> metadata::types::Language {
     let wf_type = inspect_wf_type(&wf_content);
     let wf_version = inspect_wf_version(&wf_content, &wf_type);
-    Ok(metadata::types::Language {
+    metadata::types::Language {
         r#type: wf_type,
         version: wf_version,
-    })
+    }
 }
 
 pub fn inspect_wf_type(wf_content: impl AsRef<str>) -> LanguageType {
@@ -0,0 +1,435 @@
+use crate::metadata::types::LanguageType;
+
+use anyhow::Result;
+use colored::Colorize;
+use log::{debug, warn};
+use regex::Regex;
+use std::collections::{BTreeSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+
+/// A file referenced from a workflow document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reference {
+    /// Path as written in the document, e.g., `../tools/fastqc.cwl`
+    pub path: String,
+    /// Resolve the path from the directory of the primary workflow instead of the referencing file,
+    /// e.g., `${projectDir}` in Nextflow and `configfile:` in Snakemake.
+    pub from_primary: bool,
+    /// The referenced file is also a workflow document, so its references are followed.
+    pub follow: bool,
+}
+
+impl Reference {
+    fn new(path: impl AsRef<str>, from_primary: bool, follow: bool) -> Self {
+        Self {
+            path: path.as_ref().to_string(),
+            from_primary,
+            follow,
+        }
+    }
+}
+
+/// Extract the files referenced from a workflow document.
+///
+/// - CWL: `run:`, `$import` and `$include`
+/// - WDL: `import`
+/// - NFL: `include { ... } from`
+/// - SMK: `include:`, `conda:`, `script:`, `notebook:` and `configfile:`
+pub fn extract_references(wf_content: impl AsRef<str>, wf_type: &LanguageType) -> Vec<Reference> {
+    let references = match wf_type {
+        LanguageType::Cwl => extract_cwl_references(wf_content),
+        LanguageType::Wdl => extract_wdl_references(wf_content),
+        LanguageType::Nfl => extract_nfl_references(wf_content),
+        LanguageType::Smk => extract_smk_references(wf_content),
+        LanguageType::Unknown => vec![],
+    };
+    references
+        .into_iter()
+        .filter(|r| !r.path.is_empty() && !r.path.contains("://"))
+        .collect()
+}
+
+fn extract_cwl_references(wf_content: impl AsRef<str>) -> Vec<Reference> {
+    fn walk(value: &serde_yaml::Value, references: &mut Vec<Reference>) {
+        match value {
+            serde_yaml::Value::Mapping(mapping) => {
+                for (key, value) in mapping {
+                    if let (Some(key), Some(path)) = (key.as_str(), value.as_str()) {
+                        // `tool.cwl#main` -> `tool.cwl`, `#main` refers to the same document
+                        let path = path.split('#').next().unwrap_or_default();
+                        match key {
+                            "run" | "$import" => references.push(Reference::new(path, false, true)),
+                            "$include" => references.push(Reference::new(path, false, false)),
+                            _ => {}
+                        }
+                    }
+                    walk(value, references);
+                }
+            }
+            serde_yaml::Value::Sequence(sequence) => {
+                for value in sequence {
+                    walk(value, references);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut references = vec![];
+    match serde_yaml::from_str::<serde_yaml::Value>(wf_content.as_ref()) {
+        Ok(value) => walk(&value, &mut references),
+        Err(e) => warn!(
+            "{}: Failed to parse the CWL document to find dependencies: {}",
+            "Warning".yellow(),
+            e
+        ),
+    }
+    references
+}
+
+fn extract_wdl_references(wf_content: impl AsRef<str>) -> Vec<Reference> {
+    let re = Regex::new(r#"(?m)^\s*import\s+["']([^"']+)["']"#).unwrap();
+    re.captures_iter(wf_content.as_ref())
+        .map(|caps| Reference::new(&caps[1], false, true))
+        .collect()
+}
+
+fn extract_nfl_references(wf_content: impl AsRef<str>) -> Vec<Reference> {
+    let re = Regex::new(r#"include\s*\{[^}]*\}\s*from\s*["']([^"']+)["']"#).unwrap();
+    let project_dir_re = Regex::new(r"^\$\{?(projectDir|baseDir)\}?/").unwrap();
+    let module_dir_re = Regex::new(r"^\$\{?moduleDir\}?/").unwrap();
+    re.captures_iter(wf_content.as_ref())
+        .filter_map(|caps| {
+            let path = &caps[1];
+            if project_dir_re.is_match(path) {
+                Some(Reference::new(project_dir_re.replace(path, ""), true, true))
+            } else if module_dir_re.is_match(path) {
+                Some(Reference::new(module_dir_re.replace(path, ""), false, true))
+            } else if path.starts_with("./") || path.starts_with("../") {
+                Some(Reference::new(path, false, true))
+            } else {
+                // e.g., `plugin/nf-validation`
+                None
+            }
+        })
+        .collect()
+}
+
+fn extract_smk_references(wf_content: impl AsRef<str>) -> Vec<Reference> {
+    let re =
+        Regex::new(r#"(?m)^\s*(include|conda|script|notebook|configfile)\s*:\s*["']([^"']+)["']"#)
+            .unwrap();
+    re.captures_iter(wf_content.as_ref())
+        .filter_map(|caps| {
+            let path = &caps[2];
+            match &caps[1] {
+                "include" => Some(Reference::new(path, false, true)),
+                // `conda:` can also be the name of an existing environment
+                "conda" if !(path.ends_with(".yml") || path.ends_with(".yaml")) => None,
+                // `configfile:` is relative to the working directory
+                "configfile" => Some(Reference::new(path, true, false)),
+                _ => Some(Reference::new(path, false, false)),
+            }
+        })
+        .collect()
+}
+
+/// Files that are used by the workflow engine without being referenced.
+fn implicit_files(wf_type: &LanguageType) -> &'static [&'static str] {
+    match wf_type {
+        LanguageType::Nfl => &["nextflow.config"],
+        _ => &[],
+    }
+}
+
+/// Candidate paths of a reference, e.g., Nextflow modules can omit `.nf` or refer to a directory.
+fn candidates(path: &Path, wf_type: &LanguageType) -> Vec<PathBuf> {
+    match wf_type {
+        LanguageType::Nfl if path.extension().is_none_or(|ext| ext != "nf") => vec![
+            PathBuf::from(format!("{}.nf", path.display())),
+            path.join("main.nf"),
+        ],
+        _ => vec![path.to_path_buf()],
+    }
+}
+
+/// `a/b/../c/./d` -> `a/c/d`
+/// Return None if the path goes outside the repository root.
+pub fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => normalized.push(c),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
+/// Collect the primary workflow and the files it depends on, following references recursively.
+///
+/// - `primary`: path of the primary workflow relative to the repository root
+/// - `read`: read a file by the path relative to the repository root
+///
+/// Return: sorted paths relative to the repository root
+pub fn discover(
+    primary: &Path,
+    wf_type: &LanguageType,
+    read: impl Fn(&Path) -> Result<String>,
+) -> Vec<PathBuf> {
+    let primary_dir = primary.parent().unwrap_or_else(|| Path::new(""));
+    let mut files: BTreeSet<PathBuf> = BTreeSet::new();
+    files.insert(primary.to_path_buf());
+    for implicit_file in implicit_files(wf_type) {
+        let path = primary_dir.join(implicit_file);
+        if read(&path).is_ok() {
+            files.insert(path);
+        }
+    }
+
+    let mut queue: VecDeque<(PathBuf, String)> = VecDeque::new();
+    match read(primary) {
+        Ok(content) => queue.push_back((primary.to_path_buf(), content)),
+        Err(e) => warn!(
+            "{}: Failed to read {} to find dependencies: {}",
+            "Warning".yellow(),
+            primary.display(),
+            e
+        ),
+    }
+    while let Some((file, content)) = queue.pop_front() {
+        let file_dir = file.parent().unwrap_or_else(|| Path::new(""));
+        for reference in extract_references(&content, wf_type) {
+            let base = if reference.from_primary {
+                primary_dir
+            } else {
+                file_dir
+            };
+            let path = match normalize(&base.join(&reference.path)) {
+                Some(path) => path,
+                None => {
+                    warn!(
+                        "{}: {} referenced from {} is outside the repository, skipped",
+                        "Warning".yellow(),
+                        reference.path,
+                        file.display()
+                    );
+                    continue;
+                }
+            };
+            if !reference.follow {
+                files.insert(path);
+                continue;
+            }
+            let found = candidates(&path, wf_type)
+                .into_iter()
+                .filter(|candidate| !files.contains(candidate))
+                .find_map(|candidate| read(&candidate).ok().map(|c| (candidate, c)));
+            match found {
+                Some((candidate, content)) => {
+                    debug!("{} depends on {}", file.display(), candidate.display());
+                    files.insert(candidate.clone());
+                    queue.push_back((candidate, content));
+                }
+                None => {
+                    if !candidates(&path, wf_type).iter().any(|c| files.contains(c)) {
+                        warn!(
+                            "{}: {} referenced from {} is not found, skipped",
+                            "Warning".yellow(),
+                            reference.path,
+                            file.display()
+                        );
+                    }
+                }
+            }
+        }
+    }
+    files.into_iter().collect()
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::collections::HashMap;
+
+    fn reader(files: &[(&str, &str)]) -> impl Fn(&Path) -> Result<String> {
+        let files: HashMap<PathBuf, String> = files
+            .iter()
+            .map(|(path, content)| (PathBuf::from(path), content.to_string()))
+            .collect();
+        move |path| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| anyhow!("No such file: {}", path.display()))
+        }
+    }
+
+    #[test]
+    fn test_extract_cwl_references() -> Result<()> {
+        let content = r##"
+cwlVersion: v1.2
+class: Workflow
+requirements:
+  SchemaDefRequirement:
+    types:
+      - $import: types.yml
+steps:
+  qc:
+    run: ../tools/fastqc.cwl
+  trim:
+    run: tools.cwl#trimmomatic
+  inline:
+    run:
+      class: CommandLineTool
+      arguments:
+        - valueFrom:
+            $include: script.js
+  packed:
+    run: "#main"
+"##;
+        let paths = extract_references(content, &LanguageType::Cwl)
+            .into_iter()
+            .map(|r| (r.path, r.follow))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                ("types.yml".to_string(), true),
+                ("../tools/fastqc.cwl".to_string(), true),
+                ("tools.cwl".to_string(), true),
+                ("script.js".to_string(), false),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_wdl_references() -> Result<()> {
+        let content = r#"version 1.0
+import "tasks/fastqc.wdl" as fastqc
+import "https://example.com/remote.wdl"
+  import 'tasks/trim.wdl'
+"#;
+        let paths = extract_references(content, &LanguageType::Wdl)
+            .into_iter()
+            .map(|r| r.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["tasks/fastqc.wdl", "tasks/trim.wdl"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_nfl_references() -> Result<()> {
+        let content = r#"
+include { FASTQC } from './modules/fastqc'
+include {
+    TRIM;
+    TRIM as TRIM2
+} from "${projectDir}/modules/trim.nf"
+include { validateParameters } from 'plugin/nf-validation'
+"#;
+        let references = extract_references(content, &LanguageType::Nfl);
+        assert_eq!(
+            references,
+            vec![
+                Reference::new("./modules/fastqc", false, true),
+                Reference::new("modules/trim.nf", true, true),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_smk_references() -> Result<()> {
+        let content = r#"
+configfile: "config/config.yaml"
+include: "rules/qc.smk"
+
+rule fastqc:
+    conda:
+        "envs/fastqc.yaml"
+    script:
+        "scripts/fastqc.py"
+
+rule other:
+    conda: "existing-env"
+"#;
+        let references = extract_references(content, &LanguageType::Smk);
+        assert_eq!(
+            references,
+            vec![
+                Reference::new("config/config.yaml", true, false),
+                Reference::new("rules/qc.smk", false, true),
+                Reference::new("envs/fastqc.yaml", false, false),
+                Reference::new("scripts/fastqc.py", false, false),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_cwl() -> Result<()> {
+        let read = reader(&[
+            ("wf/main.cwl", "steps:\n  a:\n    run: ../tools/a.cwl\n"),
+            (
+                "tools/a.cwl",
+                "inputs:\n  x:\n    type:\n      $import: ../types/x.yml\n",
+            ),
+            ("types/x.yml", "name: x\n"),
+            ("wf/unrelated.cwl", "class: Workflow\n"),
+        ]);
+        let files = discover(Path::new("wf/main.cwl"), &LanguageType::Cwl, read);
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("tools/a.cwl"),
+                PathBuf::from("types/x.yml"),
+                PathBuf::from("wf/main.cwl"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_nfl() -> Result<()> {
+        let read = reader(&[
+            (
+                "main.nf",
+                "include { A } from './modules/a'\ninclude { B } from './modules/b'\n",
+            ),
+            ("nextflow.config", ""),
+            ("modules/a.nf", "include { C } from '../lib/c.nf'\n"),
+            ("modules/b/main.nf", ""),
+            ("lib/c.nf", ""),
+        ]);
+        let files = discover(Path::new("main.nf"), &LanguageType::Nfl, read);
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("lib/c.nf"),
+                PathBuf::from("main.nf"),
+                PathBuf::from("modules/a.nf"),
+                PathBuf::from("modules/b/main.nf"),
+                PathBuf::from("nextflow.config"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_outside_repository() -> Result<()> {
+        let read = reader(&[("main.wdl", "import \"../../outside.wdl\"\n")]);
+        let files = discover(Path::new("main.wdl"), &LanguageType::Wdl, read);
+        assert_eq!(files, vec![PathBuf::from("main.wdl")]);
+        Ok(())
+    }
+}
@@ -0,0 +1,475 @@
+use crate::inspect::dependency::normalize;
+use crate::metadata::types::LanguageType;
+
+use colored::Colorize;
+use log::{debug, warn};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A test case found in the repository.
+/// All paths are relative to the repository root.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TestCase {
+    pub wf_params: Option<PathBuf>,
+    pub wf_engine_params: Option<PathBuf>,
+    /// (path, target): input files referenced from the test parameters.
+    /// `target` is the path as written in the test parameters.
+    pub others: Vec<(PathBuf, PathBuf)>,
+}
+
+const WF_ENGINE_PARAMS_FILE: &str = "wf_engine_params.json";
+
+/// Directories to look for test files, relative to the directory of the primary workflow.
+const TEST_DIRS: &[&str] = &[
+    "", "test", "tests", ".test", "../test", "../tests", "../.test",
+];
+
+/// Whether the file is a test parameters file for the workflow language.
+///
+/// - All: `wf_params.{json,yml,yaml}` (the layout used by Yevis)
+/// - CWL: `job.{yml,yaml,json}`, `*-job.*`, `*_job.*`, `*.job.*`
+/// - WDL: `inputs.json`, `*.inputs.json`, `*_inputs.json`, `*-inputs.json`
+/// - SMK: `.test/config/config.{yml,yaml}`, `.test/config.{yml,yaml}`
+fn is_params_file(path: &Path, wf_type: &LanguageType) -> bool {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return false,
+    };
+    let wf_params_re = Regex::new(r"^wf_params\.(json|ya?ml)$").unwrap();
+    if wf_params_re.is_match(&name) {
+        return true;
+    }
+    match wf_type {
+        LanguageType::Cwl => Regex::new(r"^(.+[-_.])?job\.(json|ya?ml)$")
+            .unwrap()
+            .is_match(&name),
+        LanguageType::Wdl => Regex::new(r"^(.+[-_.])?inputs\.json$")
+            .unwrap()
+            .is_match(&name),
+        LanguageType::Smk => {
+            Regex::new(r"^config\.ya?ml$").unwrap().is_match(&name)
+                && path
+                    .ancestors()
+                    .any(|a| a.file_name().is_some_and(|n| n == ".test"))
+        }
+        _ => false,
+    }
+}
+
+/// Find test cases next to the primary workflow.
+///
+/// - `primary`: path of the primary workflow relative to the repository root
+/// - `repo_files`: all files in the repository, relative to the repository root
+/// - `read`: read a file by the path relative to the repository root
+///
+/// Each test parameters file becomes a test case, and a `wf_engine_params.json` in the same directory is attached to it.
+/// For Nextflow, the `test` profile in `nextflow.config` also becomes a test case,
+/// if a `wf_engine_params.json` next to it passes `-profile test`.
+/// Local paths in the test parameters that point to files in the repository are collected as input files.
+pub fn discover(
+    primary: &Path,
+    wf_type: &LanguageType,
+    repo_files: &[PathBuf],
+    read: impl Fn(&Path) -> anyhow::Result<String>,
+) -> Vec<TestCase> {
+    let primary_dir = primary.parent().unwrap_or_else(|| Path::new(""));
+    let test_dirs = TEST_DIRS
+        .iter()
+        .filter_map(|dir| normalize(&primary_dir.join(dir)))
+        .collect::<Vec<_>>();
+
+    let mut params_files = repo_files
+        .iter()
+        .filter(|file| {
+            test_dirs.iter().any(|dir| {
+                file.parent() == Some(dir.as_path())
+                    || (*wf_type == LanguageType::Smk
+                        && dir.ends_with(".test")
+                        && file.starts_with(dir))
+            })
+        })
+        .filter(|file| is_params_file(file, wf_type))
+        .cloned()
+        .collect::<Vec<_>>();
+    params_files.sort();
+    params_files.dedup();
+
+    let mut test_cases = vec![];
+    for params_file in params_files {
+        let params_dir = params_file.parent().unwrap_or_else(|| Path::new(""));
+        // Snakemake tests are run in the `.test` directory
+        let base = match wf_type {
+            LanguageType::Smk => params_file
+                .ancestors()
+                .find(|a| a.ends_with(".test"))
+                .unwrap_or(params_dir),
+            _ => params_dir,
+        };
+        let content = match read(&params_file) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!(
+                    "{}: Failed to read {}: {}",
+                    "Warning".yellow(),
+                    params_file.display(),
+                    e
+                );
+                continue;
+            }
+        };
+        let mut paths = vec![];
+        match serde_yaml::from_str::<serde_yaml::Value>(&content) {
+            Ok(value) => collect_strings(&value, &mut paths),
+            Err(e) => debug!("Failed to parse {}: {}", params_file.display(), e),
+        }
+        let wf_engine_params = params_dir.join(WF_ENGINE_PARAMS_FILE);
+        debug!("Found test parameters {}", params_file.display());
+        test_cases.push(TestCase {
+            wf_engine_params: repo_files
+                .contains(&wf_engine_params)
+                .then_some(wf_engine_params),
+            others: resolve_inputs(&paths, base, &params_file, repo_files),
+            wf_params: Some(params_file),
+        });
+    }
+
+    if *wf_type == LanguageType::Nfl {
+        let config = primary_dir.join("nextflow.config");
+        if let Ok(content) = read(&config) {
+            if let Some(profile) = nfl_test_profile(&content) {
+                debug!("Found the test profile in {}", config.display());
+                let paths = Regex::new(r#"["']([^"']+)["']"#)
+                    .unwrap()
+                    .captures_iter(&profile)
+                    .map(|caps| {
+                        Regex::new(r"^\$\{?(projectDir|baseDir)\}?/")
+                            .unwrap()
+                            .replace(&caps[1], "")
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>();
+                let wf_engine_params = primary_dir.join(WF_ENGINE_PARAMS_FILE);
+                match repo_files.contains(&wf_engine_params)
+                    && read(&wf_engine_params).is_ok_and(selects_nfl_test_profile)
+                {
+                    true => test_cases.push(TestCase {
+                        wf_params: None,
+                        wf_engine_params: Some(wf_engine_params),
+                        others: resolve_inputs(&paths, primary_dir, &config, repo_files),
+                    }),
+                    false => warn!(
+                        "{}: Skipped the test profile in {}, add {} with `{{\"-profile\": \"test\"}}` to use it as a test case",
+                        "Warning".yellow(),
+                        config.display(),
+                        wf_engine_params.display()
+                    ),
+                }
+            }
+        }
+    }
+
+    test_cases
+}
+
+fn collect_strings(value: &serde_yaml::Value, strings: &mut Vec<String>) {
+    match value {
+        serde_yaml::Value::String(s) => strings.push(s.to_string()),
+        serde_yaml::Value::Sequence(sequence) => {
+            for value in sequence {
+                collect_strings(value, strings);
+            }
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            for (_, value) in mapping {
+                collect_strings(value, strings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolve the strings that are local paths of files (or directories) in the repository.
+/// Return: (path, target)
+fn resolve_inputs(
+    strings: &[String],
+    base: &Path,
+    referenced_from: &Path,
+    repo_files: &[PathBuf],
+) -> Vec<(PathBuf, PathBuf)> {
+    let mut inputs: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+    for s in strings {
+        if s.is_empty() || s.contains("://") || s.contains('\n') {
+            continue;
+        }
+        let path = match normalize(&base.join(s)) {
+            Some(path) if path != base => path,
+            _ => continue,
+        };
+        let files = repo_files
+            .iter()
+            .filter(|file| **file == path || file.starts_with(&path))
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            continue;
+        }
+        let target = match normalize(Path::new(s)) {
+            Some(target) if Path::new(s).is_relative() => target,
+            _ => {
+                warn!(
+                    "{}: {} referenced from {} is outside the test directory, please add it to the test files manually",
+                    "Warning".yellow(),
+                    s,
+                    referenced_from.display()
+                );
+                continue;
+            }
+        };
+        for file in files {
+            match file.strip_prefix(&path) {
+                Ok(rest) if rest.as_os_str().is_empty() => {
+                    inputs.insert(file.clone(), target.clone())
+                }
+                Ok(rest) => inputs.insert(file.clone(), target.join(rest)),
+                Err(_) => None,
+            };
+        }
+    }
+    inputs.into_iter().collect()
+}
+
+/// Whether `wf_engine_params` passes `-profile test` (possibly with other profiles, e.g., `test,docker`).
+fn selects_nfl_test_profile(wf_engine_params: impl AsRef<str>) -> bool {
+    serde_json::from_str::<serde_json::Value>(wf_engine_params.as_ref())
+        .ok()
+        .and_then(|params| {
+            params
+                .get("-profile")
+                .and_then(|v| v.as_str())
+                .map(|profiles| profiles.split(',').any(|p| p.trim() == "test"))
+        })
+        .unwrap_or(false)
+}
+
+/// Extract the body of `profiles { test { ... } }` in `nextflow.config`.
+fn nfl_test_profile(content: impl AsRef<str>) -> Option<String> {
+    let content = content.as_ref();
+    let profiles = Regex::new(r"(?m)^\s*profiles\s*\{")
+        .unwrap()
+        .find(content)?;
+    let profiles_body = block_body(&content[profiles.end()..])?;
+    let test = Regex::new(r"(?m)^\s*test\s*\{")
+        .unwrap()
+        .find(profiles_body)?;
+    block_body(&profiles_body[test.end()..]).map(|body| body.to_string())
+}
+
+/// `content` starts just after `{`. Return the content until the matching `}`.
+fn block_body(content: &str) -> Option<&str> {
+    let mut depth = 1;
+    for (i, c) in content.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&content[..i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::collections::HashMap;
+
+    fn repo(files: &[(&str, &str)]) -> (Vec<PathBuf>, impl Fn(&Path) -> anyhow::Result<String>) {
+        let files: HashMap<PathBuf, String> = files
+            .iter()
+            .map(|(path, content)| (PathBuf::from(path), content.to_string()))
+            .collect();
+        let mut repo_files = files.keys().cloned().collect::<Vec<_>>();
+        repo_files.sort();
+        (repo_files, move |path: &Path| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| anyhow!("No such file: {}", path.display()))
+        })
+    }
+
+    fn paths(others: &[(PathBuf, PathBuf)]) -> Vec<(&str, &str)> {
+        others
+            .iter()
+            .map(|(path, target)| (path.to_str().unwrap(), target.to_str().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_discover_cwl() -> anyhow::Result<()> {
+        let (repo_files, read) = repo(&[
+            ("wf/main.cwl", "cwlVersion: v1.2\n"),
+            (
+                "wf/main-job.yml",
+                "fastq:\n  class: File\n  path: data/sample.fq\nref:\n  class: Directory\n  location: ./data/ref\nname: sample\n",
+            ),
+            ("wf/data/sample.fq", ""),
+            ("wf/data/ref/genome.fa", ""),
+            ("wf/data/ref/genome.fa.fai", ""),
+            ("test/wf_params.json", r#"{"input": "../secret.txt"}"#),
+            ("test/wf_engine_params.json", "{}"),
+            ("secret.txt", ""),
+        ]);
+        let test_cases = discover(
+            Path::new("wf/main.cwl"),
+            &LanguageType::Cwl,
+            &repo_files,
+            read,
+        );
+        assert_eq!(test_cases.len(), 2);
+        assert_eq!(
+            test_cases[0].wf_params,
+            Some(PathBuf::from("test/wf_params.json"))
+        );
+        assert_eq!(
+            test_cases[0].wf_engine_params,
+            Some(PathBuf::from("test/wf_engine_params.json"))
+        );
+        assert!(test_cases[0].others.is_empty());
+        assert_eq!(
+            test_cases[1].wf_params,
+            Some(PathBuf::from("wf/main-job.yml"))
+        );
+        assert_eq!(test_cases[1].wf_engine_params, None);
+        assert_eq!(
+            paths(&test_cases[1].others),
+            vec![
+                ("wf/data/ref/genome.fa", "data/ref/genome.fa"),
+                ("wf/data/ref/genome.fa.fai", "data/ref/genome.fa.fai"),
+                ("wf/data/sample.fq", "data/sample.fq"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_wdl_and_smk() -> anyhow::Result<()> {
+        let (repo_files, read) = repo(&[
+            ("main.wdl", "version 1.0\n"),
+            ("main.inputs.json", r#"{"wf.bam": "tiny.bam"}"#),
+            ("tiny.bam", ""),
+            ("params.json", "{}"),
+        ]);
+        let test_cases = discover(Path::new("main.wdl"), &LanguageType::Wdl, &repo_files, read);
+        assert_eq!(test_cases.len(), 1);
+        assert_eq!(paths(&test_cases[0].others), vec![("tiny.bam", "tiny.bam")]);
+
+        let (repo_files, read) = repo(&[
+            ("workflow/Snakefile", ""),
+            ("config/config.yaml", "samples: config/samples.tsv\n"),
+            ("config/samples.tsv", ""),
+            (".test/config/config.yaml", "samples: config/samples.tsv\n"),
+            (".test/config/samples.tsv", ""),
+        ]);
+        let test_cases = discover(
+            Path::new("workflow/Snakefile"),
+            &LanguageType::Smk,
+            &repo_files,
+            read,
+        );
+        assert_eq!(test_cases.len(), 1);
+        assert_eq!(
+            test_cases[0].wf_params,
+            Some(PathBuf::from(".test/config/config.yaml"))
+        );
+        assert_eq!(
+            paths(&test_cases[0].others),
+            vec![(".test/config/samples.tsv", "config/samples.tsv")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_nfl_test_profile() -> anyhow::Result<()> {
+        let (repo_files, read) = repo(&[
+            ("main.nf", ""),
+            (
+                "nextflow.config",
+                r#"
+params {
+  input = null
+}
+profiles {
+  docker {
+    docker.enabled = true
+  }
+  test {
+    params {
+      input = "${projectDir}/assets/samplesheet.csv"
+      genome = 'assets/genome.fa'
+      outdir = "results"
+    }
+  }
+}
+"#,
+            ),
+            ("assets/samplesheet.csv", ""),
+            ("assets/genome.fa", ""),
+            ("wf_engine_params.json", r#"{"-profile": "test,docker"}"#),
+        ]);
+        let test_cases = discover(Path::new("main.nf"), &LanguageType::Nfl, &repo_files, read);
+        assert_eq!(test_cases.len(), 1);
+        assert_eq!(test_cases[0].wf_params, None);
+        assert_eq!(
+            test_cases[0].wf_engine_params,
+            Some(PathBuf::from("wf_engine_params.json"))
+        );
+        assert_eq!(
+            paths(&test_cases[0].others),
+            vec![
+                ("assets/genome.fa", "assets/genome.fa"),
+                ("assets/samplesheet.csv", "assets/samplesheet.csv"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_nfl_test_profile_without_engine_params() -> anyhow::Result<()> {
+        let (repo_files, read) = repo(&[
+            ("main.nf", ""),
+            (
+                "nextflow.config",
+                "profiles {\n  test {\n    params.a = 1\n  }\n}\n",
+            ),
+        ]);
+        let test_cases = discover(Path::new("main.nf"), &LanguageType::Nfl, &repo_files, read);
+        assert!(test_cases.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_selects_nfl_test_profile() -> anyhow::Result<()> {
+        assert!(selects_nfl_test_profile(r#"{"-profile": "test"}"#));
+        assert!(selects_nfl_test_profile(r#"{"-profile": "docker, test"}"#));
+        assert!(!selects_nfl_test_profile(r#"{"-profile": "testing"}"#));
+        assert!(!selects_nfl_test_profile("{}"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_nfl_test_profile() -> anyhow::Result<()> {
+        assert_eq!(
+            nfl_test_profile("profiles {\n  test {\n    params.a = 1\n  }\n}\n"),
+            Some("\n    params.a = 1\n  ".to_string())
+        );
+        assert_eq!(nfl_test_profile("params {\n  a = 1\n}\n"), None);
+        Ok(())
+    }
+}
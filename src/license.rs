@@ -0,0 +1,181 @@
+use crate::gh;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+use url::Url;
+
+/// SPDX license list bundled at build time (https://github.com/spdx/license-list-data).
+const BUNDLED_SPDX_LICENSES: &str = include_str!("resources/spdx-licenses.json");
+
+pub enum LicenseRegistry {
+    /// Resolve licenses via GitHub license API and Zenodo license API.
+    Remote { gh_token: String },
+    /// Resolve licenses via the bundled SPDX license list (and an optional overlay).
+    /// key: lowercase license id, value: SPDX id
+    Local(HashMap<String, String>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LicenseList {
+    licenses: Vec<LicenseListEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LicenseListEntry {
+    license_id: String,
+}
+
+impl LicenseRegistry {
+    pub fn new_remote(gh_token: impl AsRef<str>) -> Self {
+        Self::Remote {
+            gh_token: gh_token.as_ref().to_string(),
+        }
+    }
+
+    /// The overlay file has the same format as SPDX `licenses.json`,
+    /// so it can be used to add custom licenses or to replace the bundled list with a newer one.
+    pub fn new_local(overlay: &Option<impl AsRef<Path>>) -> Result<Self> {
+        let bundled: LicenseList = serde_json::from_str(BUNDLED_SPDX_LICENSES)
+            .context("Failed to parse the bundled SPDX license list")?;
+        let mut licenses = bundled.licenses;
+        if let Some(overlay) = overlay {
+            let reader = BufReader::new(fs::File::open(overlay.as_ref()).with_context(|| {
+                format!(
+                    "Failed to open the license list: {}",
+                    overlay.as_ref().display()
+                )
+            })?);
+            // Even json can be read with yaml reader
+            let overlay: LicenseList = serde_yaml::from_reader(reader).context(
+                "Failed to parse the license list, expected SPDX `licenses.json` format",
+            )?;
+            licenses.extend(overlay.licenses);
+        }
+        Ok(Self::Local(
+            licenses
+                .into_iter()
+                .map(|l| (l.license_id.to_lowercase(), l.license_id))
+                .collect(),
+        ))
+    }
+
+    /// Return the `spdx_id` of the license.
+    /// e.g., `apache-2.0` -> `Apache-2.0`
+    pub fn resolve(&self, license: impl AsRef<str>) -> Result<String> {
+        match self {
+            Self::Remote { gh_token } => {
+                let (key, spdx_id) = validate_with_github_license_api(gh_token, &license)?;
+                validate_with_zenodo_license_api(&key)?;
+                Ok(spdx_id)
+            }
+            Self::Local(licenses) => licenses
+                .get(&license.as_ref().to_lowercase())
+                .cloned()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "`license` {} is not found in the SPDX license list",
+                        license.as_ref()
+                    )
+                }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LicenseResponse {
+    key: String,
+    spdx_id: String,
+}
+
+/// https://docs.github.com/ja/rest/reference/licenses#get-a-license
+/// res: (key, spdx_id), e.g., ("mit", "MIT")
+fn validate_with_github_license_api(
+    gh_token: impl AsRef<str>,
+    license: impl AsRef<str>,
+) -> Result<(String, String)> {
+    let url = Url::parse(&format!(
+        "https://api.github.com/licenses/{}",
+        license.as_ref()
+    ))?;
+    let res = gh::get_request(gh_token, &url, &[])?;
+    let res: LicenseResponse =
+        serde_json::from_value(res).context("Failed to parse GitHub license API response")?;
+    Ok((res.key, res.spdx_id))
+}
+
+/// https://developers.zenodo.org/?shell#retrieve41
+fn validate_with_zenodo_license_api(license: impl AsRef<str>) -> Result<()> {
+    let url = Url::parse(&format!(
+        "https://zenodo.org/api/vocabularies/licenses/{}",
+        license.as_ref()
+    ))?;
+    // Return the path for this URL, as a percent-encoded ASCII string
+    let response = reqwest::blocking::get(url.as_str())?;
+    let status = response.status();
+    ensure!(
+        status.is_success(),
+        "`license` is not valid from Zenodo license API"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use crate::env;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_validate_with_github_license_api() -> Result<()> {
+        let gh_token = env::github_token(&None::<String>)?;
+        validate_with_github_license_api(&gh_token, "cc0-1.0")?;
+        validate_with_github_license_api(&gh_token, "mit")?;
+        validate_with_github_license_api(&gh_token, "MIT")?;
+        validate_with_github_license_api(&gh_token, "apache-2.0")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_with_zenodo_license_api() -> Result<()> {
+        validate_with_zenodo_license_api("cc0-1.0")?;
+        validate_with_zenodo_license_api("mit")?;
+        validate_with_zenodo_license_api("apache-2.0")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_registry_resolve() -> Result<()> {
+        let registry = LicenseRegistry::new_local(&None::<PathBuf>)?;
+        assert_eq!(registry.resolve("cc0-1.0")?, "CC0-1.0");
+        assert_eq!(registry.resolve("MIT")?, "MIT");
+        assert_eq!(registry.resolve("apache-2.0")?, "Apache-2.0");
+        assert!(registry.resolve("LicenseRef-Internal").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_registry_with_overlay() -> Result<()> {
+        let mut overlay = tempfile::NamedTempFile::new()?;
+        overlay.write_all(
+            br#"
+licenses:
+  - licenseId: LicenseRef-Internal
+"#,
+        )?;
+        let registry = LicenseRegistry::new_local(&Some(overlay.path()))?;
+        assert_eq!(
+            registry.resolve("licenseref-internal")?,
+            "LicenseRef-Internal"
+        );
+        assert_eq!(registry.resolve("mit")?, "MIT");
+        Ok(())
+    }
+}
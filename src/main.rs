@@ -1,18 +1,32 @@
 mod args;
+mod bitbucket;
+mod citation;
+mod diagnostic;
 mod env;
 mod gh;
+mod gitlab;
 mod inspect;
+mod license;
 mod logger;
 mod metadata;
+mod orcid;
 mod remote;
+mod ro_crate;
+mod runner;
 mod sub_cmd;
+mod test_cache;
+mod test_quarantine;
+mod test_report;
+mod test_state;
 mod trs;
 mod wes;
+mod workflow_ro_crate;
 mod zenodo;
 
 use anyhow::{anyhow, Result};
 use colored::Colorize;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
+use std::path::PathBuf;
 use std::process::exit;
 use structopt::StructOpt;
 
@@ -23,28 +37,72 @@ fn main() -> Result<()> {
     info!("{} yevis", "Start".green());
     debug!("args: {:?}", args);
 
-    let gh_token = env::github_token(&args.gh_token())?;
+    let gh_token = match args {
+        args::Args::Validate { offline: true, .. }
+        | args::Args::Schema { .. }
+        | args::Args::Migrate { .. } => env::github_token(&args.gh_token()).unwrap_or_default(),
+        _ => env::github_token(&args.gh_token())?,
+    };
 
     match args {
         args::Args::MakeTemplate {
             workflow_location,
             output,
             use_commit_url,
+            list_all_files,
             ..
         } => {
-            sub_cmd::make_template(&workflow_location, &gh_token, &output, &use_commit_url);
+            sub_cmd::make_template(
+                &workflow_location,
+                &gh_token,
+                &output,
+                &use_commit_url,
+                list_all_files,
+            );
         }
         args::Args::Validate {
-            metadata_locations, ..
+            metadata_locations,
+            offline,
+            license_list,
+            report_format,
+            report_output,
+            ..
+        } => {
+            sub_cmd::validate(
+                metadata_locations,
+                &gh_token,
+                offline,
+                &license_list,
+                &report_format,
+                &report_output,
+            );
+        }
+        args::Args::Schema { output, .. } => {
+            sub_cmd::schema(&output);
+        }
+        args::Args::Migrate {
+            metadata_locations,
+            dry_run,
+            ..
         } => {
-            sub_cmd::validate(metadata_locations, &gh_token);
+            sub_cmd::migrate(&metadata_locations, dry_run);
         }
         args::Args::Test {
             metadata_locations,
             wes_location,
             docker_host,
+            container_runtime,
             from_pr,
             fetch_ro_crate,
+            runner,
+            follow,
+            resume,
+            state_file,
+            quarantine_file,
+            parallel,
+            timeout,
+            junit_report,
+            json_report,
             ..
         } => {
             let meta_locs = if from_pr {
@@ -70,18 +128,71 @@ fn main() -> Result<()> {
                 metadata_locations
             };
 
-            let meta_vec = sub_cmd::validate(meta_locs, &gh_token);
-            sub_cmd::test(&meta_vec, &wes_location, &docker_host, fetch_ro_crate);
+            let meta_vec = sub_cmd::validate(
+                meta_locs,
+                &gh_token,
+                false,
+                &None::<PathBuf>,
+                &diagnostic::ReportFormat::Text,
+                &None::<PathBuf>,
+            );
+            sub_cmd::test(
+                &meta_vec,
+                &wes_location,
+                &container_runtime,
+                &docker_host,
+                &sub_cmd::test::TestOptions {
+                    fetch_ro_crate,
+                    runner,
+                    follow,
+                    resume,
+                    state_file: Some(state_file),
+                    quarantine_file,
+                    parallel,
+                    timeout,
+                    junit_report,
+                    json_report,
+                    wes_image: None,
+                },
+            );
         }
         args::Args::PullRequest {
             metadata_locations,
             repository,
             wes_location,
             docker_host,
+            container_runtime,
+            parallel,
+            timeout,
             ..
         } => {
-            let meta_vec = sub_cmd::validate(metadata_locations, &gh_token);
-            sub_cmd::test(&meta_vec, &wes_location, &docker_host, true);
+            let meta_vec = sub_cmd::validate(
+                metadata_locations,
+                &gh_token,
+                false,
+                &None::<PathBuf>,
+                &diagnostic::ReportFormat::Text,
+                &None::<PathBuf>,
+            );
+            sub_cmd::test(
+                &meta_vec,
+                &wes_location,
+                &container_runtime,
+                &docker_host,
+                &sub_cmd::test::TestOptions {
+                    fetch_ro_crate: true,
+                    runner: runner::RunnerType::Wes,
+                    follow: false,
+                    resume: false,
+                    state_file: None,
+                    quarantine_file: PathBuf::from(test_quarantine::DEFAULT_QUARANTINE_FILE),
+                    parallel,
+                    timeout,
+                    junit_report: None,
+                    json_report: None,
+                    wes_image: None,
+                },
+            );
             sub_cmd::pull_request(&meta_vec, &gh_token, &repository);
         }
         args::Args::Publish {
@@ -90,6 +201,9 @@ fn main() -> Result<()> {
             with_test,
             wes_location,
             docker_host,
+            container_runtime,
+            parallel,
+            timeout,
             from_pr,
             upload_zenodo,
             zenodo_community,
@@ -123,7 +237,14 @@ fn main() -> Result<()> {
                 metadata_locations
             };
 
-            let mut meta_vec = sub_cmd::validate(meta_locs, &gh_token);
+            let mut meta_vec = sub_cmd::validate(
+                meta_locs,
+                &gh_token,
+                false,
+                &None::<PathBuf>,
+                &diagnostic::ReportFormat::Text,
+                &None::<PathBuf>,
+            );
 
             if upload_zenodo {
                 info!("{} upload_zenodo", "Running".green());
@@ -141,11 +262,50 @@ fn main() -> Result<()> {
                 }
             }
 
-            if with_test {
-                sub_cmd::test(&meta_vec, &wes_location, &docker_host, true);
+            let test_cache = match with_test {
+                true => match gh::parse_repo(&repository)
+                    .and_then(|(owner, name)| test_cache::TestCache::new(&meta_vec, owner, name))
+                {
+                    Ok(test_cache) => test_cache,
+                    Err(e) => {
+                        warn!(
+                            "{}: Failed to check the test cache, so all workflows are tested: {}",
+                            "Warning".yellow(),
+                            e
+                        );
+                        test_cache::TestCache::default()
+                    }
+                },
+                false => test_cache::TestCache::default(),
+            };
+            let untested = meta_vec
+                .iter()
+                .filter(|meta| !test_cache.hit(meta))
+                .cloned()
+                .collect::<Vec<_>>();
+            if with_test && !untested.is_empty() {
+                sub_cmd::test(
+                    &untested,
+                    &wes_location,
+                    &container_runtime,
+                    &docker_host,
+                    &sub_cmd::test::TestOptions {
+                        fetch_ro_crate: true,
+                        runner: runner::RunnerType::Wes,
+                        follow: false,
+                        resume: false,
+                        state_file: None,
+                        quarantine_file: PathBuf::from(test_quarantine::DEFAULT_QUARANTINE_FILE),
+                        parallel,
+                        timeout,
+                        junit_report: None,
+                        json_report: None,
+                        wes_image: None,
+                    },
+                );
             };
 
-            sub_cmd::publish(&meta_vec, &gh_token, &repository, with_test);
+            sub_cmd::publish(&meta_vec, &gh_token, &repository, with_test, &test_cache);
         }
         args::Args::UploadZenodo {
             metadata_location,
@@ -156,7 +316,14 @@ fn main() -> Result<()> {
             zenodo_community,
             ..
         } => {
-            let meta_vec = sub_cmd::validate(vec![metadata_location], &gh_token);
+            let meta_vec = sub_cmd::validate(
+                vec![metadata_location],
+                &gh_token,
+                false,
+                &None::<PathBuf>,
+                &diagnostic::ReportFormat::Text,
+                &None::<PathBuf>,
+            );
             let mut meta_loc = meta_vec
                 .into_iter()
                 .next()
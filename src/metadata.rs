@@ -1,2 +1,4 @@
 pub mod io;
+pub mod migrate;
+pub mod schema;
 pub mod types;
@@ -5,7 +5,6 @@ use anyhow::{bail, Result};
 use serde_json;
 use serde_yaml;
 use std::fs;
-use std::io::BufReader;
 use std::io::{BufWriter, Write};
 use std::path::Path;
 use url::Url;
@@ -33,33 +32,56 @@ pub fn write_local(
     path: impl AsRef<Path>,
     ext: &FileExt,
 ) -> Result<()> {
-    let content = match ext {
-        FileExt::Yaml => serde_yaml::to_string(&meta)?,
-        FileExt::Json => serde_json::to_string_pretty(&meta)?,
-    };
+    let content = to_string(meta, ext)?;
     let mut buffer = BufWriter::new(fs::File::create(path)?);
     buffer.write_all(content.as_bytes())?;
 
     Ok(())
 }
 
+pub fn to_string(meta: &metadata::types::Metadata, ext: &FileExt) -> Result<String> {
+    Ok(match ext {
+        FileExt::Yaml => serde_yaml::to_string(&meta)?,
+        FileExt::Json => serde_json::to_string_pretty(&meta)?,
+    })
+}
+
+/// Older format versions are upgraded in memory (see `metadata::migrate`).
 pub fn read(
     location: impl AsRef<str>,
     gh_token: impl AsRef<str>,
 ) -> Result<metadata::types::Metadata> {
+    let content = read_content(location, gh_token)?;
+    parse(content)
+}
+
+/// Parse the content of the metadata file and upgrade it to the current format version.
+pub fn parse(content: impl AsRef<str>) -> Result<metadata::types::Metadata> {
+    // Even json can be read with yaml reader
+    let mut value: serde_yaml::Value = serde_yaml::from_str(content.as_ref())?;
+    metadata::migrate::migrate(&mut value)?;
+    Ok(serde_yaml::from_value(value)?)
+}
+
+/// Metadata of `tests/test-metadata-CWL-validated.yml`, embedded in the test binary
+/// so that the tests using the metadata neither read nor fetch it.
+#[cfg(test)]
+pub fn test_metadata() -> Result<metadata::types::Metadata> {
+    parse(include_str!("../../tests/test-metadata-CWL-validated.yml"))
+}
+
+/// Read the metadata file as it is (local file path or remote URL).
+pub fn read_content(location: impl AsRef<str>, gh_token: impl AsRef<str>) -> Result<String> {
     match Url::parse(location.as_ref()) {
         Ok(url) => {
             // as remote url
             let remote = remote::Remote::new(&url, &gh_token, None, None)?;
             let url = remote.to_url()?;
-            let content = remote::fetch_json_content(&url)?;
-            // Even json can be read with yaml reader
-            Ok(serde_yaml::from_str(&content)?)
+            remote::fetch_json_content(&url)
         }
         Err(_) => {
             // as local file path
-            let reader = BufReader::new(fs::File::open(location.as_ref())?);
-            Ok(serde_yaml::from_reader(reader)?)
+            Ok(fs::read_to_string(location.as_ref())?)
         }
     }
 }
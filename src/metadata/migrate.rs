@@ -0,0 +1,103 @@
+use anyhow::{anyhow, bail, ensure, Result};
+use serde_yaml::{Mapping, Value};
+
+/// Version of the metadata format written by this version of `yevis`.
+/// When the format changes, bump this and add a migration to `MIGRATIONS`.
+pub const CURRENT_SCHEMA_VERSION: u64 = 2;
+
+/// Documents without `schema_version` were written before the field was introduced.
+const LEGACY_SCHEMA_VERSION: u64 = 1;
+
+type Migration = fn(&mut Mapping) -> Result<()>;
+
+/// (from, migration): each migration upgrades a document from `from` to `from + 1`.
+const MIGRATIONS: &[(u64, Migration)] = &[(1, v1_to_v2)];
+
+/// Return the `schema_version` of the document.
+pub fn schema_version(value: &Value) -> Result<u64> {
+    let mapping = value
+        .as_mapping()
+        .ok_or_else(|| anyhow!("The metadata file is not a mapping"))?;
+    match mapping.get("schema_version") {
+        None => Ok(LEGACY_SCHEMA_VERSION),
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| anyhow!("`schema_version` must be a positive integer")),
+    }
+}
+
+/// Upgrade the document in memory to `CURRENT_SCHEMA_VERSION`.
+/// Return the `schema_version` of the original document.
+pub fn migrate(value: &mut Value) -> Result<u64> {
+    let original_version = schema_version(value)?;
+    ensure!(
+        original_version <= CURRENT_SCHEMA_VERSION,
+        "`schema_version` {} is newer than the version supported by this yevis-cli ({}), please update yevis-cli",
+        original_version,
+        CURRENT_SCHEMA_VERSION
+    );
+    let mapping = value
+        .as_mapping_mut()
+        .ok_or_else(|| anyhow!("The metadata file is not a mapping"))?;
+    for version in original_version..CURRENT_SCHEMA_VERSION {
+        match MIGRATIONS.iter().find(|(from, _)| *from == version) {
+            Some((_, migration)) => migration(mapping)?,
+            None => bail!("No migration from `schema_version` {}", version),
+        }
+        mapping.insert(Value::from("schema_version"), Value::from(version + 1));
+    }
+    Ok(original_version)
+}
+
+/// v2 only introduces `schema_version`.
+fn v1_to_v2(_mapping: &mut Mapping) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_migrate_legacy() -> Result<()> {
+        let mut value: Value = serde_yaml::from_str(&fs::read_to_string(
+            "./tests/test-metadata-CWL-validated.yml",
+        )?)?;
+        assert_eq!(schema_version(&value)?, LEGACY_SCHEMA_VERSION);
+        assert_eq!(migrate(&mut value)?, LEGACY_SCHEMA_VERSION);
+        assert_eq!(schema_version(&value)?, CURRENT_SCHEMA_VERSION);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_current() -> Result<()> {
+        let mut value: Value = serde_yaml::from_str(&format!(
+            "schema_version: {}\nid: c13b6e27-a4ee-426f-8bdb-8cf5c4310bad\n",
+            CURRENT_SCHEMA_VERSION
+        ))?;
+        let original = value.clone();
+        assert_eq!(migrate(&mut value)?, CURRENT_SCHEMA_VERSION);
+        assert_eq!(value, original);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_newer() -> Result<()> {
+        let mut value: Value =
+            serde_yaml::from_str(&format!("schema_version: {}\n", CURRENT_SCHEMA_VERSION + 1))?;
+        assert!(migrate(&mut value).is_err());
+        let mut value: Value = serde_yaml::from_str("schema_version: v2\n")?;
+        assert!(migrate(&mut value).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrations_cover_all_versions() -> Result<()> {
+        for version in LEGACY_SCHEMA_VERSION..CURRENT_SCHEMA_VERSION {
+            assert!(MIGRATIONS.iter().any(|(from, _)| *from == version));
+        }
+        Ok(())
+    }
+}
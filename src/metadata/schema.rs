@@ -0,0 +1,311 @@
+use crate::diagnostic;
+use crate::metadata;
+
+use anyhow::{anyhow, Result};
+use jsonschema::paths::PathChunk;
+use jsonschema::JSONSchema;
+use schemars::schema_for;
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, ScanError};
+
+/// key: YAML path, value: (line, column)
+type Positions = HashMap<String, (usize, usize)>;
+
+/// JSON Schema of the Yevis metadata file.
+/// It is generated from `metadata::types::Metadata`, so it always matches what `yevis` reads.
+pub fn generate() -> Result<Value> {
+    let mut schema = schema_for!(metadata::types::Metadata);
+    let schema_metadata = schema.schema.metadata();
+    schema_metadata.title = Some("Yevis metadata file".to_string());
+    schema_metadata.description = Some(format!(
+        "Schema of the Yevis metadata file (yevis-metadata.yml), generated by {} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    ));
+    Ok(serde_json::to_value(&schema)?)
+}
+
+/// Check the document (YAML or JSON) against the JSON Schema.
+/// Documents in older format versions are checked after migration.
+/// Each diagnostic has the line and column of the offending node.
+pub fn validate(content: impl AsRef<str>) -> diagnostic::Diagnostics {
+    let mut diagnostics = diagnostic::Diagnostics::default();
+    let positions = match locate(content.as_ref()) {
+        Ok(positions) => positions,
+        Err(e) => {
+            diagnostics.push(
+                diagnostic::Diagnostic::new(
+                    "",
+                    format!("Failed to parse the metadata file: {}", e),
+                )
+                .with_position(Some(to_position(e.marker()))),
+            );
+            return diagnostics;
+        }
+    };
+    // Even json can be read with yaml reader
+    let mut value: serde_yaml::Value = match serde_yaml::from_str(content.as_ref()) {
+        Ok(value) => value,
+        Err(e) => {
+            diagnostics.push(
+                diagnostic::Diagnostic::new(
+                    "",
+                    format!("Failed to parse the metadata file: {}", e),
+                )
+                .with_position(e.location().map(|l| (l.line(), l.column()))),
+            );
+            return diagnostics;
+        }
+    };
+    // The schema describes the current format version, so older documents are upgraded first.
+    if let Err(e) = metadata::migrate::migrate(&mut value) {
+        diagnostics.push(
+            diagnostic::Diagnostic::new("schema_version", e)
+                .with_position(lookup_position(&positions, "schema_version")),
+        );
+        return diagnostics;
+    }
+    let instance: Value = match serde_json::to_value(&value) {
+        Ok(instance) => instance,
+        Err(e) => {
+            diagnostics.error("", format!("Failed to parse the metadata file: {}", e));
+            return diagnostics;
+        }
+    };
+    let schema = match generate().and_then(|schema| compile(&schema)) {
+        Ok(schema) => schema,
+        Err(e) => {
+            diagnostics.error("", e);
+            return diagnostics;
+        }
+    };
+    if let Err(errors) = schema.validate(&instance) {
+        for e in errors {
+            let path = to_yaml_path(e.instance_path.iter());
+            let position = lookup_position(&positions, &path);
+            diagnostics.push(diagnostic::Diagnostic::new(&path, &e).with_position(position));
+        }
+    }
+    diagnostics.0.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+fn compile(schema: &Value) -> Result<JSONSchema> {
+    JSONSchema::options()
+        // Draft 7 does not check the `uuid` format by default
+        .with_format("uuid", |s| Uuid::parse_str(s).is_ok())
+        .compile(schema)
+        .map_err(|e| anyhow!("Failed to compile the JSON Schema: {}", e))
+}
+
+/// `/workflow/files/0/type` -> `workflow.files[0].type`
+fn to_yaml_path<'a>(chunks: impl Iterator<Item = &'a PathChunk>) -> String {
+    let mut path = String::new();
+    for chunk in chunks {
+        match chunk {
+            PathChunk::Property(name) => path = join_key(&path, name),
+            PathChunk::Index(i) => path = format!("{}[{}]", path, i),
+            PathChunk::Keyword(_) => {}
+        }
+    }
+    path
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    match path {
+        "" => key.to_string(),
+        path => format!("{}.{}", path, key),
+    }
+}
+
+/// Fall back to the nearest parent node, e.g., for missing required fields.
+fn lookup_position(positions: &Positions, path: &str) -> Option<(usize, usize)> {
+    let mut path = path;
+    loop {
+        if let Some(position) = positions.get(path) {
+            return Some(*position);
+        }
+        match path.rfind(['.', '[']) {
+            Some(i) => path = &path[..i],
+            None => return positions.get("").copied(),
+        }
+    }
+}
+
+/// Collect the (line, column) of each node in the document, keyed by the YAML path.
+/// For mapping values, the position of the key is used.
+fn locate(content: &str) -> Result<Positions, ScanError> {
+    let mut locator = Locator::default();
+    let mut parser = Parser::new(content.chars());
+    parser.load(&mut locator, false)?;
+    Ok(locator.positions)
+}
+
+/// yaml-rust markers have a 1-based line and a 0-based column.
+fn to_position(mark: &Marker) -> (usize, usize) {
+    (mark.line(), mark.col() + 1)
+}
+
+enum Frame {
+    Mapping { path: String, key: Option<String> },
+    Sequence { path: String, index: usize },
+}
+
+#[derive(Default)]
+struct Locator {
+    stack: Vec<Frame>,
+    positions: Positions,
+}
+
+impl Locator {
+    fn value_path(&self) -> String {
+        match self.stack.last() {
+            Some(Frame::Mapping {
+                path,
+                key: Some(key),
+            }) => join_key(path, key),
+            Some(Frame::Mapping { path, key: None }) => path.clone(),
+            Some(Frame::Sequence { path, index }) => format!("{}[{}]", path, index),
+            None => String::new(),
+        }
+    }
+
+    fn finish_value(&mut self) {
+        match self.stack.last_mut() {
+            Some(Frame::Mapping { key, .. }) => *key = None,
+            Some(Frame::Sequence { index, .. }) => *index += 1,
+            None => {}
+        }
+    }
+
+    fn record(&mut self, path: String, mark: &Marker) {
+        self.positions
+            .entry(path)
+            .or_insert_with(|| to_position(mark));
+    }
+}
+
+impl MarkedEventReceiver for Locator {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        match ev {
+            Event::Scalar(value, ..) => {
+                if let Some(Frame::Mapping { path, key: None }) = self.stack.last() {
+                    let (path, key_path) = (path.clone(), join_key(path, &value));
+                    self.record(path, &mark);
+                    self.record(key_path, &mark);
+                    if let Some(Frame::Mapping { key, .. }) = self.stack.last_mut() {
+                        *key = Some(value);
+                    }
+                } else {
+                    self.record(self.value_path(), &mark);
+                    self.finish_value();
+                }
+            }
+            Event::Alias(_) => {
+                self.record(self.value_path(), &mark);
+                self.finish_value();
+            }
+            Event::MappingStart(_) => {
+                // The position of a block mapping is recorded with its first key,
+                // because the marker of this event points after the first key.
+                let path = self.value_path();
+                self.stack.push(Frame::Mapping { path, key: None });
+            }
+            Event::SequenceStart(_) => {
+                let path = self.value_path();
+                self.record(path.clone(), &mark);
+                self.stack.push(Frame::Sequence { path, index: 0 });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+                self.finish_value();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() -> Result<()> {
+        let schema = generate()?;
+        assert_eq!(schema["title"], "Yevis metadata file");
+        assert!(schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("workflow")));
+        compile(&schema)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_test_metadata() -> Result<()> {
+        for entry in std::fs::read_dir("./tests")? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("yml") {
+                continue;
+            }
+            let diagnostics = validate(std::fs::read_to_string(&path)?);
+            assert!(
+                diagnostics.0.is_empty(),
+                "{}: {:?}",
+                path.display(),
+                diagnostics
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_with_position() -> Result<()> {
+        let content = std::fs::read_to_string("./tests/test-metadata-CWL-validated.yml")?
+            .replacen("type: primary", "type: main", 1)
+            .replace("version: 1.0.0\n", "");
+        let diagnostics = validate(&content);
+        let found = diagnostics
+            .0
+            .iter()
+            .map(|d| (d.path.as_str(), d.line, d.column))
+            .collect::<Vec<_>>();
+        let file_type_line = content
+            .lines()
+            .position(|l| l.trim() == "type: main")
+            .unwrap()
+            + 1;
+        assert_eq!(
+            found,
+            vec![
+                ("", Some(2), Some(1)),
+                ("workflow.files[1].type", Some(file_type_line), Some(7)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_invalid_yaml() -> Result<()> {
+        let diagnostics = validate("id: [a\nversion: 1.0.0\n");
+        assert_eq!(diagnostics.0.len(), 1);
+        assert!(diagnostics.0[0].line.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_yaml_path() -> Result<()> {
+        let chunks = [
+            PathChunk::Property("workflow".into()),
+            PathChunk::Property("testing".into()),
+            PathChunk::Index(1),
+            PathChunk::Property("id".into()),
+        ];
+        assert_eq!(to_yaml_path(chunks.iter()), "workflow.testing[1].id");
+        Ok(())
+    }
+}
@@ -1,18 +1,30 @@
+use crate::citation;
 use crate::gh;
 use crate::inspect;
+use crate::license;
+use crate::metadata;
 use crate::remote;
+use crate::zenodo;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, ensure, Result};
+use crypto::digest::Digest as _;
+use crypto::md5::Md5;
+use log::info;
+use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fmt;
 use std::path::{Path, PathBuf};
 use url::Url;
 use uuid::Uuid;
 
 #[skip_serializing_none]
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Metadata {
+    pub schema_version: u64,
     pub id: Uuid,
     pub version: String,
     pub license: String,
@@ -26,29 +38,148 @@ impl Metadata {
         wf_loc: &Url,
         gh_token: impl AsRef<str>,
         url_type: &remote::UrlType,
+        list_all_files: bool,
     ) -> Result<Self> {
         let primary_wf = remote::Remote::new(wf_loc, &gh_token, None, None)?;
+        if let remote::Remote::Zenodo(zenodo_url) = &primary_wf {
+            if zenodo_url.record_id.is_some() {
+                return Self::new_from_zenodo(zenodo_url, &gh_token);
+            }
+        }
+        let language = inspect::inspect_wf_type_version(&primary_wf.to_url()?)?;
         Ok(Self {
+            schema_version: metadata::migrate::CURRENT_SCHEMA_VERSION,
             id: Uuid::new_v4(),
             version: "1.0.0".to_string(),
             license: "CC0-1.0".to_string(),
-            authors: vec![Author::new_via_api(&gh_token)?],
+            authors: Author::new_from_repo(&gh_token, |path| primary_wf.read_repo_file(path))?,
             zenodo: None,
             workflow: Workflow {
                 name: primary_wf.file_prefix()?,
                 readme: primary_wf.readme(&gh_token, url_type)?,
-                language: inspect::inspect_wf_type_version(&primary_wf.to_url()?)?,
-                files: primary_wf.wf_files(&gh_token, url_type)?,
+                files: primary_wf.wf_files(
+                    &gh_token,
+                    url_type,
+                    &language.r#type,
+                    list_all_files,
+                )?,
+                testing: primary_wf.testing(&gh_token, url_type, &language.r#type)?,
+                language,
+            },
+        })
+    }
+
+    /// If the record contains a metadata file uploaded by `yevis publish`, it is imported
+    /// with the URLs replaced by the files in the record.
+    /// Otherwise, all files in the record are listed and the authors are taken from the record creators.
+    pub fn new_from_zenodo(
+        zenodo_url: &remote::ZenodoUrl,
+        gh_token: impl AsRef<str>,
+    ) -> Result<Self> {
+        let record = zenodo_url.record()?;
+        let files_map = record.files.iter().cloned().collect::<HashMap<_, _>>();
+        if let Some(meta_url) = record.metadata_file() {
+            info!(
+                "Importing the metadata file {} from the Zenodo record",
+                meta_url
+            );
+            let mut meta = metadata::io::parse(remote::fetch_raw_content(meta_url)?)?;
+            zenodo::update_file_urls(&mut meta, &files_map)?;
+            meta.zenodo = Some(record.zenodo);
+            return Ok(meta);
+        }
+
+        let (primary_name, primary_url) = record.primary_wf(&zenodo_url.file_name)?;
+        let language = inspect::inspect_wf_type_version(&primary_url)?;
+        let files = record
+            .wf_files()
+            .into_iter()
+            .map(|(filename, url)| {
+                let r#type = if *filename == primary_name {
+                    FileType::Primary
+                } else {
+                    FileType::Secondary
+                };
+                File::new(url, &Some(filename), r#type)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let authors = if record.creators.is_empty() {
+            vec![Author::new_via_api(&gh_token)?]
+        } else {
+            let (gh_account, gh_name, _) = gh::api::get_author_info(&gh_token)?;
+            record
+                .creators
+                .iter()
+                .map(|creator| Author::new_from_creator(creator, &gh_account, &gh_name))
+                .collect()
+        };
+        let license = match &record.license {
+            Some(license) => license::LicenseRegistry::new_local(&None::<PathBuf>)?
+                .resolve(license)
+                .unwrap_or_else(|_| "CC0-1.0".to_string()),
+            None => "CC0-1.0".to_string(),
+        };
+        Ok(Self {
+            schema_version: metadata::migrate::CURRENT_SCHEMA_VERSION,
+            id: Uuid::new_v4(),
+            version: record
+                .version
+                .clone()
+                .unwrap_or_else(|| "1.0.0".to_string()),
+            license,
+            authors,
+            zenodo: None,
+            workflow: Workflow {
+                name: Path::new(&primary_name)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or(primary_name.clone()),
+                readme: match record.readme() {
+                    Some(readme) => readme.clone(),
+                    None => Url::parse("https://example.com/PATH/TO/README.md")?,
+                },
+                files,
+                language,
                 testing: vec![Testing::default()],
             },
         })
     }
+
+    /// `wf_path`: local path to a primary workflow file in a clone of a GitHub repository.
+    pub fn new_from_local(
+        wf_path: impl AsRef<Path>,
+        gh_token: impl AsRef<str>,
+        url_type: &remote::UrlType,
+        list_all_files: bool,
+    ) -> Result<Self> {
+        let primary_wf = remote::LocalPath::new(wf_path)?;
+        let language = inspect::inspect_wf_content(primary_wf.content()?);
+        Ok(Self {
+            schema_version: metadata::migrate::CURRENT_SCHEMA_VERSION,
+            id: Uuid::new_v4(),
+            version: "1.0.0".to_string(),
+            license: "CC0-1.0".to_string(),
+            authors: Author::new_from_repo(&gh_token, |path| primary_wf.read_repo_file(path))?,
+            zenodo: None,
+            workflow: Workflow {
+                name: primary_wf.file_prefix()?,
+                readme: match primary_wf.readme(url_type)? {
+                    Some(readme) => readme,
+                    None => Url::parse("https://example.com/PATH/TO/README.md")?,
+                },
+                files: primary_wf.wf_files(url_type, &language.r#type, list_all_files)?,
+                testing: primary_wf.testing(url_type, &language.r#type)?,
+                language,
+            },
+        })
+    }
 }
 
 #[skip_serializing_none]
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Author {
-    pub github_account: String,
+    /// Not known for the authors in a citation file without a GitHub account
+    pub github_account: Option<String>,
     pub name: String,
     pub affiliation: Option<String>,
     pub orcid: Option<String>,
@@ -58,15 +189,89 @@ impl Author {
     pub fn new_via_api(gh_token: impl AsRef<str>) -> Result<Self> {
         let (github_account, name, affiliation) = gh::api::get_author_info(gh_token)?;
         Ok(Self {
-            github_account,
+            github_account: Some(github_account),
             name,
             affiliation,
-            orcid: Some("PUT YOUR ORCID OR REMOVE THIS LINE".to_string()),
+            orcid: None,
         })
     }
+
+    /// Authors listed in a citation file of the repository (see `citation::CITATION_FILES`).
+    /// The token owner is added as the first author if not listed.
+    /// If no citation file is found, only the token owner is returned.
+    pub fn new_from_repo(
+        gh_token: impl AsRef<str>,
+        read: impl Fn(&Path) -> Result<String>,
+    ) -> Result<Vec<Self>> {
+        let citation_authors = citation::find_authors(read);
+        let gh_author = Self::new_via_api(&gh_token)?;
+        if citation_authors.is_empty() {
+            return Ok(vec![gh_author]);
+        }
+        let mut authors = citation_authors
+            .iter()
+            .map(|author| {
+                Self::new_from_citation(
+                    author,
+                    gh_author.github_account.as_deref().unwrap_or_default(),
+                    &gh_author.name,
+                )
+            })
+            .collect::<Vec<_>>();
+        if !authors
+            .iter()
+            .any(|author| author.github_account == gh_author.github_account)
+        {
+            authors.insert(0, gh_author);
+        }
+        Ok(authors)
+    }
+
+    /// The GitHub account is taken from the citation file,
+    /// or from the token owner if the name is the same, otherwise it is left unset.
+    pub fn new_from_citation(
+        author: &citation::CitationAuthor,
+        gh_account: impl AsRef<str>,
+        gh_name: impl AsRef<str>,
+    ) -> Self {
+        let github_account = match &author.github_account {
+            Some(github_account) => Some(github_account.clone()),
+            None if is_same_name(&author.name, &gh_name) => Some(gh_account.as_ref().to_string()),
+            None => None,
+        };
+        Self {
+            github_account,
+            name: author.name.clone(),
+            affiliation: author.affiliation.clone(),
+            orcid: author.orcid.clone(),
+        }
+    }
+
+    pub fn new_from_creator(
+        creator: &zenodo::types::Creator,
+        gh_account: impl AsRef<str>,
+        gh_name: impl AsRef<str>,
+    ) -> Self {
+        let author = citation::CitationAuthor::new(
+            creator.name.clone(),
+            creator.affiliation.clone(),
+            creator.orcid.as_deref(),
+            None,
+        );
+        Self::new_from_citation(&author, gh_account, gh_name)
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+/// `Family, Given` and `Given Family` are the same name.
+fn is_same_name(name: impl AsRef<str>, other: impl AsRef<str>) -> bool {
+    let reorder = |name: &str| match name.split_once(',') {
+        Some((family, given)) => format!("{} {}", given.trim(), family.trim()),
+        None => name.trim().to_string(),
+    };
+    reorder(name.as_ref()).to_lowercase() == reorder(other.as_ref()).to_lowercase()
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Workflow {
     pub name: String,
     pub readme: Url,
@@ -86,13 +291,13 @@ impl Workflow {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Language {
     pub r#type: LanguageType,
     pub version: String,
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum LanguageType {
     Cwl,
@@ -102,6 +307,23 @@ pub enum LanguageType {
     Unknown,
 }
 
+impl LanguageType {
+    /// The engine used when `engines` is not set in the test case.
+    pub fn default_engine(&self) -> Result<Engine> {
+        let name = match self {
+            LanguageType::Cwl => "cwltool",
+            LanguageType::Wdl => "cromwell",
+            LanguageType::Nfl => "nextflow",
+            LanguageType::Smk => "snakemake",
+            LanguageType::Unknown => bail!("Unsupported workflow language type"),
+        };
+        Ok(Engine {
+            name: name.to_string(),
+            version: None,
+        })
+    }
+}
+
 impl fmt::Display for LanguageType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -114,7 +336,7 @@ impl fmt::Display for LanguageType {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct File {
     pub url: Url,
     pub target: Option<PathBuf>,
@@ -151,17 +373,29 @@ impl File {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum FileType {
     Primary,
     Secondary,
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Testing {
     pub id: String,
     pub files: Vec<TestFile>,
+    /// Timeout of the run in seconds
+    pub timeout: Option<u64>,
+    /// Number of times the test case is run again if it fails or times out
+    pub retries: Option<u64>,
+    /// Workflow engines to run the test on. The test is run on each engine (test matrix).
+    /// If empty, the default engine of the language is used.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub engines: Vec<Engine>,
+    /// Outputs checked after the run completes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expected_outputs: Vec<ExpectedOutput>,
 }
 
 impl Default for Testing {
@@ -188,11 +422,84 @@ impl Default for Testing {
                 )
                 .unwrap(),
             ],
+            timeout: None,
+            retries: None,
+            engines: vec![],
+            expected_outputs: vec![],
         }
     }
 }
 
 impl Testing {
+    /// `to_url`: convert a path relative to the repository root into a URL.
+    /// If no test case is found, a template with placeholder URLs is returned.
+    pub fn from_test_cases(
+        test_cases: &[inspect::testing::TestCase],
+        to_url: impl Fn(&Path) -> Result<Url>,
+    ) -> Result<Vec<Self>> {
+        if test_cases.is_empty() {
+            return Ok(vec![Self::default()]);
+        }
+        test_cases
+            .iter()
+            .enumerate()
+            .map(|(i, test_case)| -> Result<Self> {
+                let mut files = vec![];
+                if let Some(wf_params) = &test_case.wf_params {
+                    files.push(TestFile::new(
+                        &to_url(wf_params)?,
+                        &None::<PathBuf>,
+                        TestFileType::WfParams,
+                    )?);
+                }
+                if let Some(wf_engine_params) = &test_case.wf_engine_params {
+                    files.push(TestFile::new(
+                        &to_url(wf_engine_params)?,
+                        &None::<PathBuf>,
+                        TestFileType::WfEngineParams,
+                    )?);
+                }
+                for (path, target) in &test_case.others {
+                    files.push(TestFile::new(
+                        &to_url(path)?,
+                        &Some(target),
+                        TestFileType::Other,
+                    )?);
+                }
+                Ok(Self {
+                    id: format!("test_{}", i + 1),
+                    files,
+                    timeout: None,
+                    retries: None,
+                    engines: vec![],
+                    expected_outputs: vec![],
+                })
+            })
+            .collect()
+    }
+
+    pub fn engines(&self, language: &LanguageType) -> Result<Vec<Engine>> {
+        match self.engines.is_empty() {
+            true => Ok(vec![language.default_engine()?]),
+            false => Ok(self.engines.clone()),
+        }
+    }
+
+    /// The test ID, with the engine in the test matrix, e.g., `test_1_toil`.
+    /// The test logs and the RO-Crate of a run are named with it.
+    pub fn label(&self, engine: &Engine) -> String {
+        match self.engines.len() > 1 {
+            true => {
+                let label = format!("{}_{}", self.id, engine.name);
+                match &engine.version {
+                    Some(version) => format!("{}_{}", label, version),
+                    None => label,
+                }
+            }
+            false => self.id.clone(),
+        }
+    }
+
     pub fn wf_params(&self) -> Result<String> {
         match self
             .files
@@ -216,7 +523,7 @@ impl Testing {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestFile {
     pub url: Url,
     pub target: Option<PathBuf>,
@@ -249,7 +556,7 @@ impl TestFile {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum TestFileType {
     WfParams,
@@ -257,7 +564,129 @@ pub enum TestFileType {
     Other,
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+/// Workflow engine listed in `workflow_engine_versions` of the WES `/service-info`, e.g., `toil`.
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Engine {
+    pub name: String,
+    /// Any version supported by the WES if not specified
+    pub version: Option<String>,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {}", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// An output file of the test run and the assertions on its content.
+/// All specified assertions must hold.
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExpectedOutput {
+    /// Path of the output file in the outputs of the run, e.g., `qc/report.html`
+    pub file_name: PathBuf,
+    /// Hex-encoded SHA-256 checksum
+    pub sha256: Option<String>,
+    /// Hex-encoded MD5 checksum
+    pub md5: Option<String>,
+    pub size: Option<SizeRange>,
+    /// Regular expression that at least one line must match
+    pub regex: Option<String>,
+    pub line_count: Option<u64>,
+}
+
+/// File size in bytes (inclusive).
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SizeRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl ExpectedOutput {
+    /// Check the definition itself, e.g., the checksum format and the regex syntax.
+    pub fn validate(&self) -> Result<()> {
+        let hex_re = Regex::new(r"^[0-9a-fA-F]+$")?;
+        if let Some(sha256) = &self.sha256 {
+            ensure!(
+                sha256.len() == 64 && hex_re.is_match(sha256),
+                "`sha256` must be 64 hex digits: {}",
+                sha256
+            );
+        }
+        if let Some(md5) = &self.md5 {
+            ensure!(
+                md5.len() == 32 && hex_re.is_match(md5),
+                "`md5` must be 32 hex digits: {}",
+                md5
+            );
+        }
+        if let Some(SizeRange {
+            min: Some(min),
+            max: Some(max),
+        }) = &self.size
+        {
+            ensure!(min <= max, "`size.min` is larger than `size.max`");
+        }
+        if let Some(regex) = &self.regex {
+            Regex::new(regex).map_err(|e| anyhow!("Invalid `regex`: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Return the list of failed assertions.
+    pub fn check(&self, content: &[u8]) -> Result<Vec<String>> {
+        let mut failures = vec![];
+        if let Some(expected) = &self.sha256 {
+            let actual = format!("{:x}", Sha256::digest(content));
+            if !expected.eq_ignore_ascii_case(&actual) {
+                failures.push(format!("sha256 is {}, expected {}", actual, expected));
+            }
+        }
+        if let Some(expected) = &self.md5 {
+            let mut md5 = Md5::new();
+            md5.input(content);
+            let actual = md5.result_str();
+            if !expected.eq_ignore_ascii_case(&actual) {
+                failures.push(format!("md5 is {}, expected {}", actual, expected));
+            }
+        }
+        if let Some(size) = &self.size {
+            let actual = content.len() as u64;
+            if size.min.is_some_and(|min| actual < min) || size.max.is_some_and(|max| actual > max)
+            {
+                failures.push(format!(
+                    "size is {} bytes, expected {}..={}",
+                    actual,
+                    size.min.map(|min| min.to_string()).unwrap_or_default(),
+                    size.max.map(|max| max.to_string()).unwrap_or_default()
+                ));
+            }
+        }
+        if self.regex.is_some() || self.line_count.is_some() {
+            let text = String::from_utf8_lossy(content);
+            if let Some(regex) = &self.regex {
+                let re = Regex::new(regex)?;
+                if !text.lines().any(|line| re.is_match(line)) {
+                    failures.push(format!("no line matches the regex `{}`", regex));
+                }
+            }
+            if let Some(expected) = self.line_count {
+                let actual = text.lines().count() as u64;
+                if actual != expected {
+                    failures.push(format!("line count is {}, expected {}", actual, expected));
+                }
+            }
+        }
+        Ok(failures)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Zenodo {
     pub url: Url,
     pub id: u64,
@@ -291,6 +720,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_author_new_from_citation() -> Result<()> {
+        let author = |name: &str| citation::CitationAuthor::new(name.to_string(), None, None, None);
+        let same = Author::new_from_citation(&author("Doe, John"), "johndoe", "John Doe");
+        assert_eq!(same.github_account, Some("johndoe".to_string()));
+        let other = Author::new_from_citation(&author("Roe, Jane"), "johndoe", "John Doe");
+        assert_eq!(other.github_account, None);
+        assert_eq!(other.orcid, None);
+        Ok(())
+    }
+
     #[test]
     fn test_testing_default() -> Result<()> {
         let testing = Testing::default();
@@ -299,6 +739,92 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_testing_engines() -> Result<()> {
+        let mut testing = Testing::default();
+        assert_eq!(
+            testing.engines(&LanguageType::Cwl)?,
+            vec![Engine {
+                name: "cwltool".to_string(),
+                version: None
+            }]
+        );
+        assert!(testing.engines(&LanguageType::Unknown).is_err());
+        assert_eq!(
+            testing.label(&testing.engines(&LanguageType::Cwl)?[0]),
+            "test_1"
+        );
+        testing.engines = vec![
+            Engine {
+                name: "toil".to_string(),
+                version: Some("5.6.0".to_string()),
+            },
+            Engine {
+                name: "cwltool".to_string(),
+                version: None,
+            },
+        ];
+        assert_eq!(testing.engines(&LanguageType::Cwl)?, testing.engines);
+        assert_eq!(testing.engines[0].to_string(), "toil 5.6.0");
+        assert_eq!(testing.label(&testing.engines[0]), "test_1_toil_5.6.0");
+        assert_eq!(testing.label(&testing.engines[1]), "test_1_cwltool");
+        Ok(())
+    }
+
+    #[test]
+    fn test_expected_output_check() -> Result<()> {
+        let content = b"hello\nworld\n";
+        let expected_output = ExpectedOutput {
+            file_name: PathBuf::from("out.txt"),
+            sha256: Some(
+                "4a1e67f2fe1d1cc7b31d0ca2ec441da4778203a036a77da10344c85e24ff0f92".to_string(),
+            ),
+            md5: Some("0f723ae7f9bf07744445e93ac5595156".to_string()),
+            size: Some(SizeRange {
+                min: Some(1),
+                max: Some(12),
+            }),
+            regex: Some("^wor".to_string()),
+            line_count: Some(2),
+        };
+        expected_output.validate()?;
+        assert!(expected_output.check(content)?.is_empty());
+
+        let expected_output = ExpectedOutput {
+            file_name: PathBuf::from("out.txt"),
+            sha256: None,
+            md5: Some("0f723ae7f9bf07744445e93ac5595157".to_string()),
+            size: Some(SizeRange {
+                min: Some(100),
+                max: None,
+            }),
+            regex: Some("^foo$".to_string()),
+            line_count: Some(3),
+        };
+        assert_eq!(expected_output.check(content)?.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expected_output_validate_invalid() -> Result<()> {
+        let expected_output = ExpectedOutput {
+            file_name: PathBuf::from("out.txt"),
+            sha256: Some("abc".to_string()),
+            md5: None,
+            size: None,
+            regex: None,
+            line_count: None,
+        };
+        assert!(expected_output.validate().is_err());
+        let expected_output = ExpectedOutput {
+            sha256: None,
+            regex: Some("(".to_string()),
+            ..expected_output
+        };
+        assert!(expected_output.validate().is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_test_file_new() -> Result<()> {
         let url = Url::parse("https://example.com/path/to/file.txt")?;
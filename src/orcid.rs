@@ -0,0 +1,69 @@
+use anyhow::{ensure, Result};
+use regex::Regex;
+
+/// `https://orcid.org/0000-0002-1825-0097` -> `0000-0002-1825-0097`
+pub fn normalize(orcid: impl AsRef<str>) -> String {
+    let orcid = orcid.as_ref().trim();
+    let re = Regex::new(r"^(https?://)?(www\.)?orcid\.org/").unwrap();
+    re.replace(orcid, "").to_uppercase()
+}
+
+/// Check the format and the check digit of an ORCID iD.
+/// https://support.orcid.org/hc/en-us/articles/360006897674-Structure-of-the-ORCID-Identifier
+pub fn validate(orcid: impl AsRef<str>) -> Result<()> {
+    let orcid = orcid.as_ref();
+    let orcid_re = Regex::new(r"^\d{4}-\d{4}-\d{4}-\d{3}[\dX]$").unwrap();
+    ensure!(
+        orcid_re.is_match(orcid),
+        "ORCID {} is not in the format of `0000-0000-0000-0000`",
+        orcid
+    );
+    let digits = orcid.replace('-', "");
+    let (base, check) = digits.split_at(15);
+    ensure!(
+        check_digit(base) == check.chars().next().unwrap_or_default(),
+        "ORCID {} has an invalid check digit",
+        orcid
+    );
+    Ok(())
+}
+
+/// ISO 7064 11,2
+fn check_digit(base_digits: impl AsRef<str>) -> char {
+    let total = base_digits
+        .as_ref()
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .fold(0, |total, digit| (total + digit) * 2);
+    match (12 - total % 11) % 11 {
+        10 => 'X',
+        result => char::from_digit(result, 10).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() -> Result<()> {
+        assert_eq!(
+            normalize("https://orcid.org/0000-0002-1694-233x"),
+            "0000-0002-1694-233X"
+        );
+        assert_eq!(normalize("0000-0002-1825-0097"), "0000-0002-1825-0097");
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> Result<()> {
+        validate("0000-0002-1825-0097")?;
+        validate("0000-0002-1694-233X")?;
+        validate("0000-0003-2765-0049")?;
+        assert!(validate("0000-0002-1825-0098").is_err());
+        assert!(validate("0000-0002-1825-009").is_err());
+        assert!(validate("invalid-orcid").is_err());
+        Ok(())
+    }
+}
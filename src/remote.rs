@@ -1,13 +1,21 @@
+pub mod bitbucket_url;
 pub mod gh_url;
 pub mod gist_url;
+pub mod gitlab_url;
+pub mod local_path;
+pub mod zenodo_url;
 
+pub use bitbucket_url::BitbucketUrl;
 pub use gh_url::GitHubUrl;
 pub use gh_url::UrlType;
 pub use gist_url::GistUrl;
+pub use gitlab_url::GitLabUrl;
+pub use local_path::LocalPath;
+pub use zenodo_url::ZenodoUrl;
 
 use crate::metadata;
 
-use anyhow::{anyhow, ensure, Result};
+use anyhow::{anyhow, bail, ensure, Result};
 use std::path::Path;
 use std::{collections::HashMap, path::PathBuf};
 use url::Url;
@@ -15,7 +23,9 @@ use url::Url;
 pub enum Remote {
     Gist(GistUrl),
     GitHub(GitHubUrl),
-    Zenodo(Url),
+    GitLab(GitLabUrl),
+    Bitbucket(BitbucketUrl),
+    Zenodo(ZenodoUrl),
     Other(Url),
 }
 
@@ -27,11 +37,18 @@ impl Remote {
     ///   - Gist:
     ///     - https://gist.github.com/...
     ///     - https://gist.githubusercontent.com/...
+    ///   - GitLab (gitlab.com or self-managed):
+    ///     - https://<host>/.../-/blob/...
+    ///     - https://<host>/.../-/raw/...
+    ///   - Bitbucket:
+    ///     - https://bitbucket.org/.../src/...
+    ///     - https://bitbucket.org/.../raw/...
     ///   - Zenodo:
     ///     - https://zenodo.org/...
     ///     - https://sandbox.zenodo.org/...
     ///   - Other:
     ///     - https://...
+    ///     - other pages of GitLab and Bitbucket, e.g., repository pages and issues
     pub fn new(
         url: &Url,
         gh_token: impl AsRef<str>,
@@ -49,7 +66,15 @@ impl Remote {
             "gist.github.com" | "gist.githubusercontent.com" => {
                 Ok(Self::Gist(GistUrl::new(url, gh_token)?))
             }
-            "zenodo.org" | "sandbox.zenodo.org" => Ok(Self::Zenodo(url.clone())),
+            "zenodo.org" | "sandbox.zenodo.org" => Ok(Self::Zenodo(ZenodoUrl::new(url)?)),
+            _ if bitbucket_url::is_bitbucket_url(url) => Ok(Self::Bitbucket(BitbucketUrl::new(
+                url,
+                branch_memo,
+                commit_memo,
+            )?)),
+            _ if gitlab_url::is_gitlab_url(url) => {
+                Ok(Self::GitLab(GitLabUrl::new(url, branch_memo, commit_memo)?))
+            }
             _ => Ok(Self::Other(url.clone())),
         }
     }
@@ -57,8 +82,10 @@ impl Remote {
     pub fn to_url(&self) -> Result<Url> {
         match self {
             Self::GitHub(gh) => gh.to_url(),
+            Self::GitLab(gl) => gl.to_url(),
+            Self::Bitbucket(bb) => bb.to_url(),
             Self::Gist(gist) => gist.to_url(),
-            Self::Zenodo(zenodo) => Ok(zenodo.clone()),
+            Self::Zenodo(zenodo) => zenodo.to_url(),
             Self::Other(other) => Ok(other.clone()),
         }
     }
@@ -66,8 +93,10 @@ impl Remote {
     pub fn to_typed_url(&self, url_type: &UrlType) -> Result<Url> {
         match self {
             Self::GitHub(gh) => gh.to_typed_url(url_type),
+            Self::GitLab(gl) => gl.to_typed_url(url_type),
+            Self::Bitbucket(bb) => bb.to_typed_url(url_type),
             Self::Gist(gist) => gist.to_url(),
-            Self::Zenodo(zenodo) => Ok(zenodo.clone()),
+            Self::Zenodo(zenodo) => zenodo.to_url(),
             Self::Other(other) => Ok(other.clone()),
         }
     }
@@ -98,6 +127,8 @@ impl Remote {
         let default_url = Url::parse("https://example.com/PATH/TO/README.md")?;
         let readme = match self {
             Self::GitHub(gh_url) => gh_url.readme(gh_token, url_type)?,
+            Self::GitLab(gl_url) => gl_url.readme(url_type)?.unwrap_or(default_url),
+            Self::Bitbucket(bb_url) => bb_url.readme(url_type)?.unwrap_or(default_url),
             Self::Gist(_) => default_url,
             Self::Zenodo(_) => default_url,
             Self::Other(_) => default_url,
@@ -109,12 +140,16 @@ impl Remote {
         &self,
         gh_token: impl AsRef<str>,
         url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+        list_all_files: bool,
     ) -> Result<Vec<metadata::types::File>> {
         match self {
-            Self::GitHub(gh_url) => gh_url.wf_files(gh_token, url_type),
+            Self::GitHub(gh_url) => gh_url.wf_files(gh_token, url_type, wf_type, list_all_files),
+            Self::GitLab(gl_url) => gl_url.wf_files(url_type, wf_type, list_all_files),
+            Self::Bitbucket(bb_url) => bb_url.wf_files(url_type, wf_type, list_all_files),
             Self::Gist(gist_url) => gist_url.wf_files(gh_token),
-            Self::Zenodo(url) => Ok(vec![metadata::types::File::new(
-                url,
+            Self::Zenodo(zenodo) => Ok(vec![metadata::types::File::new(
+                &zenodo.to_url()?,
                 &None::<PathBuf>,
                 metadata::types::FileType::Primary,
             )?]),
@@ -125,6 +160,76 @@ impl Remote {
             )?]),
         }
     }
+
+    /// Read a file in the repository at the commit of the workflow.
+    /// `path`: path relative to the repository root
+    pub fn read_repo_file(&self, path: &Path) -> Result<String> {
+        let url = match self {
+            Self::GitHub(gh_url) => {
+                let mut gh_url = gh_url.clone();
+                gh_url.file_path = path.to_path_buf();
+                gh_url.to_typed_url(&UrlType::Commit)?
+            }
+            Self::GitLab(gl_url) => {
+                let mut gl_url = gl_url.clone();
+                gl_url.file_path = path.to_path_buf();
+                gl_url.to_typed_url(&UrlType::Commit)?
+            }
+            Self::Bitbucket(bb_url) => {
+                let mut bb_url = bb_url.clone();
+                bb_url.file_path = path.to_path_buf();
+                bb_url.to_typed_url(&UrlType::Commit)?
+            }
+            Self::Gist(_) | Self::Zenodo(_) | Self::Other(_) => {
+                bail!("The workflow is not in a repository")
+            }
+        };
+        fetch_raw_content(&url)
+    }
+
+    /// Test cases are generated from the test files next to the primary workflow (see `inspect::testing`).
+    pub fn testing(
+        &self,
+        gh_token: impl AsRef<str>,
+        url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+    ) -> Result<Vec<metadata::types::Testing>> {
+        match self {
+            Self::GitHub(gh_url) => gh_url.testing(gh_token, url_type, wf_type),
+            Self::GitLab(gl_url) => gl_url.testing(url_type, wf_type),
+            Self::Bitbucket(bb_url) => bb_url.testing(url_type, wf_type),
+            Self::Gist(_) | Self::Zenodo(_) | Self::Other(_) => {
+                Ok(vec![metadata::types::Testing::default()])
+            }
+        }
+    }
+}
+
+/// `primary`, `files`: paths relative to the repository root
+/// `target` is the path relative to the directory of the primary workflow,
+/// or to the common parent directory if some files are outside of it.
+pub fn to_wf_files(
+    primary: &Path,
+    files: &[PathBuf],
+    to_url: impl Fn(&Path) -> Result<Url>,
+) -> Result<Vec<metadata::types::File>> {
+    let mut base = primary.parent().unwrap_or_else(|| Path::new(""));
+    while !files.iter().all(|file| file.starts_with(base)) {
+        base = base.parent().unwrap_or_else(|| Path::new(""));
+    }
+    files
+        .iter()
+        .map(|file| -> Result<metadata::types::File> {
+            let url = to_url(file)?;
+            let target = file.strip_prefix(base)?;
+            let r#type = if file == primary {
+                metadata::types::FileType::Primary
+            } else {
+                metadata::types::FileType::Secondary
+            };
+            metadata::types::File::new(&url, &Some(target.to_path_buf()), r#type)
+        })
+        .collect::<Result<Vec<_>>>()
 }
 
 pub fn fetch_json_content(remote_loc: &Url) -> Result<String> {
@@ -173,4 +278,18 @@ mod tests {
         assert!(content.contains("yevis-cli"));
         Ok(())
     }
+
+    #[test]
+    fn test_remote_new_other() -> Result<()> {
+        for url in [
+            "https://gitlab.com/group/project",
+            "https://gitlab.com/group/project/-/issues/1",
+            "https://bitbucket.org/workspace/repo",
+            "https://bitbucket.org/workspace/repo/get/main.zip",
+        ] {
+            let remote = Remote::new(&Url::parse(url)?, "", None, None)?;
+            assert!(matches!(remote, Remote::Other(_)), "{}", url);
+        }
+        Ok(())
+    }
 }
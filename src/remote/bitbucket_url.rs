@@ -0,0 +1,248 @@
+use crate::bitbucket;
+use crate::inspect;
+use crate::metadata;
+use crate::remote;
+use crate::remote::gh_url::is_commit_hash;
+use crate::remote::UrlType;
+
+use anyhow::{anyhow, ensure, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BitbucketUrl {
+    pub workspace: String,
+    pub name: String,
+    pub branch: String,
+    pub commit: String,
+    pub file_path: PathBuf,
+    pub ori_url_type: UrlType,
+}
+
+impl BitbucketUrl {
+    /// Parse the workflow location.
+    /// The workflow location should be in the format of:
+    ///
+    /// - https://bitbucket.org/<workspace>/<name>/src/<branch>/<path_to_file>
+    /// - https://bitbucket.org/<workspace>/<name>/src/<commit>/<path_to_file>
+    /// - https://bitbucket.org/<workspace>/<name>/raw/<branch>/<path_to_file>
+    /// - https://bitbucket.org/<workspace>/<name>/raw/<commit>/<path_to_file>
+    pub fn new(
+        url: &Url,
+        branch_memo: Option<&mut HashMap<String, String>>,
+        commit_memo: Option<&mut HashMap<String, String>>,
+    ) -> Result<Self> {
+        let (workspace, name, branch_or_commit, file_path) = parse_url(url)?;
+        let (branch, commit, ori_url_type) = match is_commit_hash(&branch_or_commit)? {
+            true => {
+                let branch = bitbucket::api::get_default_branch(&workspace, &name, branch_memo)?;
+                (branch, branch_or_commit, UrlType::Commit)
+            }
+            false => {
+                let commit = bitbucket::api::get_latest_commit_sha(
+                    &workspace,
+                    &name,
+                    &branch_or_commit,
+                    commit_memo,
+                )?;
+                (branch_or_commit, commit, UrlType::Branch)
+            }
+        };
+
+        Ok(Self {
+            workspace,
+            name,
+            branch,
+            commit,
+            file_path,
+            ori_url_type,
+        })
+    }
+
+    /// default: UrlType::Branch
+    pub fn to_url(&self) -> Result<Url> {
+        self.to_typed_url(&self.ori_url_type)
+    }
+
+    /// UrlType::Branch
+    /// -> https://bitbucket.org/<workspace>/<name>/raw/<branch>/<path_to_file>
+    /// UrlType::Commit
+    /// -> https://bitbucket.org/<workspace>/<name>/raw/<commit>/<path_to_file>
+    pub fn to_typed_url(&self, url_type: &UrlType) -> Result<Url> {
+        Ok(Url::parse(&format!(
+            "https://bitbucket.org/{}/{}/raw/{}/{}",
+            self.workspace,
+            self.name,
+            match url_type {
+                UrlType::Branch => &self.branch,
+                UrlType::Commit => &self.commit,
+            },
+            self.file_path.to_string_lossy()
+        ))?)
+    }
+
+    pub fn readme(&self, url_type: &UrlType) -> Result<Option<Url>> {
+        match bitbucket::api::get_readme_path(&self.workspace, &self.name, &self.commit)? {
+            Some(readme) => {
+                let mut bb_url = self.clone();
+                bb_url.file_path = readme;
+                Ok(Some(bb_url.to_typed_url(url_type)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Same as `GitHubUrl::wf_files`.
+    pub fn wf_files(
+        &self,
+        url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+        list_all_files: bool,
+    ) -> Result<Vec<metadata::types::File>> {
+        let files = if list_all_files || *wf_type == metadata::types::LanguageType::Unknown {
+            bitbucket::api::get_file_list_recursive(
+                &self.workspace,
+                &self.name,
+                self.file_path.parent().unwrap_or_else(|| Path::new("")),
+                &self.commit,
+            )?
+        } else {
+            inspect::dependency::discover(&self.file_path, wf_type, |path| {
+                let mut bb_url = self.clone();
+                bb_url.file_path = path.to_path_buf();
+                remote::fetch_raw_content(&bb_url.to_typed_url(&UrlType::Commit)?)
+            })
+        };
+        remote::to_wf_files(&self.file_path, &files, |file| {
+            let mut bb_url = self.clone();
+            bb_url.file_path = file.to_path_buf();
+            bb_url.to_typed_url(url_type)
+        })
+    }
+
+    /// Only the files under the parent of the workflow directory are listed to find the test files,
+    /// as Bitbucket has no API to list all files in the repository at once.
+    pub fn testing(
+        &self,
+        url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+    ) -> Result<Vec<metadata::types::Testing>> {
+        let repo_files = bitbucket::api::get_file_list_recursive(
+            &self.workspace,
+            &self.name,
+            self.file_path
+                .parent()
+                .and_then(|dir| dir.parent())
+                .unwrap_or_else(|| Path::new("")),
+            &self.commit,
+        )?;
+        let test_cases =
+            inspect::testing::discover(&self.file_path, wf_type, &repo_files, |path| {
+                let mut bb_url = self.clone();
+                bb_url.file_path = path.to_path_buf();
+                remote::fetch_raw_content(&bb_url.to_typed_url(&UrlType::Commit)?)
+            });
+        metadata::types::Testing::from_test_cases(&test_cases, |path| {
+            let mut bb_url = self.clone();
+            bb_url.file_path = path.to_path_buf();
+            bb_url.to_typed_url(url_type)
+        })
+    }
+}
+
+/// Bitbucket URLs of repository files contain `/src/` or `/raw/`.
+pub fn is_bitbucket_url(url: &Url) -> bool {
+    parse_url(url).is_ok()
+}
+
+/// Return: (workspace, name, branch_or_commit, file_path)
+fn parse_url(url: &Url) -> Result<(String, String, String, PathBuf)> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("Invalid URL: {}", url))?;
+    ensure!(host == "bitbucket.org", "Host {} is not supported", host);
+    let path_segments = url
+        .path_segments()
+        .ok_or_else(|| anyhow!("No path segments in URL"))?
+        .collect::<Vec<_>>();
+    let workspace = path_segments
+        .first()
+        .ok_or_else(|| anyhow!("No workspace in URL"))?
+        .to_string();
+    let name = path_segments
+        .get(1)
+        .ok_or_else(|| anyhow!("No repo name in URL"))?
+        .to_string();
+    ensure!(
+        matches!(path_segments.get(2), Some(&"src") | Some(&"raw")),
+        "Not a Bitbucket file URL: {}",
+        url
+    );
+    let branch_or_commit = path_segments
+        .get(3)
+        .ok_or_else(|| anyhow!("No branch or commit in URL"))?
+        .to_string();
+    let file_path: PathBuf = path_segments.into_iter().skip(4).collect();
+    ensure!(
+        !file_path.as_os_str().is_empty(),
+        "No file path in URL: {}",
+        url
+    );
+    Ok((workspace, name, branch_or_commit, file_path))
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() -> Result<()> {
+        let expect = (
+            "workspace".to_string(),
+            "repo".to_string(),
+            "main".to_string(),
+            PathBuf::from("path/to/workflow.cwl"),
+        );
+        let url = Url::parse("https://bitbucket.org/workspace/repo/src/main/path/to/workflow.cwl")?;
+        assert_eq!(parse_url(&url)?, expect);
+        let url = Url::parse("https://bitbucket.org/workspace/repo/raw/main/path/to/workflow.cwl")?;
+        assert_eq!(parse_url(&url)?, expect);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_url_invalid() -> Result<()> {
+        for url in [
+            "https://example.com/workspace/repo/src/main/workflow.cwl",
+            "https://bitbucket.org/workspace/repo",
+            "https://bitbucket.org/workspace/repo/commits/main/workflow.cwl",
+            "https://bitbucket.org/workspace/repo/src/main",
+        ] {
+            assert!(parse_url(&Url::parse(url)?).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_typed_url() -> Result<()> {
+        let bb_url = BitbucketUrl {
+            workspace: "workspace".to_string(),
+            name: "repo".to_string(),
+            branch: "main".to_string(),
+            commit: "f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9".to_string(),
+            file_path: PathBuf::from("path/to/workflow.cwl"),
+            ori_url_type: UrlType::Commit,
+        };
+        assert_eq!(
+            bb_url.to_url()?,
+            Url::parse("https://bitbucket.org/workspace/repo/raw/f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9/path/to/workflow.cwl")?
+        );
+        assert_eq!(
+            bb_url.to_typed_url(&UrlType::Branch)?,
+            Url::parse("https://bitbucket.org/workspace/repo/raw/main/path/to/workflow.cwl")?
+        );
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
 use crate::gh;
+use crate::inspect;
 use crate::metadata;
+use crate::remote;
 
 use anyhow::{anyhow, ensure, Result};
 use regex::Regex;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use url::Url;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -131,40 +133,74 @@ impl GitHubUrl {
         readme_remote.to_typed_url(url_type)
     }
 
+    /// By default, only the files that the primary workflow depends on are listed.
+    /// With `list_all_files`, all files in the directory of the primary workflow are listed.
     pub fn wf_files(
         &self,
         gh_token: impl AsRef<str>,
         url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+        list_all_files: bool,
     ) -> Result<Vec<metadata::types::File>> {
-        let primary_wf_url = self.to_typed_url(url_type)?;
-        let path_parent = self.file_path.parent().ok_or_else(|| {
+        let files = if list_all_files || *wf_type == metadata::types::LanguageType::Unknown {
+            gh::api::get_file_list_recursive(
+                &gh_token,
+                &self.owner,
+                &self.name,
+                self.parent()?,
+                &self.commit,
+            )?
+        } else {
+            inspect::dependency::discover(&self.file_path, wf_type, |path| {
+                let mut gh_url = self.clone();
+                gh_url.file_path = path.to_path_buf();
+                remote::fetch_raw_content(&gh_url.to_typed_url(&UrlType::Commit)?)
+            })
+        };
+        self.to_wf_files(&files, url_type)
+    }
+
+    pub fn testing(
+        &self,
+        gh_token: impl AsRef<str>,
+        url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+    ) -> Result<Vec<metadata::types::Testing>> {
+        let repo_files =
+            gh::api::get_tree_recursive(&gh_token, &self.owner, &self.name, &self.commit)?;
+        let test_cases =
+            inspect::testing::discover(&self.file_path, wf_type, &repo_files, |path| {
+                let mut gh_url = self.clone();
+                gh_url.file_path = path.to_path_buf();
+                remote::fetch_raw_content(&gh_url.to_typed_url(&UrlType::Commit)?)
+            });
+        metadata::types::Testing::from_test_cases(&test_cases, |path| {
+            let mut gh_url = self.clone();
+            gh_url.file_path = path.to_path_buf();
+            gh_url.to_typed_url(url_type)
+        })
+    }
+
+    fn parent(&self) -> Result<&Path> {
+        self.file_path.parent().ok_or_else(|| {
             anyhow!(
                 "No parent path in file path: {}",
                 self.file_path.to_string_lossy()
             )
-        })?;
-        let files = gh::api::get_file_list_recursive(
-            &gh_token,
-            &self.owner,
-            &self.name,
-            path_parent,
-            &self.commit,
-        )?;
-        files
-            .iter()
-            .map(|file| -> Result<metadata::types::File> {
-                let mut gh_url = self.clone();
-                gh_url.file_path = file.to_path_buf();
-                let url = gh_url.to_typed_url(url_type)?;
-                let target = file.strip_prefix(&path_parent)?;
-                let r#type = if primary_wf_url == url {
-                    metadata::types::FileType::Primary
-                } else {
-                    metadata::types::FileType::Secondary
-                };
-                metadata::types::File::new(&url, &Some(target.to_path_buf()), r#type)
-            })
-            .collect::<Result<Vec<_>>>()
+        })
+    }
+
+    /// See `remote::to_wf_files`.
+    pub fn to_wf_files(
+        &self,
+        files: &[PathBuf],
+        url_type: &UrlType,
+    ) -> Result<Vec<metadata::types::File>> {
+        remote::to_wf_files(&self.file_path, files, |file| {
+            let mut gh_url = self.clone();
+            gh_url.file_path = file.to_path_buf();
+            gh_url.to_typed_url(url_type)
+        })
     }
 }
 
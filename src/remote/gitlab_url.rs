@@ -0,0 +1,267 @@
+use crate::gitlab;
+use crate::inspect;
+use crate::metadata;
+use crate::remote;
+use crate::remote::gh_url::is_commit_hash;
+use crate::remote::UrlType;
+
+use anyhow::{anyhow, ensure, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GitLabUrl {
+    pub host: String,
+    /// Full path of the project, e.g., `group/subgroup/project`
+    pub project: String,
+    pub branch: String,
+    pub commit: String,
+    pub file_path: PathBuf,
+    pub ori_url_type: UrlType,
+}
+
+impl GitLabUrl {
+    /// Parse the workflow location.
+    /// The host can be gitlab.com or a self-managed GitLab instance.
+    /// The workflow location should be in the format of:
+    ///
+    /// - https://<host>/<namespace>/<project>/-/blob/<branch>/<path_to_file>
+    /// - https://<host>/<namespace>/<project>/-/blob/<commit>/<path_to_file>
+    /// - https://<host>/<namespace>/<project>/-/raw/<branch>/<path_to_file>
+    /// - https://<host>/<namespace>/<project>/-/raw/<commit>/<path_to_file>
+    ///
+    /// `<namespace>` can contain subgroups.
+    pub fn new(
+        url: &Url,
+        branch_memo: Option<&mut HashMap<String, String>>,
+        commit_memo: Option<&mut HashMap<String, String>>,
+    ) -> Result<Self> {
+        let (host, project, branch_or_commit, file_path) = parse_url(url)?;
+        let (branch, commit, ori_url_type) = match is_commit_hash(&branch_or_commit)? {
+            true => {
+                let branch = gitlab::api::get_default_branch(&host, &project, branch_memo)?;
+                (branch, branch_or_commit, UrlType::Commit)
+            }
+            false => {
+                let commit = gitlab::api::get_latest_commit_sha(
+                    &host,
+                    &project,
+                    &branch_or_commit,
+                    commit_memo,
+                )?;
+                (branch_or_commit, commit, UrlType::Branch)
+            }
+        };
+
+        Ok(Self {
+            host,
+            project,
+            branch,
+            commit,
+            file_path,
+            ori_url_type,
+        })
+    }
+
+    /// default: UrlType::Branch
+    pub fn to_url(&self) -> Result<Url> {
+        self.to_typed_url(&self.ori_url_type)
+    }
+
+    /// UrlType::Branch
+    /// -> https://<host>/<project>/-/raw/<branch>/<path_to_file>
+    /// UrlType::Commit
+    /// -> https://<host>/<project>/-/raw/<commit>/<path_to_file>
+    pub fn to_typed_url(&self, url_type: &UrlType) -> Result<Url> {
+        Ok(Url::parse(&format!(
+            "https://{}/{}/-/raw/{}/{}",
+            self.host,
+            self.project,
+            match url_type {
+                UrlType::Branch => &self.branch,
+                UrlType::Commit => &self.commit,
+            },
+            self.file_path.to_string_lossy()
+        ))?)
+    }
+
+    pub fn readme(&self, url_type: &UrlType) -> Result<Option<Url>> {
+        match gitlab::api::get_readme_url(&self.host, &self.project)? {
+            Some(readme_url) => {
+                let (_, _, _, file_path) = parse_url(&readme_url)?;
+                let mut gl_url = self.clone();
+                gl_url.file_path = file_path;
+                Ok(Some(gl_url.to_typed_url(url_type)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Same as `GitHubUrl::wf_files`.
+    pub fn wf_files(
+        &self,
+        url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+        list_all_files: bool,
+    ) -> Result<Vec<metadata::types::File>> {
+        let files = if list_all_files || *wf_type == metadata::types::LanguageType::Unknown {
+            gitlab::api::get_file_list_recursive(
+                &self.host,
+                &self.project,
+                self.file_path.parent().unwrap_or_else(|| Path::new("")),
+                &self.commit,
+            )?
+        } else {
+            inspect::dependency::discover(&self.file_path, wf_type, |path| {
+                let mut gl_url = self.clone();
+                gl_url.file_path = path.to_path_buf();
+                remote::fetch_raw_content(&gl_url.to_typed_url(&UrlType::Commit)?)
+            })
+        };
+        remote::to_wf_files(&self.file_path, &files, |file| {
+            let mut gl_url = self.clone();
+            gl_url.file_path = file.to_path_buf();
+            gl_url.to_typed_url(url_type)
+        })
+    }
+
+    pub fn testing(
+        &self,
+        url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+    ) -> Result<Vec<metadata::types::Testing>> {
+        let repo_files =
+            gitlab::api::get_file_list_recursive(&self.host, &self.project, "", &self.commit)?;
+        let test_cases =
+            inspect::testing::discover(&self.file_path, wf_type, &repo_files, |path| {
+                let mut gl_url = self.clone();
+                gl_url.file_path = path.to_path_buf();
+                remote::fetch_raw_content(&gl_url.to_typed_url(&UrlType::Commit)?)
+            });
+        metadata::types::Testing::from_test_cases(&test_cases, |path| {
+            let mut gl_url = self.clone();
+            gl_url.file_path = path.to_path_buf();
+            gl_url.to_typed_url(url_type)
+        })
+    }
+}
+
+/// GitLab URLs of repository files contain `/-/blob/` or `/-/raw/`.
+/// Other pages (e.g., the repository page) are not file URLs, even on gitlab.com.
+pub fn is_gitlab_url(url: &Url) -> bool {
+    parse_url(url).is_ok()
+}
+
+/// Return: (host, project, branch_or_commit, file_path)
+fn parse_url(url: &Url) -> Result<(String, String, String, PathBuf)> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("Invalid URL: {}", url))?
+        .to_string();
+    let path_segments = url
+        .path_segments()
+        .ok_or_else(|| anyhow!("No path segments in URL"))?
+        .collect::<Vec<_>>();
+    let separator = path_segments
+        .iter()
+        .position(|segment| *segment == "-")
+        .ok_or_else(|| anyhow!("Not a GitLab file URL: {}", url))?;
+    ensure!(separator >= 2, "No project path in URL: {}", url);
+    ensure!(
+        matches!(
+            path_segments.get(separator + 1),
+            Some(&"blob") | Some(&"raw")
+        ),
+        "Not a GitLab file URL: {}",
+        url
+    );
+    let project = path_segments[..separator].join("/");
+    let branch_or_commit = path_segments
+        .get(separator + 2)
+        .ok_or_else(|| anyhow!("No branch or commit in URL"))?
+        .to_string();
+    let file_path: PathBuf = path_segments.into_iter().skip(separator + 3).collect();
+    ensure!(
+        !file_path.as_os_str().is_empty(),
+        "No file path in URL: {}",
+        url
+    );
+    Ok((host, project, branch_or_commit, file_path))
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() -> Result<()> {
+        let url = Url::parse("https://gitlab.com/group/project/-/blob/main/path/to/workflow.cwl")?;
+        assert_eq!(
+            parse_url(&url)?,
+            (
+                "gitlab.com".to_string(),
+                "group/project".to_string(),
+                "main".to_string(),
+                PathBuf::from("path/to/workflow.cwl")
+            )
+        );
+
+        let url = Url::parse(
+            "https://git.example.org/group/subgroup/project/-/raw/f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9/workflow.cwl",
+        )?;
+        assert_eq!(
+            parse_url(&url)?,
+            (
+                "git.example.org".to_string(),
+                "group/subgroup/project".to_string(),
+                "f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9".to_string(),
+                PathBuf::from("workflow.cwl")
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_url_invalid() -> Result<()> {
+        for url in [
+            "https://git.example.org/group/project/blob/main/workflow.cwl",
+            "https://git.example.org/project/-/blob/main/workflow.cwl",
+            "https://git.example.org/group/project/-/tree/main/dir",
+            "https://git.example.org/group/project/-/blob/main",
+        ] {
+            assert!(parse_url(&Url::parse(url)?).is_err());
+        }
+        assert!(!is_gitlab_url(&Url::parse(
+            "https://example.com/path/to/file"
+        )?));
+        assert!(is_gitlab_url(&Url::parse(
+            "https://git.example.org/group/project/-/blob/main/workflow.cwl"
+        )?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_typed_url() -> Result<()> {
+        let gl_url = GitLabUrl {
+            host: "git.example.org".to_string(),
+            project: "group/subgroup/project".to_string(),
+            branch: "main".to_string(),
+            commit: "f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9".to_string(),
+            file_path: PathBuf::from("path/to/workflow.cwl"),
+            ori_url_type: UrlType::Branch,
+        };
+        assert_eq!(
+            gl_url.to_url()?,
+            Url::parse(
+                "https://git.example.org/group/subgroup/project/-/raw/main/path/to/workflow.cwl"
+            )?
+        );
+        assert_eq!(
+            gl_url.to_typed_url(&UrlType::Commit)?,
+            Url::parse("https://git.example.org/group/subgroup/project/-/raw/f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9f9/path/to/workflow.cwl")?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,419 @@
+use crate::inspect;
+use crate::metadata;
+use crate::remote::{GitHubUrl, UrlType};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use colored::Colorize;
+use log::{debug, warn};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use url::Url;
+
+/// A workflow file in a local clone of a GitHub repository.
+/// The files are referred to by the raw GitHub URLs of the enclosing repository,
+/// so they have to be committed and pushed before the metadata file is used.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LocalPath {
+    pub repo_root: PathBuf,
+    pub gh_url: GitHubUrl,
+}
+
+impl LocalPath {
+    /// `path`: local path to a file in a git repository whose remote is on GitHub.
+    /// The remote and branch are taken from the upstream of the current branch (default: `origin`).
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path
+            .as_ref()
+            .canonicalize()
+            .with_context(|| format!("No such file: {}", path.as_ref().display()))?;
+        ensure!(path.is_file(), "{} is not a file", path.display());
+        let dir = path
+            .parent()
+            .ok_or_else(|| anyhow!("No parent directory: {}", path.display()))?;
+        let repo_root = PathBuf::from(
+            git(dir, &["rev-parse", "--show-toplevel"])
+                .with_context(|| format!("{} is not in a git repository", path.display()))?,
+        )
+        .canonicalize()?;
+        let file_path = path.strip_prefix(&repo_root)?.to_path_buf();
+
+        let commit = git(&repo_root, &["rev-parse", "HEAD"])?;
+        let local_branch = git(&repo_root, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+        let (remote_name, branch) = match git(
+            &repo_root,
+            &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+        ) {
+            // e.g., `origin/main`
+            Ok(upstream) => match upstream.split_once('/') {
+                Some((remote_name, branch)) => (remote_name.to_string(), branch.to_string()),
+                None => ("origin".to_string(), local_branch),
+            },
+            Err(_) => ("origin".to_string(), local_branch),
+        };
+        let branch = match branch.as_str() {
+            "HEAD" => {
+                warn!(
+                    "{}: HEAD is detached, so commit {} is used instead of a branch name",
+                    "Warning".yellow(),
+                    commit
+                );
+                commit.clone()
+            }
+            _ => branch,
+        };
+        let remote_url =
+            git(&repo_root, &["remote", "get-url", &remote_name]).with_context(|| {
+                format!("No git remote `{}` in {}", remote_name, repo_root.display())
+            })?;
+        let (owner, name) = parse_github_remote(&remote_url)?;
+        debug!(
+            "local repository: {}, remote: {}/{}, branch: {}, commit: {}",
+            repo_root.display(),
+            owner,
+            name,
+            branch,
+            commit
+        );
+
+        let local_path = Self {
+            repo_root,
+            gh_url: GitHubUrl {
+                owner,
+                name,
+                branch,
+                commit,
+                file_path,
+                ori_url_type: UrlType::Branch,
+            },
+        };
+        local_path.warn_unpublished_changes()?;
+        Ok(local_path)
+    }
+
+    /// Directory of the workflow file, relative to the repository root.
+    fn parent(&self) -> Result<&Path> {
+        self.gh_url.file_path.parent().ok_or_else(|| {
+            anyhow!(
+                "No parent path in file path: {}",
+                self.gh_url.file_path.display()
+            )
+        })
+    }
+
+    /// Changes that are not committed or pushed are not available at the raw GitHub URLs.
+    fn warn_unpublished_changes(&self) -> Result<()> {
+        let pathspec = pathspec(self.parent()?);
+        let status = git(&self.repo_root, &["status", "--porcelain", "--", &pathspec])?;
+        if !status.is_empty() {
+            warn!(
+                "{}: There are uncommitted changes in {}, they are not included in the generated URLs:\n{}",
+                "Warning".yellow(),
+                self.repo_root.join(self.parent()?).display(),
+                status
+            );
+        }
+        let remote_branches = git(
+            &self.repo_root,
+            &["branch", "--remotes", "--contains", &self.gh_url.commit],
+        )?;
+        if remote_branches.is_empty() {
+            warn!(
+                "{}: Commit {} is not pushed yet, please push it before using the generated URLs",
+                "Warning".yellow(),
+                self.gh_url.commit
+            );
+        }
+        Ok(())
+    }
+
+    pub fn file_prefix(&self) -> Result<String> {
+        let name = self
+            .gh_url
+            .file_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow!("No file name"))?;
+        Ok(name
+            .split('.')
+            .next()
+            .ok_or_else(|| anyhow!("No file name"))?
+            .to_string())
+    }
+
+    pub fn content(&self) -> Result<String> {
+        let path = self.repo_root.join(&self.gh_url.file_path);
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    /// README in the repository root, like `GitHubUrl::readme`.
+    pub fn readme(&self, url_type: &UrlType) -> Result<Option<Url>> {
+        let mut readmes = git(
+            &self.repo_root,
+            &["ls-files", "-z", "--", ":(top,icase)readme*"],
+        )?
+        .split('\0')
+        .filter(|f| !f.is_empty() && !f.contains('/'))
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+        readmes.sort();
+        match readmes.into_iter().next() {
+            Some(readme) => {
+                let mut gh_url = self.gh_url.clone();
+                gh_url.file_path = readme;
+                Ok(Some(gh_url.to_typed_url(url_type)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Like `GitHubUrl::wf_files`, but the files are read from the local repository.
+    /// With `list_all_files`, the files tracked by git in the directory of the primary workflow are listed.
+    pub fn wf_files(
+        &self,
+        url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+        list_all_files: bool,
+    ) -> Result<Vec<metadata::types::File>> {
+        let files = if list_all_files || *wf_type == metadata::types::LanguageType::Unknown {
+            git(
+                &self.repo_root,
+                &["ls-files", "-z", "--", &pathspec(self.parent()?)],
+            )?
+            .split('\0')
+            .filter(|f| !f.is_empty())
+            .map(PathBuf::from)
+            .collect()
+        } else {
+            inspect::dependency::discover(&self.gh_url.file_path, wf_type, |path| {
+                Ok(fs::read_to_string(self.repo_root.join(path))?)
+            })
+        };
+        self.gh_url.to_wf_files(&files, url_type)
+    }
+
+    /// `path`: path relative to the repository root
+    pub fn read_repo_file(&self, path: &Path) -> Result<String> {
+        Ok(fs::read_to_string(self.repo_root.join(path))?)
+    }
+
+    /// Like `GitHubUrl::testing`, but the files are read from the local repository.
+    pub fn testing(
+        &self,
+        url_type: &UrlType,
+        wf_type: &metadata::types::LanguageType,
+    ) -> Result<Vec<metadata::types::Testing>> {
+        let repo_files = git(&self.repo_root, &["ls-files", "-z"])?
+            .split('\0')
+            .filter(|f| !f.is_empty())
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+        let test_cases =
+            inspect::testing::discover(&self.gh_url.file_path, wf_type, &repo_files, |path| {
+                Ok(fs::read_to_string(self.repo_root.join(path))?)
+            });
+        metadata::types::Testing::from_test_cases(&test_cases, |path| {
+            let mut gh_url = self.gh_url.clone();
+            gh_url.file_path = path.to_path_buf();
+            gh_url.to_typed_url(url_type)
+        })
+    }
+}
+
+/// `git ls-files -- ""` is not allowed, so use `.` for the repository root.
+fn pathspec(path: &Path) -> String {
+    match path.to_string_lossy().as_ref() {
+        "" => ".".to_string(),
+        path => path.to_string(),
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Please make sure that the git command is present in your PATH")?;
+    ensure!(
+        output.status.success(),
+        "Failed to run `git {}`: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(String::from_utf8(output.stdout)?.trim_end().to_string())
+}
+
+/// Possible remote URL:
+///   - https://github.com/<owner>/<name>(.git)
+///   - git@github.com:<owner>/<name>(.git)
+///   - ssh://git@github.com/<owner>/<name>(.git)
+///
+/// Return: (owner, name)
+pub fn parse_github_remote(remote_url: impl AsRef<str>) -> Result<(String, String)> {
+    let re =
+        Regex::new(r"^(?:https://|ssh://git@|git@)github\.com[/:]([^/]+)/([^/]+?)(?:\.git)?/?$")?;
+    match re.captures(remote_url.as_ref()) {
+        Some(caps) => Ok((caps[1].to_string(), caps[2].to_string())),
+        None => bail!(
+            "Git remote {} is not a GitHub repository",
+            remote_url.as_ref()
+        ),
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    fn init_repo(dir: &Path) -> Result<()> {
+        for args in [
+            vec!["init", "--quiet", "--initial-branch", "main"],
+            vec![
+                "remote",
+                "add",
+                "origin",
+                "git@github.com:sapporo-wes/yevis-cli.git",
+            ],
+        ] {
+            git(dir, &args)?;
+        }
+        fs::create_dir_all(dir.join("wf/tools"))?;
+        fs::create_dir_all(dir.join("shared"))?;
+        fs::write(dir.join("README.md"), "# test")?;
+        fs::write(
+            dir.join("wf/main.cwl"),
+            "cwlVersion: v1.2\nsteps:\n  a:\n    run: ../shared/dep.cwl\n",
+        )?;
+        fs::write(dir.join("shared/dep.cwl"), "cwlVersion: v1.2")?;
+        fs::write(dir.join("wf/tools/tool.cwl"), "cwlVersion: v1.2")?;
+        fs::create_dir_all(dir.join("test"))?;
+        fs::write(dir.join("test/wf_params.json"), r#"{"input": "input.txt"}"#)?;
+        fs::write(dir.join("test/input.txt"), "")?;
+        fs::write(dir.join("untracked.txt"), "")?;
+        git(dir, &["add", "README.md", "wf", "shared", "test"])?;
+        git(
+            dir,
+            &[
+                "-c",
+                "user.name=yevis",
+                "-c",
+                "user.email=yevis@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                "init",
+            ],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_github_remote() -> Result<()> {
+        let expect = ("sapporo-wes".to_string(), "yevis-cli".to_string());
+        assert_eq!(
+            parse_github_remote("https://github.com/sapporo-wes/yevis-cli.git")?,
+            expect
+        );
+        assert_eq!(
+            parse_github_remote("https://github.com/sapporo-wes/yevis-cli")?,
+            expect
+        );
+        assert_eq!(
+            parse_github_remote("git@github.com:sapporo-wes/yevis-cli.git")?,
+            expect
+        );
+        assert_eq!(
+            parse_github_remote("ssh://git@github.com/sapporo-wes/yevis-cli.git")?,
+            expect
+        );
+        assert!(parse_github_remote("https://gitlab.com/sapporo-wes/yevis-cli.git").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_path() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        init_repo(temp_dir.path())?;
+        let local_path = LocalPath::new(temp_dir.path().join("wf/main.cwl"))?;
+        let commit = git(temp_dir.path(), &["rev-parse", "HEAD"])?;
+        assert_eq!(local_path.gh_url.owner, "sapporo-wes");
+        assert_eq!(local_path.gh_url.name, "yevis-cli");
+        assert_eq!(local_path.gh_url.branch, "main");
+        assert_eq!(local_path.gh_url.commit, commit);
+        assert_eq!(local_path.gh_url.file_path, PathBuf::from("wf/main.cwl"));
+        assert_eq!(local_path.file_prefix()?, "main");
+
+        assert_eq!(
+            local_path.readme(&UrlType::Branch)?,
+            Some(Url::parse(
+                "https://raw.githubusercontent.com/sapporo-wes/yevis-cli/main/README.md"
+            )?)
+        );
+
+        let files =
+            local_path.wf_files(&UrlType::Commit, &metadata::types::LanguageType::Cwl, true)?;
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            files[0].url,
+            Url::parse(&format!(
+                "https://raw.githubusercontent.com/sapporo-wes/yevis-cli/{}/wf/main.cwl",
+                commit
+            ))?
+        );
+        assert_eq!(files[0].target, Some(PathBuf::from("main.cwl")));
+        assert!(files[0].is_primary());
+        assert_eq!(files[1].target, Some(PathBuf::from("tools/tool.cwl")));
+        assert!(!files[1].is_primary());
+
+        let files =
+            local_path.wf_files(&UrlType::Branch, &metadata::types::LanguageType::Cwl, false)?;
+        assert_eq!(
+            files
+                .iter()
+                .map(|f| (f.target.clone().unwrap(), f.is_primary()))
+                .collect::<Vec<_>>(),
+            vec![
+                (PathBuf::from("shared/dep.cwl"), false),
+                (PathBuf::from("wf/main.cwl"), true),
+            ]
+        );
+
+        let testing = local_path.testing(&UrlType::Branch, &metadata::types::LanguageType::Cwl)?;
+        assert_eq!(testing.len(), 1);
+        assert_eq!(testing[0].id, "test_1");
+        assert_eq!(
+            testing[0]
+                .files
+                .iter()
+                .map(|f| (f.url.as_str(), f.target.clone().unwrap(), f.r#type.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "https://raw.githubusercontent.com/sapporo-wes/yevis-cli/main/test/wf_params.json",
+                    PathBuf::from("wf_params.json"),
+                    metadata::types::TestFileType::WfParams
+                ),
+                (
+                    "https://raw.githubusercontent.com/sapporo-wes/yevis-cli/main/test/input.txt",
+                    PathBuf::from("input.txt"),
+                    metadata::types::TestFileType::Other
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_path_not_in_repo() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("main.cwl");
+        fs::write(&path, "cwlVersion: v1.2")?;
+        assert!(LocalPath::new(&path).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use crate::env;
+use crate::zenodo;
+
+use anyhow::{anyhow, Result};
+use url::Url;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ZenodoUrl {
+    pub url: Url,
+    pub host: String,
+    pub record_id: Option<u64>,
+    pub file_name: Option<String>,
+}
+
+impl ZenodoUrl {
+    /// Parse the location without accessing Zenodo.
+    /// The record ID and file name are extracted from the following formats:
+    ///
+    /// - https://zenodo.org/record/<record_id>
+    /// - https://zenodo.org/records/<record_id>
+    /// - https://zenodo.org/record/<record_id>/files/<file_name>
+    /// - https://zenodo.org/records/<record_id>/files/<file_name>
+    /// - https://zenodo.org/api/records/<record_id>/files/<file_name>/content
+    ///
+    /// Other URLs, e.g., https://zenodo.org/api/files/<bucket>/<file_name>, are treated as a single file.
+    pub fn new(url: &Url) -> Result<Self> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("Invalid URL: {}", url))?
+            .to_string();
+        let path_segments = url
+            .path_segments()
+            .ok_or_else(|| anyhow!("No path segments in URL"))?
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let path_segments = match path_segments.first() {
+            Some(&"api") => &path_segments[1..],
+            _ => &path_segments[..],
+        };
+        let (record_id, file_name) = match path_segments {
+            [kind, id, rest @ ..] if *kind == "record" || *kind == "records" => {
+                let record_id = id.parse::<u64>().ok();
+                let file_name = match rest {
+                    [files, file_name, ..] if *files == "files" => Some(file_name.to_string()),
+                    _ => None,
+                };
+                (record_id, file_name)
+            }
+            _ => (None, None),
+        };
+        Ok(Self {
+            url: url.clone(),
+            host,
+            record_id,
+            file_name,
+        })
+    }
+
+    pub fn to_url(&self) -> Result<Url> {
+        Ok(self.url.clone())
+    }
+
+    /// Published records are public, so the Zenodo token is optional.
+    pub fn record(&self) -> Result<zenodo::types::Record> {
+        let record_id = self
+            .record_id
+            .ok_or_else(|| anyhow!("No Zenodo record ID in URL: {}", self.url))?;
+        zenodo::api::get_record(
+            &self.host,
+            env::zenodo_token().unwrap_or_default(),
+            &record_id,
+        )
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zenodo_url() -> Result<()> {
+        for (url, record_id, file_name) in [
+            ("https://zenodo.org/record/1234", Some(1234), None),
+            ("https://sandbox.zenodo.org/records/1234/", Some(1234), None),
+            (
+                "https://zenodo.org/record/1234/files/main.cwl?download=1",
+                Some(1234),
+                Some("main.cwl"),
+            ),
+            (
+                "https://zenodo.org/api/records/1234/files/main.cwl/content",
+                Some(1234),
+                Some("main.cwl"),
+            ),
+            (
+                "https://zenodo.org/api/files/7d2e8fb4-5f5b-4e7b-9f6b-3b1b5e0d8f5a/main.cwl",
+                None,
+                None,
+            ),
+        ] {
+            let zenodo_url = ZenodoUrl::new(&Url::parse(url)?)?;
+            assert_eq!(zenodo_url.record_id, record_id);
+            assert_eq!(zenodo_url.file_name.as_deref(), file_name);
+            assert_eq!(zenodo_url.to_url()?.as_str(), url);
+        }
+        Ok(())
+    }
+}
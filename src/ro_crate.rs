@@ -0,0 +1,386 @@
+use crate::metadata;
+use crate::orcid;
+
+use anyhow::{anyhow, ensure, Result};
+use serde_json::{json, Map, Value};
+
+pub const METADATA_DESCRIPTOR_ID: &str = "ro-crate-metadata.json";
+
+/// Profiles of the Workflow Run RO-Crate, added to `conformsTo` of the root data entity.
+/// https://www.researchobject.org/workflow-run-crate/profiles/workflow_run_crate
+pub const WORKFLOW_RUN_CRATE_PROFILES: &[&str] = &[
+    "https://w3id.org/ro/wfrun/process/0.1",
+    "https://w3id.org/ro/wfrun/workflow/0.1",
+    "https://w3id.org/workflowhub/workflow-ro-crate/1.0",
+];
+
+/// Check the RO-Crate of a test run against the requirements of the Workflow Run RO-Crate profile:
+/// the RO-Crate 1.x context, the metadata descriptor, the root data entity,
+/// the main entity as a `ComputationalWorkflow`, and a `CreateAction` with the main entity as `instrument`.
+pub fn validate(ro_crate: &Value) -> Result<()> {
+    let mut problems = vec![];
+    match ro_crate.get("@context") {
+        Some(context) if is_ro_crate_context(context) => {}
+        Some(_) => problems.push("`@context` is not the RO-Crate 1.x context".to_string()),
+        None => problems.push("No `@context`".to_string()),
+    }
+    let graph = ro_crate
+        .get("@graph")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("The RO-Crate has no `@graph`"))?;
+    if graph.iter().any(|entity| id(entity).is_none()) {
+        problems.push("Entities without `@id` in `@graph`".to_string());
+    }
+
+    match root_id(graph) {
+        Ok(root_id) => match find(graph, root_id) {
+            Some(root) => {
+                if !has_type(root, "Dataset") {
+                    problems.push("The root data entity is not a `Dataset`".to_string());
+                }
+                match root.get("mainEntity").and_then(id) {
+                    Some(main_id) => {
+                        match find(graph, main_id) {
+                            Some(main) if has_type(main, "ComputationalWorkflow") => {}
+                            Some(_) => problems.push(format!(
+                                "The main entity {} is not a `ComputationalWorkflow`",
+                                main_id
+                            )),
+                            None => problems
+                                .push(format!("The main entity {} is not in `@graph`", main_id)),
+                        }
+                        if !graph.iter().any(|entity| {
+                            has_type(entity, "CreateAction")
+                                && ids(entity.get("instrument")).contains(&main_id)
+                        }) {
+                            problems.push(format!(
+                                "No `CreateAction` with the main entity {} as `instrument`",
+                                main_id
+                            ));
+                        }
+                    }
+                    None => problems.push("The root data entity has no `mainEntity`".to_string()),
+                }
+            }
+            None => problems.push(format!(
+                "The root data entity {} is not in `@graph`",
+                root_id
+            )),
+        },
+        Err(e) => problems.push(e.to_string()),
+    }
+
+    ensure!(
+        problems.is_empty(),
+        "The RO-Crate does not conform to the Workflow Run RO-Crate profile: {}",
+        problems.join("; ")
+    );
+    Ok(())
+}
+
+/// Add the Yevis metadata to the RO-Crate of a test run:
+/// the authors (identified by ORCID if any) and the license to the root data entity and the main entity
+/// in addition to the existing ones,
+/// the workflow ID, version and test ID as `identifier` of the root data entity,
+/// and the Workflow Run RO-Crate profiles to `conformsTo`.
+pub fn enrich(
+    mut ro_crate: Value,
+    meta: &metadata::types::Metadata,
+    test_id: impl AsRef<str>,
+) -> Result<Value> {
+    let graph = ro_crate
+        .get_mut("@graph")
+        .and_then(|v| v.as_array_mut())
+        .ok_or_else(|| anyhow!("The RO-Crate has no `@graph`"))?;
+    let root_id = root_id(graph)?.to_string();
+    let main_id = find(graph, &root_id)
+        .and_then(|root| root.get("mainEntity"))
+        .and_then(id)
+        .map(|id| id.to_string());
+
+    let mut author_refs = vec![];
+    for author in &meta.authors {
+        let person = person(author);
+        author_refs.push(json!({ "@id": person["@id"] }));
+        upsert(graph, person);
+    }
+
+    let license = license(&meta.license);
+    let license_id = license["@id"].clone();
+    upsert(graph, license);
+
+    let mut identifier_refs = vec![];
+    for (name, value) in [
+        ("yevis_workflow_id", meta.id.to_string()),
+        ("yevis_version", meta.version.clone()),
+        ("yevis_test_id", test_id.as_ref().to_string()),
+    ] {
+        let property_id = format!("#{}", name);
+        upsert(
+            graph,
+            json!({
+                "@id": property_id,
+                "@type": "PropertyValue",
+                "name": name,
+                "value": value,
+            }),
+        );
+        identifier_refs.push(json!({ "@id": property_id }));
+    }
+
+    for entity in graph.iter_mut() {
+        let entity_id = id(entity).map(|id| id.to_string());
+        let is_root = entity_id.as_deref() == Some(root_id.as_str());
+        let is_main = entity_id.is_some() && entity_id == main_id;
+        let entity = match entity.as_object_mut() {
+            Some(entity) if is_root || is_main => entity,
+            _ => continue,
+        };
+        merge(entity, "author", &author_refs);
+        merge(entity, "license", &[json!({ "@id": license_id })]);
+        if is_root {
+            merge(entity, "identifier", &identifier_refs);
+            merge(
+                entity,
+                "conformsTo",
+                &WORKFLOW_RUN_CRATE_PROFILES
+                    .iter()
+                    .map(|profile| json!({ "@id": profile }))
+                    .collect::<Vec<_>>(),
+            );
+        }
+        if is_main {
+            entity.insert("version".to_string(), json!(meta.version));
+            entity
+                .entry("name")
+                .or_insert_with(|| json!(meta.workflow.name));
+        }
+    }
+    Ok(ro_crate)
+}
+
+/// `Person` entity of the author, identified by ORCID if any, otherwise by the GitHub account,
+/// otherwise by the name in the RO-Crate.
+pub fn person(author: &metadata::types::Author) -> Value {
+    let author_id = match (&author.orcid, &author.github_account) {
+        (Some(orcid), _) if orcid::validate(orcid::normalize(orcid)).is_ok() => {
+            format!("https://orcid.org/{}", orcid::normalize(orcid))
+        }
+        (_, Some(github_account)) => format!("https://github.com/{}", github_account),
+        _ => format!(
+            "#{}",
+            author
+                .name
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '-' })
+                .collect::<String>()
+        ),
+    };
+    let mut person = json!({
+        "@id": author_id,
+        "@type": "Person",
+        "name": author.name,
+    });
+    if let Some(affiliation) = &author.affiliation {
+        person["affiliation"] = json!(affiliation);
+    }
+    person
+}
+
+/// `CreativeWork` entity of the SPDX license.
+pub fn license(license: impl AsRef<str>) -> Value {
+    json!({
+        "@id": format!("https://spdx.org/licenses/{}", license.as_ref()),
+        "@type": "CreativeWork",
+        "identifier": license.as_ref(),
+        "name": license.as_ref(),
+    })
+}
+
+/// `@context` is the RO-Crate 1.x context URL, or an array of contexts including it.
+fn is_ro_crate_context(context: &Value) -> bool {
+    let is_ro_crate_url = |url: &str| url.starts_with("https://w3id.org/ro/crate/1.");
+    match context {
+        Value::String(url) => is_ro_crate_url(url),
+        Value::Array(contexts) => contexts
+            .iter()
+            .any(|context| context.as_str().is_some_and(is_ro_crate_url)),
+        _ => false,
+    }
+}
+
+fn id(entity: &Value) -> Option<&str> {
+    entity.get("@id").and_then(|v| v.as_str())
+}
+
+/// `{"@id": ...}` or an array of them
+fn ids(value: Option<&Value>) -> Vec<&str> {
+    match value {
+        Some(Value::Array(values)) => values.iter().filter_map(id).collect(),
+        Some(value) => id(value).into_iter().collect(),
+        None => vec![],
+    }
+}
+
+fn has_type(entity: &Value, r#type: &str) -> bool {
+    match entity.get("@type") {
+        Some(Value::String(t)) => t == r#type,
+        Some(Value::Array(types)) => types.iter().any(|t| t.as_str() == Some(r#type)),
+        _ => false,
+    }
+}
+
+fn find<'a>(graph: &'a [Value], entity_id: &str) -> Option<&'a Value> {
+    graph.iter().find(|entity| id(entity) == Some(entity_id))
+}
+
+/// `about` of the metadata descriptor
+fn root_id(graph: &[Value]) -> Result<&str> {
+    find(graph, METADATA_DESCRIPTOR_ID)
+        .ok_or_else(|| anyhow!("No metadata descriptor `{}`", METADATA_DESCRIPTOR_ID))?
+        .get("about")
+        .and_then(id)
+        .ok_or_else(|| anyhow!("The metadata descriptor has no `about`"))
+}
+
+/// Add the properties missing in the entity with the same `@id`, or add the entity.
+/// The existing properties are kept.
+fn upsert(graph: &mut Vec<Value>, entity: Value) {
+    let existing = graph
+        .iter_mut()
+        .find(|e| id(e).is_some() && id(e) == id(&entity))
+        .and_then(|e| e.as_object_mut());
+    match (existing, entity) {
+        (Some(existing), Value::Object(entity)) => {
+            for (key, value) in entity {
+                existing.entry(key).or_insert(value);
+            }
+        }
+        (_, entity) => graph.push(entity),
+    }
+}
+
+/// Add the values to the property as an array, without duplicates.
+fn merge(entity: &mut Map<String, Value>, key: &str, values: &[Value]) {
+    let mut merged = match entity.remove(key) {
+        Some(Value::Array(existing)) => existing,
+        Some(existing) => vec![existing],
+        None => vec![],
+    };
+    for value in values {
+        if !merged.contains(value) {
+            merged.push(value.clone());
+        }
+    }
+    entity.insert(key.to_string(), Value::Array(merged));
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    fn ro_crate() -> Value {
+        json!({
+            "@context": "https://w3id.org/ro/crate/1.1/context",
+            "@graph": [
+                {
+                    "@id": "ro-crate-metadata.json",
+                    "@type": "CreativeWork",
+                    "about": {"@id": "./"},
+                    "conformsTo": {"@id": "https://w3id.org/ro/crate/1.1"}
+                },
+                {
+                    "@id": "./",
+                    "@type": "Dataset",
+                    "mainEntity": {"@id": "trimming_and_qc.cwl"}
+                },
+                {
+                    "@id": "trimming_and_qc.cwl",
+                    "@type": ["File", "SoftwareSourceCode", "ComputationalWorkflow"]
+                },
+                {
+                    "@id": "#run-1",
+                    "@type": "CreateAction",
+                    "instrument": {"@id": "trimming_and_qc.cwl"}
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_validate() -> Result<()> {
+        validate(&ro_crate())?;
+
+        let mut no_action = ro_crate();
+        no_action["@graph"].as_array_mut().unwrap().pop();
+        let err = validate(&no_action).unwrap_err().to_string();
+        assert!(err.contains("No `CreateAction` with the main entity trimming_and_qc.cwl"));
+
+        let mut not_workflow = ro_crate();
+        not_workflow["@graph"][2]["@type"] = json!("File");
+        assert!(validate(&not_workflow).is_err());
+
+        assert!(validate(&json!({"@context": "https://schema.org"})).is_err());
+
+        let mut context_array = ro_crate();
+        context_array["@context"] = json!([
+            "https://w3id.org/ro/crate/1.1/context",
+            {"@vocab": "http://schema.org/"}
+        ]);
+        validate(&context_array)?;
+
+        let mut context_in_value = ro_crate();
+        context_in_value["@context"] = json!([{"ro": "https://w3id.org/ro/crate/1.1/context"}]);
+        assert!(validate(&context_in_value).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_enrich() -> Result<()> {
+        let meta = metadata::io::test_metadata()?;
+        let ro_crate = enrich(ro_crate(), &meta, "test_1")?;
+        validate(&ro_crate)?;
+        let graph = ro_crate["@graph"].as_array().unwrap();
+
+        let root = find(graph, "./").unwrap();
+        assert_eq!(
+            root["license"][0]["@id"],
+            "https://spdx.org/licenses/CC0-1.0"
+        );
+        assert_eq!(
+            root["author"][0]["@id"],
+            "https://orcid.org/0000-0003-2765-0049"
+        );
+        assert_eq!(root["identifier"].as_array().unwrap().len(), 3);
+        assert_eq!(root["conformsTo"].as_array().unwrap().len(), 3);
+        assert_eq!(find(graph, "#yevis_test_id").unwrap()["value"], "test_1");
+
+        let main = find(graph, "trimming_and_qc.cwl").unwrap();
+        assert_eq!(main["version"], json!(meta.version));
+
+        // idempotent
+        let again = enrich(ro_crate.clone(), &meta, "test_1")?;
+        assert_eq!(again, ro_crate);
+
+        // the existing authors, license and properties are kept
+        let mut existing = self::ro_crate();
+        existing["@graph"][1]["license"] = json!({"@id": "https://spdx.org/licenses/MIT"});
+        existing["@graph"][1]["author"] = json!({"@id": "#someone"});
+        existing["@graph"].as_array_mut().unwrap().push(json!({
+            "@id": "https://orcid.org/0000-0003-2765-0049",
+            "@type": "Person",
+            "name": "Someone",
+            "email": "someone@example.com"
+        }));
+        let ro_crate = enrich(existing, &meta, "test_1")?;
+        let graph = ro_crate["@graph"].as_array().unwrap();
+        let root = find(graph, "./").unwrap();
+        assert_eq!(root["license"].as_array().unwrap().len(), 2);
+        assert_eq!(root["author"][0]["@id"], "#someone");
+        let person = find(graph, "https://orcid.org/0000-0003-2765-0049").unwrap();
+        assert_eq!(person["name"], "Someone");
+        assert_eq!(person["email"], "someone@example.com");
+        Ok(())
+    }
+}
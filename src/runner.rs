@@ -0,0 +1,124 @@
+pub mod local;
+
+use crate::metadata;
+use crate::wes;
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::str::FromStr;
+use url::Url;
+
+/// Where the test runs are executed.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RunnerType {
+    /// A WES, started as `sapporo-service` if `--wes-location` is not specified
+    Wes,
+    /// The engine CLIs on the host, without WES or Docker
+    Local,
+}
+
+impl RunnerType {
+    pub const VARIANTS: &'static [&'static str] = &["wes", "local"];
+}
+
+impl FromStr for RunnerType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "wes" => Ok(RunnerType::Wes),
+            "local" => Ok(RunnerType::Local),
+            _ => bail!("Unsupported runner: {}", s),
+        }
+    }
+}
+
+/// The operations of the test scheduler, in terms of WES.
+pub enum Runner {
+    Wes(wes::client::WesClient),
+    Local(local::LocalRunner),
+}
+
+impl Runner {
+    /// None for the local runner.
+    pub fn wes_loc(&self) -> Option<&Url> {
+        match self {
+            Self::Wes(client) => Some(client.wes_loc()),
+            Self::Local(_) => None,
+        }
+    }
+
+    /// Whether the default engines of the languages (e.g., `cwltool`) are available by these names.
+    pub fn knows_default_engines(&self) -> bool {
+        match self {
+            Self::Wes(client) => client.knows_default_engines(),
+            Self::Local(_) => true,
+        }
+    }
+
+    pub fn check_engines<'a>(
+        &self,
+        engines: impl IntoIterator<Item = &'a metadata::types::Engine>,
+    ) -> Result<()> {
+        match self {
+            Self::Wes(client) => client.check_engines(engines),
+            Self::Local(local) => local.check_engines(engines),
+        }
+    }
+
+    /// Return: run_id
+    pub fn post_run(
+        &self,
+        meta: &metadata::types::Metadata,
+        test_case: &metadata::types::Testing,
+        engine: &metadata::types::Engine,
+    ) -> Result<String> {
+        match self {
+            Self::Wes(client) => client.post_run(meta, test_case, engine),
+            Self::Local(local) => local.post_run(meta, test_case, engine),
+        }
+    }
+
+    pub fn get_run_status(&self, run_id: impl AsRef<str>) -> Result<wes::api::RunStatus> {
+        match self {
+            Self::Wes(client) => client.get_run_status(run_id),
+            Self::Local(local) => local.get_run_status(run_id),
+        }
+    }
+
+    pub fn get_run_log(&self, run_id: impl AsRef<str>) -> Result<Value> {
+        match self {
+            Self::Wes(client) => client.get_run_log(run_id),
+            Self::Local(local) => local.get_run_log(run_id),
+        }
+    }
+
+    pub fn cancel_run(&self, run_id: impl AsRef<str>) -> Result<()> {
+        match self {
+            Self::Wes(client) => client.cancel_run(run_id),
+            Self::Local(local) => local.cancel_run(run_id),
+        }
+    }
+
+    pub fn get_run_outputs(&self, run_log: &Value) -> Result<Vec<(String, Url)>> {
+        match self {
+            Self::Wes(client) => client.get_run_outputs(run_log),
+            Self::Local(local) => local.get_run_outputs(run_log),
+        }
+    }
+
+    /// Whether the runner generates the RO-Crate of a run.
+    pub fn provides_ro_crate(&self) -> bool {
+        match self {
+            Self::Wes(client) => client.provides_ro_crate(),
+            Self::Local(_) => false,
+        }
+    }
+
+    pub fn fetch_ro_crate(&self, run_id: impl AsRef<str>) -> Result<Value> {
+        match self {
+            Self::Wes(client) => client.fetch_ro_crate(run_id),
+            Self::Local(_) => bail!("The local runner does not generate the RO-Crate of a run"),
+        }
+    }
+}
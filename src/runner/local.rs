@@ -0,0 +1,453 @@
+use crate::metadata;
+use crate::wes;
+
+use anyhow::{anyhow, bail, ensure, Result};
+use log::debug;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use url::Url;
+use uuid::Uuid;
+
+/// Runs the tests with the engine CLIs (e.g., `cwltool`, `nextflow`) on the host.
+/// Each run is staged in `<run_dir>/<run_id>/exe` with the `target` paths of the files,
+/// and its stdout and stderr are written next to the `exe` directory.
+#[derive(Debug)]
+pub struct LocalRunner {
+    run_dir: PathBuf,
+    runs: RefCell<HashMap<String, LocalRun>>,
+}
+
+#[derive(Debug)]
+struct LocalRun {
+    child: Child,
+    cmd: Vec<String>,
+    exe_dir: PathBuf,
+    /// Staged inputs, not listed as outputs
+    staged: HashSet<PathBuf>,
+    exit_code: Option<i32>,
+    canceled: bool,
+}
+
+/// Directories used by the engines for intermediate files, not listed as outputs.
+const ENGINE_WORK_DIRS: &[&str] = &["work", "cromwell-workflow-logs"];
+
+impl LocalRunner {
+    pub fn new(run_dir: impl AsRef<Path>) -> Result<Self> {
+        fs::create_dir_all(run_dir.as_ref())?;
+        Ok(Self {
+            run_dir: run_dir.as_ref().canonicalize()?,
+            runs: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Check that the engine CLIs are installed, and their versions if specified.
+    pub fn check_engines<'a>(
+        &self,
+        engines: impl IntoIterator<Item = &'a metadata::types::Engine>,
+    ) -> Result<()> {
+        let unsupported = engines
+            .into_iter()
+            .filter(|engine| {
+                let program = match engine_program(&engine.name) {
+                    Ok(program) => program,
+                    Err(_) => return true,
+                };
+                match Command::new(program).arg("--version").output() {
+                    Ok(output) => match &engine.version {
+                        Some(version) => {
+                            !String::from_utf8_lossy(&output.stdout).contains(version.as_str())
+                                && !String::from_utf8_lossy(&output.stderr)
+                                    .contains(version.as_str())
+                        }
+                        None => false,
+                    },
+                    Err(_) => true,
+                }
+            })
+            .map(|engine| engine.to_string())
+            .collect::<Vec<_>>();
+        ensure!(
+            unsupported.is_empty(),
+            "The engines are not installed on the host: {} (supported: {})",
+            unsupported.join(", "),
+            SUPPORTED_ENGINES.join(", ")
+        );
+        Ok(())
+    }
+
+    /// Return: run_id
+    pub fn post_run(
+        &self,
+        meta: &metadata::types::Metadata,
+        test_case: &metadata::types::Testing,
+        engine: &metadata::types::Engine,
+    ) -> Result<String> {
+        let run_id = Uuid::new_v4().to_string();
+        let exe_dir = self.run_dir.join(&run_id).join("exe");
+        fs::create_dir_all(&exe_dir)?;
+
+        let mut staged = HashSet::new();
+        for (target, url) in meta
+            .workflow
+            .files
+            .iter()
+            .map(|f| (&f.target, &f.url))
+            .chain(
+                test_case
+                    .files
+                    .iter()
+                    .filter(|f| f.r#type == metadata::types::TestFileType::Other)
+                    .map(|f| (&f.target, &f.url)),
+            )
+        {
+            let target = target
+                .as_ref()
+                .ok_or_else(|| anyhow!("No target for the file {}", url))?;
+            stage_file(&exe_dir, target, &wes::api::download_file(url)?)?;
+            staged.insert(target.clone());
+        }
+        stage_file(
+            &exe_dir,
+            Path::new(WF_PARAMS),
+            test_case.wf_params()?.as_bytes(),
+        )?;
+        staged.insert(PathBuf::from(WF_PARAMS));
+
+        let primary_wf = meta.workflow.primary_wf()?;
+        let primary_wf_target = primary_wf
+            .target
+            .as_ref()
+            .ok_or_else(|| anyhow!("No target for the primary workflow"))?;
+        let engine_params = engine_params_to_args(&test_case.wf_engine_params()?)?;
+        let cmd = engine_command(engine, primary_wf_target, &engine_params)?;
+        debug!("Running `{}` in {}", cmd.join(" "), exe_dir.display());
+
+        let child = Command::new(&cmd[0])
+            .args(&cmd[1..])
+            .current_dir(&exe_dir)
+            .stdin(Stdio::null())
+            .stdout(fs::File::create(
+                self.run_dir.join(&run_id).join("stdout.log"),
+            )?)
+            .stderr(fs::File::create(
+                self.run_dir.join(&run_id).join("stderr.log"),
+            )?)
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run `{}` with error: {}", cmd[0], e))?;
+        self.runs.borrow_mut().insert(
+            run_id.clone(),
+            LocalRun {
+                child,
+                cmd,
+                exe_dir,
+                staged,
+                exit_code: None,
+                canceled: false,
+            },
+        );
+        Ok(run_id)
+    }
+
+    pub fn get_run_status(&self, run_id: impl AsRef<str>) -> Result<wes::api::RunStatus> {
+        let mut runs = self.runs.borrow_mut();
+        let run = runs
+            .get_mut(run_id.as_ref())
+            .ok_or_else(|| anyhow!("No local run: {}", run_id.as_ref()))?;
+        if run.exit_code.is_none() {
+            if let Some(status) = run.child.try_wait()? {
+                // killed by a signal if no exit code
+                run.exit_code = Some(status.code().unwrap_or(-1));
+            }
+        }
+        Ok(match (run.exit_code, run.canceled) {
+            (_, true) => wes::api::RunStatus::Failed,
+            (None, _) => wes::api::RunStatus::Running,
+            (Some(0), _) => wes::api::RunStatus::Complete,
+            (Some(_), _) => wes::api::RunStatus::Failed,
+        })
+    }
+
+    /// The run log in the form of the WES `/runs/{run_id}` response.
+    pub fn get_run_log(&self, run_id: impl AsRef<str>) -> Result<Value> {
+        let status = self.get_run_status(&run_id)?;
+        let runs = self.runs.borrow();
+        let run = runs
+            .get(run_id.as_ref())
+            .ok_or_else(|| anyhow!("No local run: {}", run_id.as_ref()))?;
+        let run_dir = self.run_dir.join(run_id.as_ref());
+        let outputs = match status {
+            wes::api::RunStatus::Running => vec![],
+            _ => list_files(&run.exe_dir)?
+                .into_iter()
+                .filter(|path| !run.staged.contains(path))
+                .map(|path| {
+                    let file_url = Url::from_file_path(run.exe_dir.join(&path))
+                        .map_err(|_| anyhow!("Invalid file path: {}", path.display()))?;
+                    Ok(json!({
+                        "file_name": path.to_string_lossy(),
+                        "file_url": file_url.as_str(),
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        };
+        Ok(json!({
+            "run_id": run_id.as_ref(),
+            "state": match (&status, run.canceled) {
+                (_, true) => "CANCELED",
+                (wes::api::RunStatus::Running, _) => "RUNNING",
+                (wes::api::RunStatus::Complete, _) => "COMPLETE",
+                _ => "EXECUTOR_ERROR",
+            },
+            "run_log": {
+                "cmd": run.cmd,
+                "exit_code": run.exit_code,
+                "stdout": fs::read_to_string(run_dir.join("stdout.log")).unwrap_or_default(),
+                "stderr": fs::read_to_string(run_dir.join("stderr.log")).unwrap_or_default(),
+            },
+            "outputs": outputs,
+        }))
+    }
+
+    pub fn cancel_run(&self, run_id: impl AsRef<str>) -> Result<()> {
+        let mut runs = self.runs.borrow_mut();
+        let run = runs
+            .get_mut(run_id.as_ref())
+            .ok_or_else(|| anyhow!("No local run: {}", run_id.as_ref()))?;
+        if run.exit_code.is_none() {
+            run.child.kill()?;
+            run.exit_code = Some(run.child.wait()?.code().unwrap_or(-1));
+            run.canceled = true;
+        }
+        Ok(())
+    }
+
+    pub fn get_run_outputs(&self, run_log: &Value) -> Result<Vec<(String, Url)>> {
+        let run_dir = Url::from_directory_path(&self.run_dir)
+            .map_err(|_| anyhow!("Invalid run directory: {}", self.run_dir.display()))?;
+        wes::api::get_run_outputs(&run_dir, run_log)
+    }
+}
+
+pub const SUPPORTED_ENGINES: &[&str] = &[
+    "cwltool",
+    "toil",
+    "cromwell",
+    "miniwdl",
+    "nextflow",
+    "snakemake",
+];
+
+/// Written from the `wf_params` of the test case, and passed to the engine.
+const WF_PARAMS: &str = "wf_params.json";
+
+fn engine_program(name: impl AsRef<str>) -> Result<&'static str> {
+    match name.as_ref() {
+        "cwltool" => Ok("cwltool"),
+        "toil" => Ok("toil-cwl-runner"),
+        "cromwell" => Ok("cromwell"),
+        "miniwdl" => Ok("miniwdl"),
+        "nextflow" => Ok("nextflow"),
+        "snakemake" => Ok("snakemake"),
+        _ => bail!(
+            "The local runner does not support the engine `{}` (supported: {})",
+            name.as_ref(),
+            SUPPORTED_ENGINES.join(", ")
+        ),
+    }
+}
+
+/// The command line to run the workflow in the execution directory.
+fn engine_command(
+    engine: &metadata::types::Engine,
+    wf: &Path,
+    engine_params: &[String],
+) -> Result<Vec<String>> {
+    let program = engine_program(&engine.name)?.to_string();
+    let wf = wf.to_string_lossy().to_string();
+    let params = WF_PARAMS.to_string();
+    let args: Vec<String> = match engine.name.as_str() {
+        // the options of cwltool must precede the workflow
+        "cwltool" | "toil" => [
+            engine_params,
+            &["--outdir".into(), "outputs".into(), wf, params],
+        ]
+        .concat(),
+        "cromwell" => [
+            &["run".into(), wf, "--inputs".into(), params],
+            engine_params,
+        ]
+        .concat(),
+        // `DIR/.` makes miniwdl use the directory as is, without a timestamped subdirectory
+        "miniwdl" => [
+            &[
+                "run".into(),
+                wf,
+                "--input".into(),
+                params,
+                "--dir".into(),
+                "outputs/.".into(),
+            ],
+            engine_params,
+        ]
+        .concat(),
+        "nextflow" => [
+            &["run".into(), wf, "-params-file".into(), params],
+            engine_params,
+        ]
+        .concat(),
+        "snakemake" => {
+            let mut args = vec!["--snakefile".into(), wf, "--configfile".into(), params];
+            if !engine_params
+                .iter()
+                .any(|p| matches!(p.as_str(), "--cores" | "-c" | "--jobs" | "-j"))
+            {
+                args.extend(["--cores".into(), "1".into()]);
+            }
+            [&args, engine_params].concat()
+        }
+        _ => unreachable!(),
+    };
+    Ok([vec![program], args].concat())
+}
+
+/// `{"--flag": "value", "--switch": ""}` in `wf_engine_params` -> `["--flag", "value", "--switch"]`
+/// The options are passed in the order written (serde_json with `preserve_order`).
+fn engine_params_to_args(wf_engine_params: impl AsRef<str>) -> Result<Vec<String>> {
+    let err_msg = "wf_engine_params for the local runner must be a JSON object of the CLI options";
+    let params: Value = serde_json::from_str(wf_engine_params.as_ref())
+        .map_err(|e| anyhow!("{} with error: {}", err_msg, e))?;
+    let params = params.as_object().ok_or_else(|| anyhow!(err_msg))?;
+    let mut args = vec![];
+    for (key, value) in params {
+        args.push(key.clone());
+        match value {
+            Value::Null | Value::Bool(true) => {}
+            Value::String(value) if value.is_empty() => {}
+            Value::String(value) => args.push(value.clone()),
+            Value::Number(value) => args.push(value.to_string()),
+            _ => bail!("{}: {}", err_msg, key),
+        }
+    }
+    Ok(args)
+}
+
+fn stage_file(exe_dir: &Path, target: &Path, content: &[u8]) -> Result<()> {
+    ensure!(
+        target
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir)),
+        "The target {} must be a relative path in the execution directory",
+        target.display()
+    );
+    let path = exe_dir.join(target);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Files in the directory, relative to it, except for the hidden and engine work directories.
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(rel_dir) = dirs.pop() {
+        for entry in fs::read_dir(dir.join(&rel_dir))? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = rel_dir.join(&name);
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if !name.starts_with('.') && !ENGINE_WORK_DIRS.contains(&name.as_str()) {
+                    dirs.push(path);
+                }
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    fn engine(name: &str) -> metadata::types::Engine {
+        metadata::types::Engine {
+            name: name.to_string(),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_engine_command() -> Result<()> {
+        let wf = Path::new("wf/main.cwl");
+        assert_eq!(
+            engine_command(&engine("cwltool"), wf, &["--debug".to_string()])?,
+            vec![
+                "cwltool",
+                "--debug",
+                "--outdir",
+                "outputs",
+                "wf/main.cwl",
+                "wf_params.json"
+            ]
+        );
+        assert_eq!(
+            engine_command(&engine("snakemake"), Path::new("Snakefile"), &[])?,
+            vec![
+                "snakemake",
+                "--snakefile",
+                "Snakefile",
+                "--configfile",
+                "wf_params.json",
+                "--cores",
+                "1"
+            ]
+        );
+        assert!(engine_command(&engine("unknown"), wf, &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_engine_params_to_args() -> Result<()> {
+        assert_eq!(
+            engine_params_to_args(r#"{"-profile": "test", "-resume": "", "--max-cpus": 2}"#)?,
+            vec!["-profile", "test", "-resume", "--max-cpus", "2"]
+        );
+        assert_eq!(
+            engine_params_to_args(
+                r#"{"--outdir": "out", "--debug": true, "--cachedir": "cache"}"#
+            )?,
+            vec!["--outdir", "out", "--debug", "--cachedir", "cache"]
+        );
+        assert!(engine_params_to_args("{}")?.is_empty());
+        assert!(engine_params_to_args(r#"["--debug"]"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_file_and_list_files() -> Result<()> {
+        let exe_dir = std::env::temp_dir().join(format!("yevis-local-{}", Uuid::new_v4()));
+        stage_file(&exe_dir, Path::new("data/input.txt"), b"input")?;
+        stage_file(&exe_dir, Path::new("work/ab/cd/tmp.txt"), b"tmp")?;
+        stage_file(&exe_dir, Path::new("outputs/out.txt"), b"out")?;
+        assert!(stage_file(&exe_dir, Path::new("../escape.txt"), b"").is_err());
+        assert_eq!(
+            list_files(&exe_dir)?,
+            vec![
+                PathBuf::from("data/input.txt"),
+                PathBuf::from("outputs/out.txt")
+            ]
+        );
+        fs::remove_dir_all(&exe_dir)?;
+        Ok(())
+    }
+}
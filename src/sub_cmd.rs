@@ -1,35 +1,45 @@
 pub mod make_template;
+pub mod migrate;
 pub mod publish;
 pub mod pull_request;
+pub mod schema;
 pub mod test;
 pub mod validate;
 
 use crate::zenodo;
 use anyhow::bail;
 use make_template::make_template as make_template_process;
+use migrate::migrate as migrate_process;
 use publish::publish as publish_process;
 use pull_request::pull_request as pull_request_process;
+use schema::schema as schema_process;
 use test::test as test_process;
 use validate::validate as validate_process;
 
+use crate::diagnostic;
 use crate::env;
+use crate::license;
 use crate::metadata;
+use crate::runner;
+use crate::test_cache;
 use crate::wes;
 
 use colored::Colorize;
-use log::{error, info};
+use log::{error, info, warn};
+use std::fs;
 use std::path::Path;
 use std::process::exit;
 use url::Url;
 
 pub fn make_template(
-    wf_loc: &Url,
+    wf_loc: impl AsRef<str>,
     gh_token: impl AsRef<str>,
     output: impl AsRef<Path>,
     use_commit_url: &bool,
+    list_all_files: bool,
 ) {
     info!("{} make-template", "Running".green());
-    match make_template_process(wf_loc, &gh_token, &output, use_commit_url) {
+    match make_template_process(&wf_loc, &gh_token, &output, use_commit_url, list_all_files) {
         Ok(()) => info!("{} make-template", "Success".green()),
         Err(e) => {
             error!("{} to make-template with error: {}", "Failed".red(), e);
@@ -41,82 +51,239 @@ pub fn make_template(
 pub fn validate(
     meta_locs: Vec<impl AsRef<str>>,
     gh_token: impl AsRef<str>,
+    offline: bool,
+    license_list: &Option<impl AsRef<Path>>,
+    report_format: &diagnostic::ReportFormat,
+    report_output: &Option<impl AsRef<Path>>,
 ) -> Vec<metadata::types::Metadata> {
     info!("{} validate", "Running".green());
-    let mut meta_vec = vec![];
-    for meta_loc in meta_locs {
-        info!("Validating {}", meta_loc.as_ref());
-        let meta = match validate_process(meta_loc, &gh_token) {
-            Ok(meta) => meta,
+    let license_registry = if offline {
+        info!("Run yevis-cli validate in offline mode");
+        match license::LicenseRegistry::new_local(license_list) {
+            Ok(license_registry) => license_registry,
             Err(e) => {
-                error!("{} to validate with error: {}", "Failed".red(), e);
+                error!("{} to load license list with error: {}", "Failed".red(), e);
                 exit(1);
             }
-        };
-        meta_vec.push(meta);
+        }
+    } else {
+        license::LicenseRegistry::new_remote(&gh_token)
+    };
+    let skipped_checks = if offline {
+        validate::OFFLINE_SKIPPED_CHECKS
+    } else {
+        &[]
+    };
+    let mut meta_vec = vec![];
+    let mut reports = vec![];
+    for meta_loc in meta_locs {
+        info!("Validating {}", meta_loc.as_ref());
+        let (meta, diagnostics) =
+            validate_process(meta_loc.as_ref(), &gh_token, &license_registry, offline);
+        if let Some(meta) = meta {
+            meta_vec.push(meta);
+        }
+        reports.push(diagnostic::FileReport {
+            location: meta_loc.as_ref().to_string(),
+            diagnostics: diagnostics.0,
+        });
+    }
+    if !skipped_checks.is_empty() {
+        warn!(
+            "{}: Skipped checks that require network access:\n{}",
+            "Warning".yellow(),
+            skipped_checks
+                .iter()
+                .map(|c| format!("  - {}", c))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    if let Err(e) = write_validation_report(&reports, skipped_checks, report_format, report_output)
+    {
+        error!(
+            "{} to write validation report with error: {}",
+            "Failed".red(),
+            e
+        );
+        exit(1);
+    }
+    let num_errors = reports.iter().map(|r| r.diagnostics.len()).sum::<usize>();
+    if num_errors > 0 {
+        error!(
+            "{} to validate with {} error(s) in {} file(s)",
+            "Failed".red(),
+            num_errors,
+            reports.iter().filter(|r| !r.diagnostics.is_empty()).count()
+        );
+        exit(1);
     }
     info!("{} validate", "Success".green());
     meta_vec
 }
 
+fn write_validation_report(
+    reports: &[diagnostic::FileReport],
+    skipped_checks: &[&str],
+    report_format: &diagnostic::ReportFormat,
+    report_output: &Option<impl AsRef<Path>>,
+) -> Result<(), anyhow::Error> {
+    let content = match report_format {
+        diagnostic::ReportFormat::Text => {
+            for report in reports {
+                for d in &report.diagnostics {
+                    error!("{}: {} {}", report.location_of(d), "error".red(), d)
+                }
+            }
+            match report_output {
+                Some(_) => reports
+                    .iter()
+                    .flat_map(|r| {
+                        r.diagnostics
+                            .iter()
+                            .map(move |d| format!("{}: error {}\n", r.location_of(d), d))
+                    })
+                    .collect::<String>(),
+                None => return Ok(()),
+            }
+        }
+        diagnostic::ReportFormat::Json => diagnostic::to_json(reports, skipped_checks)?,
+        diagnostic::ReportFormat::Sarif => diagnostic::to_sarif(reports, skipped_checks)?,
+    };
+    match report_output {
+        Some(report_output) => {
+            info!(
+                "Writing validation report to {}",
+                report_output.as_ref().display()
+            );
+            fs::write(report_output, content)?;
+        }
+        None => println!("{}", content),
+    }
+    Ok(())
+}
+
+pub fn migrate(meta_locs: &[impl AsRef<Path>], dry_run: bool) {
+    info!("{} migrate", "Running".green());
+    match migrate_process(meta_locs, dry_run) {
+        Ok(()) => info!("{} migrate", "Success".green()),
+        Err(e) => {
+            error!("{} to migrate with error: {}", "Failed".red(), e);
+            exit(1);
+        }
+    }
+}
+
+pub fn schema(output: &Option<impl AsRef<Path>>) {
+    info!("{} schema", "Running".green());
+    match schema_process(output) {
+        Ok(()) => info!("{} schema", "Success".green()),
+        Err(e) => {
+            error!("{} to schema with error: {}", "Failed".red(), e);
+            exit(1);
+        }
+    }
+}
+
 pub fn test(
-    meta_vec: &Vec<metadata::types::Metadata>,
+    meta_vec: &[metadata::types::Metadata],
     wes_loc: &Option<Url>,
-    docker_host: &Url,
-    fetch_ro_crate: bool,
+    container_runtime: &Option<wes::instance::RuntimeType>,
+    docker_host: &Option<Url>,
+    opts: &test::TestOptions,
 ) {
     info!("{} test", "Running".green());
+    let write_log = env::in_ci();
+    if opts.runner == runner::RunnerType::Local {
+        let local = match env::local_run_dir().and_then(runner::local::LocalRunner::new) {
+            Ok(local) => local,
+            Err(e) => {
+                error!(
+                    "{} to prepare the local runner with error: {}",
+                    "Failed".red(),
+                    e
+                );
+                exit(1);
+            }
+        };
+        info!("Use the engines on the host for testing");
+        match test_process(meta_vec, &runner::Runner::Local(local), write_log, opts) {
+            Ok(()) => info!("{} test", "Success".green()),
+            Err(e) => {
+                error!("{} to test with error: {}", "Failed".red(), e);
+                exit(1);
+            }
+        };
+        return;
+    }
+    let mut opts = opts.clone();
+    // sapporo-service started by yevis, stopped after the test
+    let mut sapporo = None;
     let wes_loc = match wes_loc {
         Some(wes_loc) => wes_loc.clone(),
-        None => match wes::instance::start_wes(docker_host) {
-            Ok(_) => wes::instance::default_wes_location(),
-            Err(e) => {
+        None => {
+            let config = match wes::instance::SapporoConfig::from_env() {
+                Ok(config) => config,
+                Err(e) => {
+                    error!(
+                        "{} to configure sapporo-service with error: {}",
+                        "Failed".red(),
+                        e
+                    );
+                    exit(1);
+                }
+            };
+            let runtime = wes::instance::ContainerRuntime::new(container_runtime, docker_host);
+            if let Err(e) = wes::instance::start_wes(&runtime, &config) {
                 error!("{} to start WES instance with error: {}", "Failed".red(), e);
-                wes::instance::stop_wes_no_result(docker_host);
+                wes::instance::stop_wes_no_result(&runtime, &config);
                 exit(1);
             }
-        },
+            opts.wes_image = match wes::instance::image_digest(&runtime, &config) {
+                Ok(digest) => {
+                    info!("Use sapporo-service image: {}", digest);
+                    Some(digest)
+                }
+                Err(e) => {
+                    warn!(
+                        "{}: Failed to get the digest of the sapporo-service image: {}",
+                        "Warning".yellow(),
+                        e
+                    );
+                    None
+                }
+            };
+            let wes_loc = config.wes_location();
+            sapporo = Some((runtime, config));
+            wes_loc
+        }
+    };
+    let stop_wes = || {
+        if let Some((runtime, config)) = &sapporo {
+            wes::instance::stop_wes_no_result(runtime, config);
+        }
     };
     info!("Use WES location: {} for testing", wes_loc);
-    match wes::api::get_supported_wes_versions(&wes_loc) {
-        Ok(supported_wes_versions) => {
-            if !supported_wes_versions
-                .into_iter()
-                .any(|v| v == "sapporo-wes-1.0.1")
-            {
-                error!(
-                    "{}: Yevis only supports WES version `sapporo-wes-1.0.1`",
-                    "Failed".red()
-                );
-                wes::instance::stop_wes_no_result(docker_host);
-                exit(1);
-            }
+    let client = match wes::client::WesClient::new(&wes_loc) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("{} to connect to WES with error: {}", "Failed".red(), e);
+            stop_wes();
+            exit(1);
+        }
+    };
+    info!("Use {} as WES", client.name());
+    match test_process(meta_vec, &runner::Runner::Wes(client), write_log, &opts) {
+        Ok(()) => {
+            info!("{} test", "Success".green());
         }
         Err(e) => {
-            error!(
-                "{} to get supported WES versions with error: {}",
-                "Failed".red(),
-                e
-            );
-            wes::instance::stop_wes_no_result(docker_host);
+            error!("{} to test with error: {}", "Failed".red(), e);
+            stop_wes();
             exit(1);
         }
     };
-    let write_log = env::in_ci();
-    for meta in meta_vec {
-        info!("Test workflow_id: {}, version: {}", meta.id, meta.version);
-        match test_process(meta, &wes_loc, write_log, fetch_ro_crate) {
-            Ok(()) => {
-                info!("{} test", "Success".green());
-            }
-            Err(e) => {
-                error!("{} to test with error: {}", "Failed".red(), e);
-                wes::instance::stop_wes_no_result(docker_host);
-                exit(1);
-            }
-        };
-    }
-    wes::instance::stop_wes_no_result(docker_host);
+    stop_wes();
 }
 
 pub fn pull_request(
@@ -139,9 +306,10 @@ pub fn publish(
     gh_token: impl AsRef<str>,
     repo: impl AsRef<str>,
     verified: bool,
+    test_cache: &test_cache::TestCache,
 ) {
     info!("{} publish", "Running".green());
-    match publish_process(meta_vec, &gh_token, &repo, verified) {
+    match publish_process(meta_vec, &gh_token, &repo, verified, test_cache) {
         Ok(()) => info!("{} publish", "Success".green()),
         Err(e) => {
             error!("{} to publish with error: {}", "Failed".red(), e);
@@ -6,18 +6,29 @@ use log::{debug, info};
 use std::path::Path;
 use url::Url;
 
+/// `wf_loc`: remote URL or local file path of the primary workflow
 pub fn make_template(
-    wf_loc: &Url,
+    wf_loc: impl AsRef<str>,
     gh_token: impl AsRef<str>,
     output: impl AsRef<Path>,
     use_commit_url: &bool,
+    list_all_files: bool,
 ) -> Result<()> {
-    info!("Making a template from {}", wf_loc);
+    info!("Making a template from {}", wf_loc.as_ref());
     let url_type = match use_commit_url {
         true => remote::UrlType::Commit,
         false => remote::UrlType::Branch,
     };
-    let metadata = metadata::types::Metadata::new(wf_loc, gh_token, &url_type)?;
+    let metadata = match Url::parse(wf_loc.as_ref()) {
+        Ok(url) => metadata::types::Metadata::new(&url, gh_token, &url_type, list_all_files)?,
+        // as local file path
+        Err(_) => metadata::types::Metadata::new_from_local(
+            wf_loc.as_ref(),
+            gh_token,
+            &url_type,
+            list_all_files,
+        )?,
+    };
     debug!(
         "template metadata file:\n{}",
         serde_yaml::to_string(&metadata)?
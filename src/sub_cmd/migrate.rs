@@ -0,0 +1,85 @@
+use crate::metadata;
+
+use anyhow::{Context, Result};
+use log::info;
+use similar::TextDiff;
+use std::fs;
+use std::path::Path;
+
+/// Rewrite the metadata files in the current format version and print the diff to stdout.
+/// Files that are already in the current format version are left untouched.
+pub fn migrate(meta_locs: &[impl AsRef<Path>], dry_run: bool) -> Result<()> {
+    for meta_loc in meta_locs {
+        let path = meta_loc.as_ref();
+        let original = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        // Even json can be read with yaml reader
+        let version = metadata::migrate::schema_version(&serde_yaml::from_str(&original)?)?;
+        if version == metadata::migrate::CURRENT_SCHEMA_VERSION {
+            info!(
+                "{} is already in schema_version {}",
+                path.display(),
+                version
+            );
+            continue;
+        }
+
+        let meta = metadata::io::read(path.to_string_lossy(), "")?;
+        let file_ext = metadata::io::parse_file_ext(path)?;
+        let migrated = metadata::io::to_string(&meta, &file_ext)?;
+        let header = path.display().to_string();
+        print!(
+            "{}",
+            TextDiff::from_lines(&original, &migrated)
+                .unified_diff()
+                .header(&header, &header)
+        );
+
+        if dry_run {
+            info!("Skip rewriting {} (dry run)", path.display());
+        } else {
+            info!(
+                "Migrating {} from schema_version {} to {}",
+                path.display(),
+                version,
+                meta.schema_version
+            );
+            metadata::io::write_local(&meta, path, &file_ext)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("yevis-metadata.yml");
+        fs::copy("./tests/test-metadata-CWL-validated.yml", &path)?;
+
+        migrate(&[&path], true)?;
+        let content = fs::read_to_string(&path)?;
+        assert!(!content.contains("schema_version"));
+
+        migrate(&[&path], false)?;
+        let meta = metadata::io::read(path.to_string_lossy(), "")?;
+        assert_eq!(
+            meta.schema_version,
+            metadata::migrate::CURRENT_SCHEMA_VERSION
+        );
+        let content = fs::read_to_string(&path)?;
+        assert!(content.contains(&format!(
+            "schema_version: {}",
+            metadata::migrate::CURRENT_SCHEMA_VERSION
+        )));
+
+        // already migrated files are not rewritten
+        migrate(&[&path], false)?;
+        assert_eq!(fs::read_to_string(&path)?, content);
+        Ok(())
+    }
+}
@@ -1,12 +1,15 @@
 use crate::env;
 use crate::gh;
 use crate::metadata;
+use crate::ro_crate;
+use crate::test_cache;
 use crate::trs;
+use crate::workflow_ro_crate;
 
 use anyhow::{anyhow, bail, Result};
-use log::{debug, info};
+use log::{debug, info, warn};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use url::Url;
 
 pub fn publish(
@@ -14,6 +17,7 @@ pub fn publish(
     gh_token: impl AsRef<str>,
     repo: impl AsRef<str>,
     verified: bool,
+    test_cache: &test_cache::TestCache,
 ) -> Result<()> {
     let (owner, name) = gh::parse_repo(repo)?;
     let branch = get_gh_pages_branch(&gh_token, &owner, &name)?;
@@ -34,9 +38,18 @@ pub fn publish(
         gh::api::get_latest_commit_sha(&gh_token, &owner, &name, &branch, None)?;
     let mut trs_response = trs::response::TrsResponse::new(&owner, &name)?;
     for meta in meta_vec {
-        trs_response.add(&owner, &name, meta, verified)?;
+        // not verified again, so that the previous `verified_source` is carried forward
+        let tested = verified && !test_cache.hit(meta);
+        trs_response.add(&owner, &name, meta, tested)?;
+    }
+    let mut trs_contents = generate_trs_contents(trs_response)?;
+    if verified {
+        for meta in meta_vec {
+            if let Some((path, content)) = test_cache.contents(meta)? {
+                trs_contents.insert(path, content);
+            }
+        }
     }
-    let trs_contents = generate_trs_contents(trs_response)?;
     let new_tree_sha =
         gh::api::create_tree(&gh_token, &owner, &name, Some(&branch_sha), trs_contents)?;
     let mut commit_message = if meta_vec.len() == 1 {
@@ -125,6 +138,10 @@ fn generate_trs_contents(trs_res: trs::response::TrsResponse) -> Result<HashMap<
             .tools_descriptor
             .get(&(*id, version.clone()))
             .unwrap();
+        let tools_descriptor_files = trs_res
+            .tools_descriptor_files
+            .get(&(*id, version.clone()))
+            .unwrap();
         let tools_files = trs_res.tools_files.get(&(*id, version.clone())).unwrap();
         let tools_tests = trs_res.tools_tests.get(&(*id, version.clone())).unwrap();
 
@@ -156,6 +173,44 @@ fn generate_trs_contents(trs_res: trs::response::TrsResponse) -> Result<HashMap<
             )),
             serde_json::to_string(&tools_descriptor)?,
         );
+        let plain_type =
+            trs::types::DescriptorTypeWithPlain::new_plain(&meta.workflow.language.r#type);
+        if let (Some(plain_type), Some(content)) = (&plain_type, &tools_descriptor.content) {
+            map.insert(
+                PathBuf::from(format!(
+                    "tools/{}/versions/{}/{}/descriptor/index.json",
+                    id, version, plain_type
+                )),
+                content.clone(),
+            );
+        }
+        for (target, descriptor) in tools_descriptor_files {
+            if !is_descriptor_relative_path(target) {
+                warn!(
+                    "Skip publishing the descriptor of {:?}, as it is not a relative path available as a TRS endpoint",
+                    target
+                );
+                continue;
+            }
+            map.insert(
+                PathBuf::from(format!(
+                    "tools/{}/versions/{}/{}/descriptor",
+                    id, version, desc_type
+                ))
+                .join(target),
+                serde_json::to_string(&descriptor)?,
+            );
+            if let (Some(plain_type), Some(content)) = (&plain_type, &descriptor.content) {
+                map.insert(
+                    PathBuf::from(format!(
+                        "tools/{}/versions/{}/{}/descriptor",
+                        id, version, plain_type
+                    ))
+                    .join(target),
+                    content.clone(),
+                );
+            }
+        }
         map.insert(
             PathBuf::from(format!(
                 "tools/{}/versions/{}/{}/files/index.json",
@@ -178,35 +233,80 @@ fn generate_trs_contents(trs_res: trs::response::TrsResponse) -> Result<HashMap<
             serde_json::to_string(&Vec::<trs::types::FileWrapper>::new())?,
         );
 
-        // Test is executed and RO-Crate is fetched to test-logs/ro-crate-metadata_{id}_{version}_{test_id}.json
-        meta.workflow.testing.iter().for_each(|test| {
-            let ro_crate_path = PathBuf::from(format!(
-                "test-logs/ro-crate-metadata_{}_{}_{}.json",
-                id, version, test.id
-            ));
-            if ro_crate_path.exists() {
+        map.insert(
+            PathBuf::from(format!(
+                "tools/{}/versions/{}/{}",
+                id,
+                version,
+                ro_crate::METADATA_DESCRIPTOR_ID
+            )),
+            serde_json::to_string(&workflow_ro_crate::generate(meta)?)?,
+        );
+
+        // Test is executed and RO-Crate is fetched to test-logs/ro-crate-metadata_{id}_{version}_{label}.json,
+        // where the label is the test ID with the engine in the test matrix
+        for test in &meta.workflow.testing {
+            // a workflow in an unknown language has no default engine, and is not tested
+            let engines = test
+                .engines(&meta.workflow.language.r#type)
+                .unwrap_or_default();
+            for engine in engines {
+                let label = test.label(&engine);
+                let ro_crate_path = PathBuf::from(format!(
+                    "test-logs/ro-crate-metadata_{}_{}_{}.json",
+                    id, version, label
+                ));
+                if !ro_crate_path.exists() {
+                    debug!("RO-Crate not found: {:?}", ro_crate_path);
+                    continue;
+                }
                 debug!("Found RO-Crate: {:?}", ro_crate_path);
-                let ro_crate = std::fs::read_to_string(ro_crate_path).unwrap();
+                let ro_crate = std::fs::read_to_string(&ro_crate_path)?;
+                if let Err(e) = serde_json::from_str(&ro_crate)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|ro_crate| ro_crate::validate(&ro_crate))
+                {
+                    warn!("Skip publishing RO-Crate {:?}: {}", ro_crate_path, e);
+                    continue;
+                }
                 map.insert(
                     PathBuf::from(format!(
                         "tools/{}/versions/{}/ro-crate-metadata_{}.json",
-                        id, version, test.id
+                        id, version, label
                     )),
                     ro_crate,
                 );
-            } else {
-                debug!("RO-Crate not found: {:?}", ro_crate_path);
             }
-        });
+        }
     }
     Ok(map)
 }
 
+/// `{type}/descriptor/{relative_path}` is written as a file next to `{type}/descriptor/index.json`,
+/// so the path must stay under the directory and must not overwrite `index.json`.
+fn is_descriptor_relative_path(target: &Path) -> bool {
+    target
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        && target != Path::new("index.json")
+}
+
 #[cfg(test)]
 #[cfg(not(tarpaulin_include))]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_descriptor_relative_path() {
+        assert!(is_descriptor_relative_path(Path::new(
+            "trimming_and_qc.cwl"
+        )));
+        assert!(is_descriptor_relative_path(Path::new("tools/fastqc.cwl")));
+        assert!(!is_descriptor_relative_path(Path::new("index.json")));
+        assert!(!is_descriptor_relative_path(Path::new("../fastqc.cwl")));
+        assert!(!is_descriptor_relative_path(Path::new("/fastqc.cwl")));
+    }
+
     #[test]
     fn test_get_gh_pages_branch() -> Result<()> {
         let gh_token = env::github_token(&None::<String>)?;
@@ -0,0 +1,18 @@
+use crate::metadata;
+
+use anyhow::Result;
+use log::info;
+use std::fs;
+use std::path::Path;
+
+pub fn schema(output: &Option<impl AsRef<Path>>) -> Result<()> {
+    let content = serde_json::to_string_pretty(&metadata::schema::generate()?)?;
+    match output {
+        Some(output) => {
+            info!("Writing JSON Schema to {}", output.as_ref().display());
+            fs::write(output, content)?;
+        }
+        None => println!("{}", content),
+    }
+    Ok(())
+}
@@ -1,98 +1,663 @@
 use crate::metadata;
+use crate::ro_crate;
+use crate::runner;
+use crate::test_quarantine;
+use crate::test_report;
+use crate::test_state;
 use crate::wes;
 
-use anyhow::{anyhow, bail, Result};
-use log::{debug, info};
+use anyhow::{anyhow, bail, ensure, Result};
+use colored::Colorize;
+use log::{debug, info, warn};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env::current_dir;
 use std::fs;
 use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time;
 use url::Url;
 use uuid::Uuid;
 
+/// Options of the `test` subcommand.
+#[derive(Debug, Clone)]
+pub struct TestOptions {
+    pub fetch_ro_crate: bool,
+    pub runner: runner::RunnerType,
+    /// Maximum number of runs submitted to WES at the same time
+    pub parallel: usize,
+    /// Timeout of each test case in seconds, unless `timeout` is set in the test case
+    pub timeout: Option<u64>,
+    /// Print the stdout and stderr of the runs while they are in progress
+    pub follow: bool,
+    /// Re-attach to the runs saved in the state file instead of submitting them again
+    pub resume: bool,
+    /// Where the WES runs are saved, added to the runs of the other test cases in it.
+    /// The runs are not saved if not specified.
+    pub state_file: Option<PathBuf>,
+    /// Lists the test cases known to be flaky, whose failures do not fail the test
+    pub quarantine_file: PathBuf,
+    pub junit_report: Option<PathBuf>,
+    pub json_report: Option<PathBuf>,
+    /// Digest of the sapporo-service image started by yevis, recorded in the test reports
+    pub wes_image: Option<String>,
+}
+
+/// A test case waiting to be submitted, on one of its engines.
+struct Job<'a> {
+    meta: &'a metadata::types::Metadata,
+    test_case: &'a metadata::types::Testing,
+    engine: metadata::types::Engine,
+    /// Earlier runs of the test case, which did not pass and are retried
+    previous_attempts: Vec<test_report::Attempt>,
+}
+
+impl Job<'_> {
+    /// The test ID, with the engine in the test matrix, e.g., `test_1_toil`
+    fn label(&self) -> String {
+        self.test_case.label(&self.engine)
+    }
+}
+
+/// A test case submitted to WES and not finished yet.
+struct ActiveRun<'a> {
+    job: Job<'a>,
+    run_id: String,
+    iter_num: usize,
+    started: time::Instant,
+    deadline: Option<time::Instant>,
+    /// Number of lines already printed per log stream in `--follow` mode
+    log_offsets: HashMap<String, usize>,
+}
+
+/// Set by the Ctrl-C handler.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// While the test cases are scheduled, Ctrl-C cancels the runs in flight instead of exiting immediately.
+static SCHEDULING: AtomicBool = AtomicBool::new(false);
+
+/// Run the test cases of all workflows.
+/// Up to `parallel` runs are submitted to WES at the same time, and the active runs are polled together.
+/// The results are collected per test case as they finish, and all failed test cases are reported at the end.
+/// Runs exceeding the timeout and runs in flight on Ctrl-C are canceled.
 pub fn test(
-    meta: &metadata::types::Metadata,
-    wes_loc: &Url,
+    meta_vec: &[metadata::types::Metadata],
+    client: &runner::Runner,
     write_log: bool,
-    fetch_ro_crate: bool,
+    opts: &TestOptions,
 ) -> Result<()> {
+    ensure!(
+        opts.parallel > 0,
+        "The number of parallel runs must be at least 1"
+    );
+    // The handler is process-global, so Ctrl-C after the tests (e.g., while publishing)
+    // or a second Ctrl-C exits immediately.
+    if let Err(e) = ctrlc::set_handler(|| {
+        if !SCHEDULING.load(Ordering::SeqCst) || INTERRUPTED.swap(true, Ordering::SeqCst) {
+            exit(130);
+        }
+    }) {
+        debug!("Failed to set the Ctrl-C handler: {}", e);
+    }
+    let mut pending = VecDeque::new();
+    for meta in meta_vec {
+        info!("Test workflow_id: {}, version: {}", meta.id, meta.version);
+        for test_case in &meta.workflow.testing {
+            for engine in test_case.engines(&meta.workflow.language.r#type)? {
+                pending.push_back(Job {
+                    meta,
+                    test_case,
+                    engine,
+                    previous_attempts: vec![],
+                });
+            }
+        }
+    }
+    // a standard WES runs the test cases without engines on its own default engine
+    client.check_engines(
+        pending
+            .iter()
+            .filter(|job| !job.test_case.engines.is_empty() || client.knows_default_engines())
+            .map(|job| &job.engine)
+            .collect::<HashSet<_>>(),
+    )?;
+    let quarantine = test_quarantine::Quarantine::load(&opts.quarantine_file)?;
+    let mut active: Vec<ActiveRun> = vec![];
     let mut test_results = vec![];
-    for test_case in &meta.workflow.testing {
-        info!("Testing test case: {}", test_case.id);
-
-        let form = wes::api::test_case_to_form(meta, test_case)?;
-        debug!("Form:\n{:#?}", &form);
-        let run_id = wes::api::post_run(wes_loc, form)?;
-        info!("WES run_id: {}", run_id);
-
-        let mut status = wes::api::RunStatus::Running;
-        let mut iter_num = 0;
-        while status == wes::api::RunStatus::Running {
-            sleep(iter_num);
-            status = wes::api::get_run_status(wes_loc, &run_id)?;
-            debug!("WES run status: {:?}", status);
-            iter_num += 1;
-        }
-
-        let run_log = serde_json::to_string_pretty(&wes::api::get_run_log(wes_loc, &run_id)?)?;
-        if write_log {
-            write_test_log(&meta.id, &meta.version, &test_case.id, &run_log)?;
-        }
-        match status {
-            wes::api::RunStatus::Complete => {
-                info!("Complete test case: {}", test_case.id);
-                debug!("Run log:\n{}", run_log);
+    SCHEDULING.store(true, Ordering::SeqCst);
+    let result = run_jobs(
+        &mut pending,
+        &mut active,
+        &mut test_results,
+        client,
+        write_log,
+        opts,
+    );
+    if result.is_err() || interrupted() {
+        info!("Canceling {} running test cases", active.len());
+        for run in &active {
+            cancel(client, &run.run_id);
+        }
+    }
+    SCHEDULING.store(false, Ordering::SeqCst);
+    result?;
+    for report in &mut test_results {
+        report.quarantined = quarantine
+            .find(&report.workflow_id, &report.version, &report.test_id)
+            .is_some();
+    }
+
+    for meta in meta_vec {
+        if test_results
+            .iter()
+            .filter(|r| r.workflow_id == meta.id && r.version == meta.version)
+            .all(|r| r.passed())
+        {
+            info!(
+                "Passed all test cases in workflow_id: {}, version: {}",
+                meta.id, meta.version
+            );
+        }
+    }
+    if let Some(junit_report) = &opts.junit_report {
+        info!("Writing JUnit report to {}", junit_report.display());
+        fs::write(
+            junit_report,
+            test_report::to_junit(&test_results, &opts.wes_image)?,
+        )?;
+    }
+    if let Some(json_report) = &opts.json_report {
+        info!("Writing JSON report to {}", json_report.display());
+        fs::write(
+            json_report,
+            test_report::to_json(&test_results, &opts.wes_image)?,
+        )?;
+    }
+    ensure!(
+        !interrupted(),
+        "Interrupted, {} test cases were not run",
+        pending.len() + active.len()
+    );
+    check_test_results(test_results)
+}
+
+/// Submit the pending jobs up to `opts.parallel` at a time and poll them until they finish or are interrupted.
+/// On an error, the runs left in `active` are still running.
+fn run_jobs<'a>(
+    pending: &mut VecDeque<Job<'a>>,
+    active: &mut Vec<ActiveRun<'a>>,
+    test_results: &mut Vec<test_report::TestCaseReport>,
+    client: &runner::Runner,
+    write_log: bool,
+    opts: &TestOptions,
+) -> Result<()> {
+    // the saved runs of the other test cases are kept, and only re-attached to with `--resume`
+    let mut state = match &opts.state_file {
+        Some(state_file) => test_state::TestState::load(state_file)?,
+        None => test_state::TestState::default(),
+    };
+    while !pending.is_empty() || !active.is_empty() {
+        if interrupted() {
+            break;
+        }
+        while active.len() < opts.parallel {
+            let job = match pending.pop_front() {
+                Some(job) => job,
+                None => break,
+            };
+            // a retried job is submitted again instead of re-attached to the run that did not pass
+            let enabled = opts.resume && job.previous_attempts.is_empty();
+            match resume(&job, client, &state, enabled) {
+                Some((run_id, wes::api::RunStatus::Running)) => {
+                    active.push(attach(job, run_id, opts.timeout))
+                }
+                Some((run_id, status)) => {
+                    let run = attach(job, run_id, opts.timeout);
+                    let report = finish(&run, status, client, write_log, opts.fetch_ro_crate)?;
+                    retry_or_record(run.job, report, pending, test_results);
+                }
+                None => {
+                    let run = submit(job, client, opts.timeout)?;
+                    // the runs of the local runner can not be re-attached to
+                    let run_state = client.wes_loc().map(|wes_loc| test_state::RunState {
+                        workflow_id: run.job.meta.id,
+                        version: run.job.meta.version.clone(),
+                        test_id: run.job.label(),
+                        wes_location: wes_loc.clone(),
+                        run_id: run.run_id.clone(),
+                    });
+                    active.push(run);
+                    if let (Some(run_state), Some(state_file)) = (run_state, &opts.state_file) {
+                        state.upsert(run_state);
+                        state.save(state_file)?;
+                    }
+                }
             }
-            wes::api::RunStatus::Failed => {
-                info!(
-                    "Failed test case: {} with run_log:\n{}",
-                    test_case.id, run_log
+        }
+
+        // all the jobs may have been resumed as finished
+        if active.is_empty() {
+            continue;
+        }
+        wait(
+            active.iter().map(|run| run.iter_num).min().unwrap_or(0),
+            active.iter().filter_map(|run| run.deadline).min(),
+        );
+        if interrupted() {
+            break;
+        }
+
+        let follow_max_lines = match opts.follow {
+            true => (FOLLOW_LINES_PER_POLL / active.len()).max(FOLLOW_MIN_LINES),
+            false => 0,
+        };
+        let mut i = 0;
+        while i < active.len() {
+            let run = &mut active[i];
+            let status = client.get_run_status(&run.run_id)?;
+            debug!(
+                "WES run status of test case {}: {:?}",
+                run.job.label(),
+                status
+            );
+            if opts.follow {
+                follow_logs(
+                    run,
+                    client,
+                    status != wes::api::RunStatus::Running,
+                    follow_max_lines,
                 );
             }
-            _ => {
-                unreachable!("WES run status: {:?}", status);
+            match status {
+                wes::api::RunStatus::Running
+                    if run
+                        .deadline
+                        .is_some_and(|deadline| time::Instant::now() >= deadline) =>
+                {
+                    let run = active.remove(i);
+                    info!(
+                        "Timed out test case: {} after {} seconds",
+                        run.job.label(),
+                        run.started.elapsed().as_secs()
+                    );
+                    cancel(client, &run.run_id);
+                    let report = finish(
+                        &run,
+                        wes::api::RunStatus::TimedOut,
+                        client,
+                        write_log,
+                        opts.fetch_ro_crate,
+                    )?;
+                    retry_or_record(run.job, report, pending, test_results);
+                }
+                wes::api::RunStatus::Running => {
+                    run.iter_num += 1;
+                    i += 1;
+                }
+                _ => {
+                    let run = active.remove(i);
+                    let report = finish(&run, status, client, write_log, opts.fetch_ro_crate)?;
+                    retry_or_record(run.job, report, pending, test_results);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Submit the job again if the test case did not pass and `retries` is left, or record the result.
+fn retry_or_record<'a>(
+    mut job: Job<'a>,
+    report: test_report::TestCaseReport,
+    pending: &mut VecDeque<Job<'a>>,
+    test_results: &mut Vec<test_report::TestCaseReport>,
+) {
+    let retries = job.test_case.retries.unwrap_or(0);
+    if report.passed() || job.previous_attempts.len() as u64 >= retries {
+        test_results.push(report);
+        return;
+    }
+    info!(
+        "Retrying test case: {} ({}/{})",
+        job.label(),
+        job.previous_attempts.len() + 1,
+        retries
+    );
+    job.previous_attempts.push(test_report::Attempt {
+        run_id: report.run_id,
+        status: report.status,
+        duration: report.duration,
+    });
+    pending.push_back(job);
+}
+
+fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// A failure to cancel is only logged, as the test result does not change.
+fn cancel(client: &runner::Runner, run_id: impl AsRef<str>) {
+    info!("Canceling WES run: {}", run_id.as_ref());
+    if let Err(e) = client.cancel_run(&run_id) {
+        warn!(
+            "{}: Failed to cancel WES run {}: {}",
+            "Warning".yellow(),
+            run_id.as_ref(),
+            e
+        );
+    }
+}
+
+fn submit<'a>(
+    job: Job<'a>,
+    client: &runner::Runner,
+    timeout: Option<u64>,
+) -> Result<ActiveRun<'a>> {
+    info!(
+        "Testing test case: {} on {} (workflow_id: {}, version: {})",
+        job.label(),
+        job.engine,
+        job.meta.id,
+        job.meta.version
+    );
+    let run_id = client.post_run(job.meta, job.test_case, &job.engine)?;
+    info!("WES run_id: {}", run_id);
+    Ok(attach(job, run_id, timeout))
+}
+
+/// The saved run of the job, if it is still running or has completed.
+/// Failed or missing runs are submitted again.
+fn resume(
+    job: &Job,
+    client: &runner::Runner,
+    state: &test_state::TestState,
+    enabled: bool,
+) -> Option<(String, wes::api::RunStatus)> {
+    if !enabled {
+        return None;
+    }
+    let saved = state.find(
+        &job.meta.id,
+        &job.meta.version,
+        job.label(),
+        client.wes_loc()?,
+    )?;
+    match client.get_run_status(&saved.run_id) {
+        Ok(status @ (wes::api::RunStatus::Running | wes::api::RunStatus::Complete)) => {
+            info!(
+                "Resuming test case: {} with WES run_id: {} ({:?})",
+                job.label(),
+                saved.run_id,
+                status
+            );
+            Some((saved.run_id.clone(), status))
+        }
+        Ok(status) => {
+            info!(
+                "Resubmitting test case: {}, as WES run {} finished with status {:?}",
+                job.label(),
+                saved.run_id,
+                status
+            );
+            None
+        }
+        Err(e) => {
+            info!(
+                "Resubmitting test case: {}, as WES run {} is not available: {}",
+                job.label(),
+                saved.run_id,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// The timeout of a resumed run counts from when it is re-attached to.
+fn attach(job: Job, run_id: String, timeout: Option<u64>) -> ActiveRun {
+    let started = time::Instant::now();
+    let deadline = job
+        .test_case
+        .timeout
+        .or(timeout)
+        .map(|timeout| started + time::Duration::from_secs(timeout));
+    ActiveRun {
+        job,
+        run_id,
+        iter_num: 0,
+        started,
+        deadline,
+        log_offsets: HashMap::new(),
+    }
+}
+
+/// Lines of the logs printed per poll in `--follow` mode, shared by the active runs.
+const FOLLOW_LINES_PER_POLL: usize = 60;
+const FOLLOW_MIN_LINES: usize = 5;
+
+/// Print the new lines of the stdout and stderr of the run and its tasks, prefixed by the test ID.
+/// Only the last `max_lines` new lines of each log are printed, and the others are skipped.
+/// A failure to get the logs is only logged, as the test result does not change.
+fn follow_logs(run: &mut ActiveRun, client: &runner::Runner, finished: bool, max_lines: usize) {
+    let label = run.job.label();
+    let run_log = match client.get_run_log(&run.run_id) {
+        Ok(run_log) => run_log,
+        Err(e) => {
+            debug!("Failed to get the run log of test case {}: {}", label, e);
+            return;
+        }
+    };
+    for (stream, log) in log_streams(&run_log) {
+        // standard WES returns the URLs of the logs
+        let content = match Url::parse(&log) {
+            Ok(url) if matches!(url.scheme(), "http" | "https" | "file") => {
+                match wes::api::download_file(&url) {
+                    Ok(content) => String::from_utf8_lossy(&content).to_string(),
+                    Err(e) => {
+                        debug!("Failed to get the {} of test case {}: {}", stream, label, e);
+                        continue;
+                    }
+                }
+            }
+            _ => log,
+        };
+        let offset = run.log_offsets.entry(stream.clone()).or_insert(0);
+        let lines = new_lines(&content, *offset, finished);
+        *offset += lines.len();
+        if lines.len() > max_lines {
+            info!(
+                "[{} {}] ... {} lines skipped",
+                label,
+                stream,
+                lines.len() - max_lines
+            );
+        }
+        for line in &lines[lines.len().saturating_sub(max_lines)..] {
+            info!("[{} {}] {}", label, stream, line);
+        }
+    }
+}
+
+/// `run_log.stdout`, `run_log.stderr` and those of `task_logs` in the run log.
+fn log_streams(run_log: &Value) -> Vec<(String, String)> {
+    let mut streams = vec![];
+    let mut push = |prefix: String, log: &Value| {
+        for key in ["stdout", "stderr"] {
+            if let Some(content) = log.get(key).and_then(|v| v.as_str()) {
+                streams.push((format!("{}{}", prefix, key), content.to_string()));
             }
         }
+    };
+    if let Some(log) = run_log.get("run_log") {
+        push(String::new(), log);
+    }
+    if let Some(task_logs) = run_log.get("task_logs").and_then(|v| v.as_array()) {
+        for (i, task_log) in task_logs.iter().enumerate() {
+            let name = match task_log.get("name").and_then(|v| v.as_str()) {
+                Some(name) => name.to_string(),
+                None => format!("task_{}", i),
+            };
+            push(format!("{} ", name), task_log);
+        }
+    }
+    streams
+}
+
+/// Lines after the first `offset` lines.
+/// The last line is held back until it is terminated, unless the run has finished.
+fn new_lines(content: &str, offset: usize, finished: bool) -> Vec<&str> {
+    let complete = match finished || content.ends_with('\n') {
+        true => content,
+        false => content
+            .rsplit_once('\n')
+            .map_or("", |(complete, _)| complete),
+    };
+    complete.lines().skip(offset).collect()
+}
+
+fn finish(
+    run: &ActiveRun,
+    status: wes::api::RunStatus,
+    client: &runner::Runner,
+    write_log: bool,
+    fetch_ro_crate: bool,
+) -> Result<test_report::TestCaseReport> {
+    let duration = run.started.elapsed().as_secs_f64();
+    let meta = run.job.meta;
+    let test_case = run.job.test_case;
+    let label = run.job.label();
+    let run_log_value = client.get_run_log(&run.run_id)?;
+    let run_log = serde_json::to_string_pretty(&run_log_value)?;
+    let output_assertions = match status {
+        wes::api::RunStatus::Complete => check_outputs(test_case, client, &run_log_value)?,
+        _ => vec![],
+    };
+    for assertion in &output_assertions {
+        for failure in &assertion.failures {
+            info!(
+                "Output assertion failed in test case {}: {}: {}",
+                label, assertion.file_name, failure
+            );
+        }
+    }
+    let status = match output_assertions.iter().all(|a| a.passed) {
+        true => status,
+        false => wes::api::RunStatus::Failed,
+    };
+    if write_log {
+        write_test_log(&meta.id, &meta.version, &label, &run_log)?;
+    }
+    match status {
+        wes::api::RunStatus::Complete => {
+            info!("Complete test case: {}", label);
+            debug!("Run log:\n{}", run_log);
+        }
+        wes::api::RunStatus::Failed => {
+            info!("Failed test case: {} with run_log:\n{}", label, run_log);
+        }
+        wes::api::RunStatus::TimedOut => {
+            debug!("Run log:\n{}", run_log);
+        }
+        _ => {
+            unreachable!("WES run status: {:?}", status);
+        }
+    }
 
-        match wes::api::fetch_ro_crate(wes_loc, &run_id) {
+    // The RO-Crate of a canceled run may never be generated,
+    // and a standard WES or the local runner does not generate it at all
+    let ro_crate = match status {
+        wes::api::RunStatus::TimedOut => None,
+        _ if !client.provides_ro_crate() => {
+            if fetch_ro_crate {
+                warn!(
+                    "Skip fetching the RO-Crate of test case {}, as the runner does not generate it",
+                    label
+                );
+            }
+            None
+        }
+        _ => Some(client.fetch_ro_crate(&run.run_id)),
+    };
+    match ro_crate {
+        Some(Ok(ro_crate)) => match ro_crate::validate(&ro_crate)
+            .and_then(|_| ro_crate::enrich(ro_crate, meta, &test_case.id))
+        {
             Ok(ro_crate) => {
                 if fetch_ro_crate || write_log {
                     let ro_crate_dir = current_dir()?.join("test-logs");
                     fs::create_dir_all(&ro_crate_dir)?;
                     let ro_crate_path = ro_crate_dir.join(format!(
                         "ro-crate-metadata_{}_{}_{}.json",
-                        &meta.id, &meta.version, &test_case.id
+                        &meta.id, &meta.version, &label
                     ));
                     let mut file = BufWriter::new(fs::File::create(&ro_crate_path)?);
                     file.write_all(serde_json::to_string_pretty(&ro_crate)?.as_bytes())?;
                 }
             }
-            Err(e) => {
-                if fetch_ro_crate {
-                    bail!("Failed to fetch RO-Crate with error: {}", e)
-                }
-            }
-        };
-
-        test_results.push(TestResult {
-            id: test_case.id.clone(),
-            status,
-        });
-    }
-    match check_test_results(test_results) {
-        Ok(()) => info!(
-            "Passed all test cases in workflow_id: {}, version: {}",
-            meta.id, meta.version
-        ),
-        Err(e) => bail!(e),
+            // the test result does not depend on the RO-Crate
+            Err(e) => match fetch_ro_crate {
+                true => warn!("Skip the RO-Crate of test case {}: {}", label, e),
+                false => debug!("Skip the RO-Crate of test case {}: {}", label, e),
+            },
+        },
+        Some(Err(e)) if fetch_ro_crate => {
+            bail!("Failed to fetch RO-Crate with error: {}", e)
+        }
+        _ => {}
     };
-    Ok(())
+
+    Ok(test_report::TestCaseReport {
+        workflow_id: meta.id,
+        version: meta.version.clone(),
+        test_id: test_case.id.clone(),
+        engine: run.job.engine.to_string(),
+        status,
+        duration,
+        run_id: run.run_id.clone(),
+        stderr_tail: wes::api::get_stderr_tail(&run_log_value, STDERR_TAIL_LINES),
+        output_assertions,
+        previous_attempts: run.job.previous_attempts.clone(),
+        quarantined: false,
+    })
 }
 
-struct TestResult {
-    pub id: String,
-    pub status: wes::api::RunStatus,
+/// Number of the stderr lines in the test reports
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Download the outputs listed in `expected_outputs` and check them.
+fn check_outputs(
+    test_case: &metadata::types::Testing,
+    client: &runner::Runner,
+    run_log: &Value,
+) -> Result<Vec<test_report::OutputAssertion>> {
+    if test_case.expected_outputs.is_empty() {
+        return Ok(vec![]);
+    }
+    let outputs = client.get_run_outputs(run_log)?;
+    test_case
+        .expected_outputs
+        .iter()
+        .map(|expected| {
+            let output = outputs
+                .iter()
+                .find(|(name, _)| Path::new(name).ends_with(&expected.file_name));
+            let failures = match output {
+                Some((_, file_url)) => match wes::api::download_file(file_url) {
+                    Ok(content) => expected.check(&content)?,
+                    Err(e) => vec![format!("failed to download: {}", e)],
+                },
+                None => vec!["not found in the outputs".to_string()],
+            };
+            Ok(test_report::OutputAssertion {
+                file_name: expected.file_name.to_string_lossy().to_string(),
+                passed: failures.is_empty(),
+                failures,
+            })
+        })
+        .collect()
 }
 
 fn write_test_log(
@@ -117,19 +682,35 @@ fn write_test_log(
     Ok(())
 }
 
-fn check_test_results(test_results: Vec<TestResult>) -> Result<()> {
-    let failed_tests = test_results
-        .iter()
-        .filter(|r| r.status == wes::api::RunStatus::Failed)
-        .collect::<Vec<_>>();
-    if !failed_tests.is_empty() {
+/// The quarantined failures are only reported.
+fn check_test_results(test_results: Vec<test_report::TestCaseReport>) -> Result<()> {
+    let describe = |r: &test_report::TestCaseReport| {
+        format!(
+            "{} on {} (workflow_id: {}, version: {})",
+            r.test_id, r.engine, r.workflow_id, r.version
+        )
+    };
+    for r in test_results.iter().filter(|r| r.quarantined_failure()) {
+        warn!(
+            "Quarantined test case {} finished with status {:?}, which does not fail the test",
+            describe(r),
+            r.status
+        );
+    }
+    let list = |status: wes::api::RunStatus| {
+        test_results
+            .iter()
+            .filter(|r| r.failed() && r.status == status)
+            .map(describe)
+            .collect::<Vec<_>>()
+    };
+    let failed_tests = list(wes::api::RunStatus::Failed);
+    let timed_out_tests = list(wes::api::RunStatus::TimedOut);
+    if !failed_tests.is_empty() || !timed_out_tests.is_empty() {
         bail!(
-            "Some tests failed. Failed tests: {}",
-            failed_tests
-                .iter()
-                .map(|r| r.id.clone())
-                .collect::<Vec<_>>()
-                .join(", ")
+            "Some tests failed. Failed tests: [{}], Timed out tests: [{}]",
+            failed_tests.join(", "),
+            timed_out_tests.join(", ")
         );
     }
     Ok(())
@@ -139,14 +720,167 @@ fn check_test_results(test_results: Vec<TestResult>) -> Result<()> {
 /// Up to 5 minutes, every 30 seconds: 10 * 6 + 30 * 8
 /// Up to 60 minutes, every 1 minute: 10 * 6 + 30 * 8 + 60 * 55
 /// Beyond that, every 2 minutes
-fn sleep(iter_num: usize) {
+fn poll_interval(iter_num: usize) -> time::Duration {
     if iter_num < 6 {
-        thread::sleep(time::Duration::from_secs(10));
+        time::Duration::from_secs(10)
     } else if iter_num < 15 {
-        thread::sleep(time::Duration::from_secs(30));
+        time::Duration::from_secs(30)
     } else if iter_num < 69 {
-        thread::sleep(time::Duration::from_secs(60));
+        time::Duration::from_secs(60)
     } else {
-        thread::sleep(time::Duration::from_secs(120));
+        time::Duration::from_secs(120)
+    }
+}
+
+/// Sleep for the polling interval, but wake up at the deadline or on Ctrl-C.
+fn wait(iter_num: usize, deadline: Option<time::Instant>) {
+    let until = time::Instant::now() + poll_interval(iter_num);
+    let until = match deadline {
+        Some(deadline) => until.min(deadline),
+        None => until,
+    };
+    while !interrupted() {
+        let now = time::Instant::now();
+        if now >= until {
+            break;
+        }
+        thread::sleep((until - now).min(time::Duration::from_secs(1)));
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    /// WES returning the body to every request
+    fn serve(body: &'static str) -> Result<Url> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let wes_loc = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+        Ok(wes_loc)
+    }
+
+    #[test]
+    fn test_log_streams() -> Result<()> {
+        let run_log = json!({
+            "run_log": {"stdout": "N E X T F L O W\n", "stderr": ""},
+            "task_logs": [
+                {"name": "fastqc", "stdout": "started\n"},
+                {"stderr": "http://localhost:1122/runs/1/tasks/2/stderr"}
+            ]
+        });
+        assert_eq!(
+            log_streams(&run_log),
+            vec![
+                ("stdout".to_string(), "N E X T F L O W\n".to_string()),
+                ("stderr".to_string(), "".to_string()),
+                ("fastqc stdout".to_string(), "started\n".to_string()),
+                (
+                    "task_1 stderr".to_string(),
+                    "http://localhost:1122/runs/1/tasks/2/stderr".to_string()
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_lines() -> Result<()> {
+        assert_eq!(new_lines("a\nb\nc", 0, false), vec!["a", "b"]);
+        assert_eq!(new_lines("a\nb\nc", 1, true), vec!["b", "c"]);
+        assert_eq!(new_lines("a\nb\n", 2, false), Vec::<&str>::new());
+        assert_eq!(new_lines("partial", 0, false), Vec::<&str>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_or_record() -> Result<()> {
+        let mut meta = metadata::io::test_metadata()?;
+        meta.workflow.testing[0].retries = Some(1);
+        let test_case = &meta.workflow.testing[0];
+        let report = |status: wes::api::RunStatus, run_id: &str| test_report::TestCaseReport {
+            workflow_id: meta.id,
+            version: meta.version.clone(),
+            test_id: test_case.id.clone(),
+            engine: "cwltool".to_string(),
+            status,
+            duration: 1.0,
+            run_id: run_id.to_string(),
+            stderr_tail: None,
+            output_assertions: vec![],
+            previous_attempts: vec![],
+            quarantined: false,
+        };
+        let job = Job {
+            meta: &meta,
+            test_case,
+            engine: test_case.engines(&meta.workflow.language.r#type)?[0].clone(),
+            previous_attempts: vec![],
+        };
+        let mut pending = VecDeque::new();
+        let mut test_results = vec![];
+
+        retry_or_record(
+            job,
+            report(wes::api::RunStatus::Failed, "run_1"),
+            &mut pending,
+            &mut test_results,
+        );
+        assert!(test_results.is_empty());
+        let job = pending.pop_front().unwrap();
+        assert_eq!(job.previous_attempts[0].run_id, "run_1");
+
+        retry_or_record(
+            job,
+            report(wes::api::RunStatus::Failed, "run_2"),
+            &mut pending,
+            &mut test_results,
+        );
+        assert!(pending.is_empty());
+        assert_eq!(test_results[0].run_id, "run_2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_finish_on_standard_wes() -> Result<()> {
+        let meta = metadata::io::test_metadata()?;
+        let test_case = &meta.workflow.testing[0];
+        let wes_loc =
+            serve(r#"{"run_id": "run_1", "state": "COMPLETE", "run_log": {"stderr": "done"}}"#)?;
+        let client = runner::Runner::Wes(wes::client::WesClient::Standard {
+            wes_loc,
+            version: "1.1.0".to_string(),
+        });
+        assert!(!client.provides_ro_crate());
+        let run = attach(
+            Job {
+                meta: &meta,
+                test_case,
+                engine: test_case.engines(&meta.workflow.language.r#type)?[0].clone(),
+                previous_attempts: vec![],
+            },
+            "run_1".to_string(),
+            None,
+        );
+        // the RO-Crate is skipped instead of failing the test
+        let report = finish(&run, wes::api::RunStatus::Complete, &client, false, true)?;
+        assert!(report.passed());
+        assert_eq!(report.stderr_tail, Some("done".to_string()));
+        Ok(())
     }
 }
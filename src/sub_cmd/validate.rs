@@ -1,28 +1,76 @@
-use crate::gh;
+use crate::diagnostic;
+use crate::license;
 use crate::metadata;
+use crate::orcid;
 use crate::remote;
 
-use anyhow::Context;
-use anyhow::{anyhow, bail, ensure, Result};
+use anyhow::{ensure, Result};
 use log::debug;
-use regex::Regex;
-use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 use url::Url;
 
+/// Checks that are skipped in offline mode because they require network access.
+pub const OFFLINE_SKIPPED_CHECKS: &[&str] = &[
+    "reading remote metadata locations",
+    "validating `license` with GitHub and Zenodo license APIs (the bundled SPDX license list is used instead)",
+    "pinning `workflow.readme` to a commit URL",
+    "pinning `workflow.files[].url` to commit URLs",
+    "pinning `workflow.testing[].files[].url` to commit URLs",
+];
+
+/// Run all checks and collect every problem instead of stopping at the first one.
+/// Return: (updated metadata if the document could be read, diagnostics)
 pub fn validate(
     meta_loc: impl AsRef<str>,
     gh_token: impl AsRef<str>,
-) -> Result<metadata::types::Metadata> {
-    let mut meta = metadata::io::read(meta_loc.as_ref(), &gh_token)?;
-    validate_version(&meta.version)?;
-    validate_license(&mut meta, &gh_token)?;
-    validate_authors(&meta)?;
-    validate_language(&meta)?;
-    validate_wf_name(&meta.workflow.name)?;
-    validate_and_update_workflow(&mut meta, &gh_token)?;
-    debug!("updated metadata file:\n{}", serde_yaml::to_string(&meta)?);
-    Ok(meta)
+    license_registry: &license::LicenseRegistry,
+    offline: bool,
+) -> (Option<metadata::types::Metadata>, diagnostic::Diagnostics) {
+    let mut diagnostics = diagnostic::Diagnostics::default();
+    if offline && Url::parse(meta_loc.as_ref()).is_ok() {
+        diagnostics.error(
+            "",
+            format!(
+                "Remote metadata location {} cannot be read in offline mode",
+                meta_loc.as_ref()
+            ),
+        );
+        return (None, diagnostics);
+    }
+    let content = match metadata::io::read_content(meta_loc.as_ref(), &gh_token) {
+        Ok(content) => content,
+        Err(e) => {
+            diagnostics.error("", format!("Failed to read the metadata file: {}", e));
+            return (None, diagnostics);
+        }
+    };
+    // Schema errors make the remaining checks meaningless, so stop here.
+    let schema_diagnostics = metadata::schema::validate(&content);
+    if !schema_diagnostics.0.is_empty() {
+        diagnostics.0.extend(schema_diagnostics.0);
+        return (None, diagnostics);
+    }
+    let mut meta = match metadata::io::parse(&content) {
+        Ok(meta) => meta,
+        Err(e) => {
+            diagnostics.error("", format!("Failed to parse the metadata file: {}", e));
+            return (None, diagnostics);
+        }
+    };
+    if let Err(e) = validate_version(&meta.version) {
+        diagnostics.error("version", e);
+    }
+    validate_license(&mut meta, license_registry, &mut diagnostics);
+    validate_authors(&meta, &mut diagnostics);
+    validate_language(&meta, &mut diagnostics);
+    if let Err(e) = validate_wf_name(&meta.workflow.name) {
+        diagnostics.error("workflow.name", e);
+    }
+    validate_and_update_workflow(&mut meta, &gh_token, offline, &mut diagnostics);
+    if let Ok(content) = serde_yaml::to_string(&meta) {
+        debug!("updated metadata file:\n{}", content);
+    }
+    (Some(meta), diagnostics)
 }
 
 /// allow characters
@@ -40,80 +88,51 @@ pub fn validate_version(version: impl AsRef<str>) -> Result<()> {
 }
 
 /// Validate the license of the metadata file.
-/// Contact GitHub API and Zenodo API to confirm.
 /// Change the license to `spdx_id`
 /// e.g., `apache-2.0` -> `Apache-2.0`
-fn validate_license(meta: &mut metadata::types::Metadata, gh_token: impl AsRef<str>) -> Result<()> {
-    let (key, spdx_id) = validate_with_github_license_api(gh_token, &meta.license)?;
-    validate_with_zenodo_license_api(&key)?;
-    meta.license = spdx_id;
-    Ok(())
-}
-
-#[derive(Debug, Deserialize)]
-struct LicenseResponse {
-    key: String,
-    spdx_id: String,
-}
-
-/// https://docs.github.com/ja/rest/reference/licenses#get-a-license
-/// res: (key, spdx_id), e.g., ("mit", "MIT")
-fn validate_with_github_license_api(
-    gh_token: impl AsRef<str>,
-    license: impl AsRef<str>,
-) -> Result<(String, String)> {
-    let url = Url::parse(&format!(
-        "https://api.github.com/licenses/{}",
-        license.as_ref()
-    ))?;
-    let res = gh::get_request(gh_token, &url, &[])?;
-    let res: LicenseResponse =
-        serde_json::from_value(res).context("Failed to parse GitHub license API response")?;
-    Ok((res.key, res.spdx_id))
-}
-
-/// https://developers.zenodo.org/?shell#retrieve41
-fn validate_with_zenodo_license_api(license: impl AsRef<str>) -> Result<()> {
-    let url = Url::parse(&format!(
-        "https://zenodo.org/api/vocabularies/licenses/{}",
-        license.as_ref()
-    ))?;
-    // Return the path for this URL, as a percent-encoded ASCII string
-    let response = reqwest::blocking::get(url.as_str())?;
-    let status = response.status();
-    ensure!(
-        status.is_success(),
-        "`license` is not valid from Zenodo license API"
-    );
-    Ok(())
+fn validate_license(
+    meta: &mut metadata::types::Metadata,
+    license_registry: &license::LicenseRegistry,
+    diagnostics: &mut diagnostic::Diagnostics,
+) {
+    match license_registry.resolve(&meta.license) {
+        Ok(spdx_id) => meta.license = spdx_id,
+        Err(e) => diagnostics.error("license", e),
+    }
 }
 
-fn validate_authors(meta: &metadata::types::Metadata) -> Result<()> {
-    let orcid_re = Regex::new(r"^\d{4}-\d{4}-\d{4}-\d{3}[\dX]$")?;
+fn validate_authors(meta: &metadata::types::Metadata, diagnostics: &mut diagnostic::Diagnostics) {
     let mut account_set: HashSet<&str> = HashSet::new();
-    for author in &meta.authors {
+    for (i, author) in meta.authors.iter().enumerate() {
         if let Some(orcid) = &author.orcid {
-            ensure!(orcid_re.is_match(orcid), "`authors[].orcid` is not valid",);
+            if let Err(e) = orcid::validate(orcid) {
+                diagnostics.error(
+                    format!("authors[{}].orcid", i),
+                    format!("`authors[].orcid` is not valid: {}", e),
+                );
+            }
         };
-        ensure!(
-            !account_set.contains(author.github_account.as_str()),
-            "`authors[].github_account` is not unique",
-        );
-        account_set.insert(author.github_account.as_str());
+        if let Some(github_account) = &author.github_account {
+            if account_set.contains(github_account.as_str()) {
+                diagnostics.error(
+                    format!("authors[{}].github_account", i),
+                    "`authors[].github_account` is not unique",
+                );
+            }
+            account_set.insert(github_account.as_str());
+        }
+    }
+    if meta.authors.is_empty() {
+        diagnostics.error("authors", "`authors` must have more than one author");
     }
-    ensure!(
-        !meta.authors.is_empty(),
-        "`authors` must have more than one author",
-    );
-    Ok(())
 }
 
-fn validate_language(meta: &metadata::types::Metadata) -> Result<()> {
-    match meta.workflow.language.r#type {
-        metadata::types::LanguageType::Unknown => {
-            bail!("`language.type` is not specified. Please specify `CWL`, `WDL`, `NFL` or `SMK`")
-        }
-        _ => Ok(()),
+fn validate_language(meta: &metadata::types::Metadata, diagnostics: &mut diagnostic::Diagnostics) {
+    if meta.workflow.language.r#type == metadata::types::LanguageType::Unknown {
+        diagnostics.error(
+            "workflow.language.type",
+            "`language.type` is not specified. Please specify `CWL`, `WDL`, `NFL` or `SMK`",
+        );
     }
 }
 
@@ -142,92 +161,220 @@ fn update_url(
     remote.to_typed_url(&remote::UrlType::Commit)
 }
 
+/// In offline mode, URLs are not pinned to commits.
 fn validate_and_update_workflow(
     meta: &mut metadata::types::Metadata,
     gh_token: impl AsRef<str>,
-) -> Result<()> {
+    offline: bool,
+    diagnostics: &mut diagnostic::Diagnostics,
+) {
     let mut branch_memo = HashMap::new();
     let mut commit_memo = HashMap::new();
 
-    meta.workflow.readme = update_url(
-        &meta.workflow.readme,
-        &gh_token,
-        Some(&mut branch_memo),
-        Some(&mut commit_memo),
-    )
-    .map_err(|e| anyhow!("Invalid `workflow.readme`: {}", e))?;
-
-    ensure!(
-        meta.workflow.primary_wf().is_ok(),
-        "One `primary` needs to be specified in the `workflow.files[].type` field",
-    );
-
-    for file in &mut meta.workflow.files {
-        file.url = update_url(
-            &file.url,
+    if !offline {
+        match update_url(
+            &meta.workflow.readme,
             &gh_token,
             Some(&mut branch_memo),
             Some(&mut commit_memo),
-        )
-        .map_err(|e| anyhow!("Invalid `workflow.files[].url`: {}", e))?;
-        file.complement_target()?;
+        ) {
+            Ok(url) => meta.workflow.readme = url,
+            Err(e) => diagnostics.error("workflow.readme", format!("Invalid URL: {}", e)),
+        }
     }
 
-    let mut test_id_set: HashSet<&str> = HashSet::new();
-    for testing in &mut meta.workflow.testing {
-        ensure!(
-            !test_id_set.contains(testing.id.as_str()),
-            "`workflow.testing[].id` is not unique, duplicated id: {}",
-            testing.id.as_str()
+    if meta.workflow.primary_wf().is_err() {
+        diagnostics.error(
+            "workflow.files",
+            "One `primary` needs to be specified in the `workflow.files[].type` field",
         );
-        test_id_set.insert(testing.id.as_str());
+    }
 
-        for file in &mut testing.files {
-            file.url = update_url(
+    for (i, file) in meta.workflow.files.iter_mut().enumerate() {
+        if !offline {
+            match update_url(
                 &file.url,
                 &gh_token,
                 Some(&mut branch_memo),
                 Some(&mut commit_memo),
-            )
-            .map_err(|e| anyhow!("Invalid `workflow.testing[].files[].url`: {}", e))?;
-            file.complement_target()?;
+            ) {
+                Ok(url) => file.url = url,
+                Err(e) => diagnostics.error(
+                    format!("workflow.files[{}].url", i),
+                    format!("Invalid URL: {}", e),
+                ),
+            }
+        }
+        if let Err(e) = file.complement_target() {
+            diagnostics.error(format!("workflow.files[{}].target", i), e);
+        }
+    }
+
+    let mut test_id_set: HashSet<String> = HashSet::new();
+    for (i, testing) in meta.workflow.testing.iter_mut().enumerate() {
+        if test_id_set.contains(testing.id.as_str()) {
+            diagnostics.error(
+                format!("workflow.testing[{}].id", i),
+                format!(
+                    "`workflow.testing[].id` is not unique, duplicated id: {}",
+                    testing.id.as_str()
+                ),
+            );
+        }
+        test_id_set.insert(testing.id.clone());
+
+        for (j, file) in testing.files.iter_mut().enumerate() {
+            if !offline {
+                match update_url(
+                    &file.url,
+                    &gh_token,
+                    Some(&mut branch_memo),
+                    Some(&mut commit_memo),
+                ) {
+                    Ok(url) => file.url = url,
+                    Err(e) => diagnostics.error(
+                        format!("workflow.testing[{}].files[{}].url", i, j),
+                        format!("Invalid URL: {}", e),
+                    ),
+                }
+            }
+            if let Err(e) = file.complement_target() {
+                diagnostics.error(format!("workflow.testing[{}].files[{}].target", i, j), e);
+            }
+        }
+
+        if testing.timeout == Some(0) {
+            diagnostics.error(
+                format!("workflow.testing[{}].timeout", i),
+                "`workflow.testing[].timeout` must be at least 1 second",
+            );
+        }
+
+        let mut engine_set = HashSet::new();
+        for (j, engine) in testing.engines.iter().enumerate() {
+            if engine.name.trim().is_empty() {
+                diagnostics.error(
+                    format!("workflow.testing[{}].engines[{}].name", i, j),
+                    "`workflow.testing[].engines[].name` is empty",
+                );
+            }
+            if !engine_set.insert(engine) {
+                diagnostics.error(
+                    format!("workflow.testing[{}].engines[{}]", i, j),
+                    format!("Duplicated engine: {}", engine),
+                );
+            }
+        }
+
+        for (j, expected_output) in testing.expected_outputs.iter().enumerate() {
+            if let Err(e) = expected_output.validate() {
+                diagnostics.error(
+                    format!("workflow.testing[{}].expected_outputs[{}]", i, j),
+                    e,
+                );
+            }
         }
     }
-    Ok(())
 }
 
 #[cfg(test)]
 #[cfg(not(tarpaulin_include))]
 mod tests {
     use super::*;
-    use crate::env;
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn test_validate_wf_name() -> Result<()> {
+        validate_wf_name("abc")?;
+        validate_wf_name("abcABC123")?;
+        validate_wf_name("abcABC123~!@#$%^&*()_+-={{}}[]|:;,.<>? ")?;
+        validate_wf_name("Workflow name: example_workflow-123.cwl (for example)")?;
+        let err = validate_wf_name("`");
+        assert!(err.is_err());
+        Ok(())
+    }
 
     #[test]
-    fn test_validate_with_github_license_api() -> Result<()> {
-        let gh_token = env::github_token(&None::<String>)?;
-        validate_with_github_license_api(&gh_token, "cc0-1.0")?;
-        validate_with_github_license_api(&gh_token, "mit")?;
-        validate_with_github_license_api(&gh_token, "MIT")?;
-        validate_with_github_license_api(&gh_token, "apache-2.0")?;
+    fn test_validate_offline() -> Result<()> {
+        let license_registry = license::LicenseRegistry::new_local(&None::<String>)?;
+        let (meta, diagnostics) = validate(
+            "./tests/test-metadata-CWL-validated.yml",
+            "",
+            &license_registry,
+            true,
+        );
+        assert!(diagnostics.0.is_empty());
+        assert_eq!(meta.unwrap().license, "CC0-1.0");
         Ok(())
     }
 
     #[test]
-    fn test_validate_with_zenodo_license_api() -> Result<()> {
-        validate_with_zenodo_license_api("cc0-1.0")?;
-        validate_with_zenodo_license_api("mit")?;
-        validate_with_zenodo_license_api("apache-2.0")?;
+    fn test_validate_offline_remote_location() -> Result<()> {
+        let license_registry = license::LicenseRegistry::new_local(&None::<String>)?;
+        let (meta, diagnostics) = validate(
+            "https://raw.githubusercontent.com/sapporo-wes/yevis-cli/main/tests/test-metadata-CWL-validated.yml",
+            "",
+            &license_registry,
+            true,
+        );
+        assert!(meta.is_none());
+        assert_eq!(diagnostics.0.len(), 1);
         Ok(())
     }
 
     #[test]
-    fn test_validate_wf_name() -> Result<()> {
-        validate_wf_name("abc")?;
-        validate_wf_name("abcABC123")?;
-        validate_wf_name("abcABC123~!@#$%^&*()_+-={{}}[]|:;,.<>? ")?;
-        validate_wf_name("Workflow name: example_workflow-123.cwl (for example)")?;
-        let err = validate_wf_name("`");
-        assert!(err.is_err());
+    fn test_validate_collects_all_diagnostics() -> Result<()> {
+        let mut meta_file = tempfile::Builder::new().suffix(".yml").tempfile()?;
+        let meta = fs::read_to_string("./tests/test-metadata-CWL-validated.yml")?
+            .replace("orcid: 0000-0003-2765-0049", "orcid: invalid-orcid")
+            .replace("type: CWL", "type: UNKNOWN")
+            .replace(
+                "    - id: test_1\n",
+                "    - id: test_1\n      files: []\n    - id: test_1\n",
+            );
+        meta_file.write_all(meta.as_bytes())?;
+        let license_registry = license::LicenseRegistry::new_local(&None::<String>)?;
+        let (meta, diagnostics) = validate(
+            meta_file.path().to_string_lossy(),
+            "",
+            &license_registry,
+            true,
+        );
+        assert!(meta.is_some());
+        let paths = diagnostics
+            .0
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "authors[0].orcid",
+                "workflow.language.type",
+                "workflow.testing[1].id"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_schema_error() -> Result<()> {
+        let mut meta_file = tempfile::Builder::new().suffix(".yml").tempfile()?;
+        let meta = fs::read_to_string("./tests/test-metadata-CWL-validated.yml")?
+            .replace("type: primary", "type: main");
+        meta_file.write_all(meta.as_bytes())?;
+        let license_registry = license::LicenseRegistry::new_local(&None::<String>)?;
+        let (meta, diagnostics) = validate(
+            meta_file.path().to_string_lossy(),
+            "",
+            &license_registry,
+            true,
+        );
+        assert!(meta.is_none());
+        assert_eq!(diagnostics.0.len(), 1);
+        assert_eq!(diagnostics.0[0].path, "workflow.files[1].type");
+        assert!(diagnostics.0[0].line.is_some());
         Ok(())
     }
 }
@@ -0,0 +1,178 @@
+use crate::metadata;
+use crate::trs;
+
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use url::Url;
+use uuid::Uuid;
+
+/// Published next to the TRS response of each verified workflow version.
+pub const TEST_CACHE_FILE: &str = "yevis-test-cache.json";
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TestCacheEntry {
+    pub cache_key: String,
+}
+
+/// Workflow versions whose tests can be skipped in `publish --with-test`,
+/// because their cache key matches the one published with the last verified version.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TestCache {
+    keys: HashMap<(Uuid, String), String>,
+    hits: HashSet<(Uuid, String)>,
+}
+
+impl TestCache {
+    pub fn new(
+        meta_vec: &[metadata::types::Metadata],
+        owner: impl AsRef<str>,
+        name: impl AsRef<str>,
+    ) -> Result<Self> {
+        let trs_endpoint = trs::api::TrsEndpoint::new_gh_pages(&owner, &name)?;
+        let tools = trs::api::get_tools(&trs_endpoint).unwrap_or_default();
+        let mut cache = Self::default();
+        for meta in meta_vec {
+            let key = cache_key(meta)?;
+            let verified = tools
+                .iter()
+                .find(|t| t.id == meta.id)
+                .and_then(|t| t.versions.iter().find(|v| v.version() == meta.version))
+                .is_some_and(|v| v.verified == Some(true));
+            if verified {
+                match get_test_cache_entry(&trs_endpoint, &meta.id, &meta.version) {
+                    Ok(entry) if entry.cache_key == key => {
+                        info!(
+                            "Skip testing workflow_id: {}, version: {}, as it is unchanged since the last verified publish",
+                            meta.id, meta.version
+                        );
+                        cache.hits.insert((meta.id, meta.version.clone()));
+                    }
+                    Ok(_) => debug!(
+                        "The cache key of workflow_id: {}, version: {} has changed",
+                        meta.id, meta.version
+                    ),
+                    Err(e) => debug!(
+                        "No test cache of workflow_id: {}, version: {}: {}",
+                        meta.id, meta.version, e
+                    ),
+                }
+            }
+            cache.keys.insert((meta.id, meta.version.clone()), key);
+        }
+        Ok(cache)
+    }
+
+    pub fn hit(&self, meta: &metadata::types::Metadata) -> bool {
+        self.hits.contains(&(meta.id, meta.version.clone()))
+    }
+
+    /// `tools/{id}/versions/{version}/yevis-test-cache.json` -> content
+    pub fn contents(&self, meta: &metadata::types::Metadata) -> Result<Option<(PathBuf, String)>> {
+        match self.keys.get(&(meta.id, meta.version.clone())) {
+            Some(key) => Ok(Some((
+                PathBuf::from(format!(
+                    "tools/{}/versions/{}/{}",
+                    meta.id, meta.version, TEST_CACHE_FILE
+                )),
+                serde_json::to_string(&TestCacheEntry {
+                    cache_key: key.clone(),
+                })?,
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// sha256 of the contents of the workflow files and the test files (as the TRS `checksum` of them),
+/// the language version, and the engines and expected outputs of the test cases.
+pub fn cache_key(meta: &metadata::types::Metadata) -> Result<String> {
+    cache_key_with(meta, |url| {
+        Ok(trs::types::Checksum::new_from_url(url)?.checksum)
+    })
+}
+
+/// `checksum`: the checksum of the content of the file URL
+fn cache_key_with(
+    meta: &metadata::types::Metadata,
+    checksum: impl Fn(&Url) -> Result<String>,
+) -> Result<String> {
+    let target = |target: &Option<PathBuf>| -> Result<String> {
+        Ok(target
+            .as_ref()
+            .ok_or_else(|| anyhow!("No target in the metadata"))?
+            .to_string_lossy()
+            .to_string())
+    };
+    let mut entries = vec![format!(
+        "language {} {}",
+        meta.workflow.language.r#type, meta.workflow.language.version
+    )];
+    for file in &meta.workflow.files {
+        entries.push(format!(
+            "workflow_file {} {}",
+            target(&file.target)?,
+            checksum(&file.url)?
+        ));
+    }
+    for test_case in &meta.workflow.testing {
+        for file in &test_case.files {
+            entries.push(format!(
+                "test_file {} {} {}",
+                test_case.id,
+                target(&file.target)?,
+                checksum(&file.url)?
+            ));
+        }
+        for engine in test_case.engines(&meta.workflow.language.r#type)? {
+            entries.push(format!("engine {} {}", test_case.id, engine));
+        }
+        entries.push(format!(
+            "expected_outputs {} {}",
+            test_case.id,
+            serde_json::to_string(&test_case.expected_outputs)?
+        ));
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(entries.join("\n"));
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn get_test_cache_entry(
+    trs_endpoint: &trs::api::TrsEndpoint,
+    id: &Uuid,
+    version: impl AsRef<str>,
+) -> Result<TestCacheEntry> {
+    let url = Url::parse(&format!(
+        "{}/tools/{}/versions/{}/{}",
+        trs_endpoint.url.as_str().trim().trim_matches('/'),
+        id,
+        version.as_ref(),
+        TEST_CACHE_FILE
+    ))?;
+    let body = trs::api::get_request(&url)?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key() -> Result<()> {
+        let checksum = |url: &Url| -> Result<String> { Ok(format!("sha256 of {}", url)) };
+        let mut meta = metadata::io::test_metadata()?;
+        let key = cache_key_with(&meta, checksum)?;
+        assert_eq!(key, cache_key_with(&meta, checksum)?);
+        meta.workflow.testing[0].engines = vec![metadata::types::Engine {
+            name: "toil".to_string(),
+            version: None,
+        }];
+        assert_ne!(key, cache_key_with(&meta, checksum)?);
+        Ok(())
+    }
+}
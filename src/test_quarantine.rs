@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+pub const DEFAULT_QUARANTINE_FILE: &str = "yevis-quarantine.yml";
+
+/// Test cases known to be flaky, listed in the quarantine file of the registry.
+/// Their failures are reported, but do not fail `yevis test`.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Quarantine {
+    #[serde(default)]
+    pub tests: Vec<QuarantinedTest>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct QuarantinedTest {
+    pub workflow_id: Uuid,
+    /// All the versions if not specified
+    pub version: Option<String>,
+    /// The test ID, on all the engines in the test matrix
+    pub test_id: String,
+    /// Why the test case is quarantined, e.g., a download mirror that often times out
+    pub reason: Option<String>,
+}
+
+impl Quarantine {
+    /// An empty quarantine if the file does not exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        if !path.as_ref().exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path.as_ref())?;
+        serde_yaml::from_str(&content).with_context(|| {
+            format!(
+                "Failed to parse the quarantine file {}",
+                path.as_ref().display()
+            )
+        })
+    }
+
+    pub fn find(
+        &self,
+        workflow_id: &Uuid,
+        version: impl AsRef<str>,
+        test_id: impl AsRef<str>,
+    ) -> Option<&QuarantinedTest> {
+        self.tests.iter().find(|test| {
+            test.workflow_id == *workflow_id
+                && test.version.as_ref().is_none_or(|v| v == version.as_ref())
+                && test.test_id == test_id.as_ref()
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_not_found() -> Result<()> {
+        let quarantine = Quarantine::load("/tmp/yevis-quarantine-not-found.yml")?;
+        assert!(quarantine.tests.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find() -> Result<()> {
+        let quarantine: Quarantine = serde_yaml::from_str(
+            r#"
+tests:
+  - workflow_id: c13b6e27-a4ee-426f-8bdb-8cf5c4310bad
+    test_id: test_1
+    reason: The download mirror often times out
+  - workflow_id: c13b6e27-a4ee-426f-8bdb-8cf5c4310bad
+    version: 1.0.0
+    test_id: test_2
+"#,
+        )?;
+        let workflow_id = Uuid::parse_str("c13b6e27-a4ee-426f-8bdb-8cf5c4310bad")?;
+        assert!(quarantine.find(&workflow_id, "2.0.0", "test_1").is_some());
+        assert!(quarantine.find(&workflow_id, "1.0.0", "test_2").is_some());
+        assert!(quarantine.find(&workflow_id, "2.0.0", "test_2").is_none());
+        assert!(quarantine
+            .find(&Uuid::new_v4(), "1.0.0", "test_1")
+            .is_none());
+        Ok(())
+    }
+}
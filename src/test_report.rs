@@ -0,0 +1,343 @@
+use crate::wes;
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Result of one test case, written to the test reports.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct TestCaseReport {
+    pub workflow_id: Uuid,
+    pub version: String,
+    pub test_id: String,
+    /// e.g., `toil 5.6.0`
+    pub engine: String,
+    pub status: wes::api::RunStatus,
+    /// From the submission to the end of the run, in seconds
+    pub duration: f64,
+    pub run_id: String,
+    /// Last lines of the stderr in the WES run log
+    pub stderr_tail: Option<String>,
+    pub output_assertions: Vec<OutputAssertion>,
+    /// Earlier runs of the test case, which did not pass and were retried
+    pub previous_attempts: Vec<Attempt>,
+    /// Listed in the quarantine file, so a failure does not fail the test
+    pub quarantined: bool,
+}
+
+/// Run of a test case that did not pass and was retried.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Attempt {
+    pub run_id: String,
+    pub status: wes::api::RunStatus,
+    pub duration: f64,
+}
+
+/// Result of the assertions on one of `expected_outputs`.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct OutputAssertion {
+    pub file_name: String,
+    pub passed: bool,
+    pub failures: Vec<String>,
+}
+
+impl TestCaseReport {
+    pub fn passed(&self) -> bool {
+        self.status == wes::api::RunStatus::Complete
+    }
+
+    /// Failed or timed out, and not quarantined
+    pub fn failed(&self) -> bool {
+        !self.passed() && !self.quarantined
+    }
+
+    /// Failed or timed out, but quarantined
+    pub fn quarantined_failure(&self) -> bool {
+        !self.passed() && self.quarantined
+    }
+
+    fn failure_message(&self) -> String {
+        let failures = self
+            .output_assertions
+            .iter()
+            .flat_map(|a| {
+                a.failures
+                    .iter()
+                    .map(move |f| format!("{}: {}", a.file_name, f))
+            })
+            .collect::<Vec<_>>();
+        match (&self.status, failures.is_empty()) {
+            (wes::api::RunStatus::TimedOut, _) => format!(
+                "WES run {} timed out after {:.0} seconds and was canceled",
+                self.run_id, self.duration
+            ),
+            (_, true) => format!(
+                "WES run {} finished with status {:?}",
+                self.run_id, self.status
+            ),
+            (_, false) => format!("Output assertions failed: {}", failures.join("; ")),
+        }
+    }
+}
+
+/// `wes_image` is the digest of the sapporo-service image, if started by yevis.
+pub fn to_json(reports: &[TestCaseReport], wes_image: &Option<String>) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&json!({
+        "wes_image": wes_image,
+        "tests": reports.len(),
+        "failures": reports.iter().filter(|r| r.failed()).count(),
+        "quarantined_failures": reports.iter().filter(|r| r.quarantined_failure()).count(),
+        "test_cases": reports,
+    }))?)
+}
+
+/// JUnit XML in the format read by most CI test dashboards.
+/// Each workflow version is a `testsuite`, and each test case is a `testcase`.
+/// `wes_image` is recorded as a property of each `testsuite`.
+/// A quarantined failure is `skipped` so as not to fail CI, and the retried runs are listed in `system-out`.
+pub fn to_junit(reports: &[TestCaseReport], wes_image: &Option<String>) -> Result<String> {
+    let mut suites: Vec<(&Uuid, &str, Vec<&TestCaseReport>)> = vec![];
+    for report in reports {
+        match suites
+            .iter_mut()
+            .find(|(id, version, _)| **id == report.workflow_id && *version == report.version)
+        {
+            Some((_, _, cases)) => cases.push(report),
+            None => suites.push((&report.workflow_id, &report.version, vec![report])),
+        }
+    }
+
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        xml,
+        r#"<testsuites name="yevis test" tests="{}" failures="{}" skipped="{}" time="{:.3}">"#,
+        reports.len(),
+        reports.iter().filter(|r| r.failed()).count(),
+        reports.iter().filter(|r| r.quarantined_failure()).count(),
+        reports.iter().map(|r| r.duration).sum::<f64>()
+    )?;
+    for (id, version, cases) in suites {
+        writeln!(
+            xml,
+            r#"  <testsuite name="{}_{}" tests="{}" failures="{}" skipped="{}" time="{:.3}">"#,
+            id,
+            escape_xml(version),
+            cases.len(),
+            cases.iter().filter(|r| r.failed()).count(),
+            cases.iter().filter(|r| r.quarantined_failure()).count(),
+            cases.iter().map(|r| r.duration).sum::<f64>()
+        )?;
+        if let Some(wes_image) = wes_image {
+            writeln!(xml, "    <properties>")?;
+            writeln!(
+                xml,
+                r#"      <property name="wes_image" value="{}"/>"#,
+                escape_xml(wes_image)
+            )?;
+            writeln!(xml, "    </properties>")?;
+        }
+        for case in cases {
+            writeln!(
+                xml,
+                r#"    <testcase name="{} [{}]" classname="{}_{}" time="{:.3}">"#,
+                escape_xml(&case.test_id),
+                escape_xml(&case.engine),
+                id,
+                escape_xml(version),
+                case.duration
+            )?;
+            if case.failed() {
+                writeln!(
+                    xml,
+                    r#"      <failure message="{}" type="{:?}"/>"#,
+                    escape_xml(case.failure_message()),
+                    case.status
+                )?;
+            }
+            if case.quarantined_failure() {
+                writeln!(
+                    xml,
+                    r#"      <skipped message="{}"/>"#,
+                    escape_xml(format!("Quarantined: {}", case.failure_message()))
+                )?;
+            }
+            let mut system_out = case
+                .previous_attempts
+                .iter()
+                .enumerate()
+                .map(|(i, attempt)| {
+                    format!(
+                        "Attempt {}: WES run_id: {}, status: {:?}, duration: {:.3}",
+                        i + 1,
+                        attempt.run_id,
+                        attempt.status,
+                        attempt.duration
+                    )
+                })
+                .collect::<Vec<_>>();
+            system_out.push(format!("WES run_id: {}", case.run_id));
+            writeln!(
+                xml,
+                "      <system-out>{}</system-out>",
+                escape_xml(system_out.join("\n"))
+            )?;
+            if let Some(stderr_tail) = &case.stderr_tail {
+                writeln!(
+                    xml,
+                    "      <system-err>{}</system-err>",
+                    escape_xml(stderr_tail)
+                )?;
+            }
+            writeln!(xml, "    </testcase>")?;
+        }
+        writeln!(xml, "  </testsuite>")?;
+    }
+    writeln!(xml, "</testsuites>")?;
+    Ok(xml)
+}
+
+fn escape_xml(s: impl AsRef<str>) -> String {
+    s.as_ref()
+        .chars()
+        .filter(|c| matches!(c, '\t' | '\n' | '\r') || !c.is_control())
+        .fold(String::new(), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                c => escaped.push(c),
+            };
+            escaped
+        })
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn reports() -> Vec<TestCaseReport> {
+        let workflow_id = Uuid::parse_str("c13b6e27-a4ee-426f-8bdb-8cf5c4310bad").unwrap();
+        vec![
+            TestCaseReport {
+                workflow_id,
+                version: "1.0.0".to_string(),
+                test_id: "test_1".to_string(),
+                engine: "cwltool".to_string(),
+                status: wes::api::RunStatus::Complete,
+                duration: 12.5,
+                run_id: "run_1".to_string(),
+                stderr_tail: None,
+                output_assertions: vec![],
+                previous_attempts: vec![],
+                quarantined: false,
+            },
+            TestCaseReport {
+                workflow_id,
+                version: "1.0.0".to_string(),
+                test_id: "test_2".to_string(),
+                engine: "toil 5.6.0".to_string(),
+                status: wes::api::RunStatus::Failed,
+                duration: 3.0,
+                run_id: "run_2".to_string(),
+                stderr_tail: Some("Error: <missing> input".to_string()),
+                output_assertions: vec![OutputAssertion {
+                    file_name: "out.txt".to_string(),
+                    passed: false,
+                    failures: vec!["line count is 3, expected 2".to_string()],
+                }],
+                previous_attempts: vec![],
+                quarantined: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_to_json() -> Result<()> {
+        let json: Value = serde_json::from_str(&to_json(&reports(), &None)?)?;
+        assert_eq!(json["wes_image"], Value::Null);
+        assert_eq!(json["tests"], 2);
+        assert_eq!(json["failures"], 1);
+        assert_eq!(json["test_cases"][1]["status"], "Failed");
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_junit() -> Result<()> {
+        let xml = to_junit(&reports(), &None)?;
+        assert!(xml.contains(
+            r#"<testsuites name="yevis test" tests="2" failures="1" skipped="0" time="15.500">"#
+        ));
+        assert!(xml.contains(
+            r#"<testsuite name="c13b6e27-a4ee-426f-8bdb-8cf5c4310bad_1.0.0" tests="2" failures="1" skipped="0""#
+        ));
+        assert!(xml.contains(
+            r#"<failure message="Output assertions failed: out.txt: line count is 3, expected 2" type="Failed"/>"#
+        ));
+        assert!(xml.contains(r#"<testcase name="test_2 [toil 5.6.0]""#));
+        assert!(xml.contains("<system-err>Error: &lt;missing&gt; input</system-err>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_junit_timed_out() -> Result<()> {
+        let mut reports = reports();
+        reports[0].status = wes::api::RunStatus::TimedOut;
+        let xml = to_junit(&reports, &None)?;
+        assert!(xml.contains(
+            r#"<failure message="WES run run_1 timed out after 12 seconds and was canceled" type="TimedOut"/>"#
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quarantined_and_retried() -> Result<()> {
+        let mut reports = reports();
+        reports[0].previous_attempts = vec![Attempt {
+            run_id: "run_0".to_string(),
+            status: wes::api::RunStatus::Failed,
+            duration: 2.0,
+        }];
+        reports[1].quarantined = true;
+
+        let json: Value = serde_json::from_str(&to_json(&reports, &None)?)?;
+        assert_eq!(json["failures"], 0);
+        assert_eq!(json["quarantined_failures"], 1);
+        assert_eq!(
+            json["test_cases"][0]["previous_attempts"][0]["status"],
+            "Failed"
+        );
+
+        let xml = to_junit(&reports, &None)?;
+        assert!(xml.contains(r#"<testsuites name="yevis test" tests="2" failures="0" skipped="1""#));
+        assert!(!xml.contains("<failure"));
+        assert!(xml.contains(
+            r#"<skipped message="Quarantined: Output assertions failed: out.txt: line count is 3, expected 2"/>"#
+        ));
+        assert!(xml.contains(
+            "<system-out>Attempt 1: WES run_id: run_0, status: Failed, duration: 2.000\nWES run_id: run_1</system-out>"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wes_image() -> Result<()> {
+        let wes_image = Some("ghcr.io/sapporo-wes/sapporo-service@sha256:def".to_string());
+        let json: Value = serde_json::from_str(&to_json(&reports(), &wes_image)?)?;
+        assert_eq!(
+            json["wes_image"],
+            "ghcr.io/sapporo-wes/sapporo-service@sha256:def"
+        );
+        let xml = to_junit(&reports(), &wes_image)?;
+        assert!(xml.contains(
+            r#"<property name="wes_image" value="ghcr.io/sapporo-wes/sapporo-service@sha256:def"/>"#
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use url::Url;
+use uuid::Uuid;
+
+pub const DEFAULT_STATE_FILE: &str = "yevis-test-state.json";
+
+/// WES runs submitted by `yevis test`, saved to re-attach to them with `--resume`.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct TestState {
+    pub runs: Vec<RunState>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RunState {
+    pub workflow_id: Uuid,
+    pub version: String,
+    /// The test ID, with the engine in the test matrix
+    pub test_id: String,
+    pub wes_location: Url,
+    pub run_id: String,
+}
+
+impl TestState {
+    /// An empty state if the file does not exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        if !path.as_ref().exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path.as_ref())?;
+        serde_json::from_str(&content).with_context(|| {
+            format!(
+                "Failed to parse the test state file {}",
+                path.as_ref().display()
+            )
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path.as_ref(), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn find(
+        &self,
+        workflow_id: &Uuid,
+        version: impl AsRef<str>,
+        test_id: impl AsRef<str>,
+        wes_location: &Url,
+    ) -> Option<&RunState> {
+        self.runs.iter().find(|run| {
+            run.workflow_id == *workflow_id
+                && run.version == version.as_ref()
+                && run.test_id == test_id.as_ref()
+                && run.wes_location == *wes_location
+        })
+    }
+
+    /// Replace the run of the same test case on the same WES, if any.
+    pub fn upsert(&mut self, run: RunState) {
+        self.runs.retain(|saved| {
+            !(saved.workflow_id == run.workflow_id
+                && saved.version == run.version
+                && saved.test_id == run.test_id
+                && saved.wes_location == run.wes_location)
+        });
+        self.runs.push(run);
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    fn run_state(run_id: &str) -> Result<RunState> {
+        Ok(RunState {
+            workflow_id: Uuid::parse_str("c13b6e27-a4ee-426f-8bdb-8cf5c4310bad")?,
+            version: "1.0.0".to_string(),
+            test_id: "test_1".to_string(),
+            wes_location: Url::parse("http://localhost:1122")?,
+            run_id: run_id.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_upsert_and_find() -> Result<()> {
+        let mut state = TestState::default();
+        state.upsert(run_state("run_1")?);
+        state.upsert(run_state("run_2")?);
+        assert_eq!(state.runs.len(), 1);
+        let saved = run_state("run_2")?;
+        assert_eq!(
+            state.find(
+                &saved.workflow_id,
+                "1.0.0",
+                "test_1",
+                &Url::parse("http://localhost:1122")?
+            ),
+            Some(&saved)
+        );
+        assert!(state
+            .find(
+                &saved.workflow_id,
+                "1.0.0",
+                "test_1",
+                &Url::parse("http://localhost:8080")?
+            )
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_load() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("yevis-test-state-{}.json", Uuid::new_v4()));
+        assert_eq!(TestState::load(&path)?, TestState::default());
+        let mut state = TestState::default();
+        state.upsert(run_state("run_1")?);
+        state.save(&path)?;
+        assert_eq!(TestState::load(&path)?, state);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
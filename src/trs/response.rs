@@ -2,10 +2,11 @@ use crate::metadata;
 use crate::remote;
 use crate::trs;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use uuid::Uuid;
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -15,6 +16,8 @@ pub struct TrsResponse {
     pub tool_classes: Vec<trs::types::ToolClass>,
     pub tools: Vec<trs::types::Tool>,
     pub tools_descriptor: HashMap<(Uuid, String), trs::types::FileWrapper>,
+    /// (target, descriptor) of each workflow file, for `{type}/descriptor/{relative_path}`
+    pub tools_descriptor_files: HashMap<(Uuid, String), Vec<(PathBuf, trs::types::FileWrapper)>>,
     pub tools_files: HashMap<(Uuid, String), Vec<trs::types::ToolFile>>,
     pub tools_tests: HashMap<(Uuid, String), Vec<trs::types::FileWrapper>>,
 }
@@ -39,6 +42,7 @@ impl TrsResponse {
             tool_classes,
             tools,
             tools_descriptor: HashMap::new(),
+            tools_descriptor_files: HashMap::new(),
             tools_files: HashMap::new(),
             tools_tests: HashMap::new(),
         })
@@ -66,6 +70,10 @@ impl TrsResponse {
 
         self.tools_descriptor
             .insert((meta.id, meta.version.clone()), generate_descriptor(meta)?);
+        self.tools_descriptor_files.insert(
+            (meta.id, meta.version.clone()),
+            generate_descriptor_files(meta)?,
+        );
         self.tools_files
             .insert((meta.id, meta.version.clone()), generate_files(meta)?);
         self.tools_tests
@@ -111,6 +119,37 @@ pub fn generate_descriptor(meta: &metadata::types::Metadata) -> Result<trs::type
     })
 }
 
+/// The descriptor of each workflow file (including the primary one) with its `target` as the relative path.
+pub fn generate_descriptor_files(
+    meta: &metadata::types::Metadata,
+) -> Result<Vec<(PathBuf, trs::types::FileWrapper)>> {
+    meta.workflow
+        .files
+        .iter()
+        .map(|f| {
+            let target = f
+                .target
+                .clone()
+                .ok_or_else(|| anyhow!("No target in the metadata"))?;
+            let (content, checksum) = match remote::fetch_raw_content(&f.url) {
+                Ok(content) => {
+                    let checksum = trs::types::Checksum::new_from_string(content.clone());
+                    (Some(content), Some(vec![checksum]))
+                }
+                Err(_) => (None, None),
+            };
+            Ok((
+                target,
+                trs::types::FileWrapper {
+                    content,
+                    checksum,
+                    url: Some(f.url.clone()),
+                },
+            ))
+        })
+        .collect()
+}
+
 pub fn generate_files(meta: &metadata::types::Metadata) -> Result<Vec<trs::types::ToolFile>> {
     Ok(meta
         .workflow
@@ -6,6 +6,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use sha2::{Digest, Sha256};
+use std::fmt;
 use url::Url;
 use uuid::Uuid;
 
@@ -229,7 +230,10 @@ impl Tool {
         let organization = meta
             .authors
             .iter()
-            .map(|a| format!("@{}", a.github_account))
+            .map(|a| match &a.github_account {
+                Some(github_account) => format!("@{}", github_account),
+                None => a.name.clone(),
+            })
             .collect::<Vec<_>>()
             .join(", ");
         Ok(Self {
@@ -329,7 +333,7 @@ impl ToolVersion {
             author: Some(
                 meta.authors
                     .iter()
-                    .map(|a| a.github_account.clone())
+                    .map(|a| a.github_account.clone().unwrap_or_else(|| a.name.clone()))
                     .collect::<Vec<String>>(),
             ),
             name: Some(meta.workflow.name.clone()),
@@ -382,7 +386,7 @@ impl ToolVersion {
         self.author = Some(
             meta.authors
                 .iter()
-                .map(|a| a.github_account.clone())
+                .map(|a| a.github_account.clone().unwrap_or_else(|| a.name.clone()))
                 .collect::<Vec<String>>(),
         );
         self.name = Some(meta.workflow.name.clone());
@@ -451,7 +455,7 @@ impl DescriptorType {
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "UPPERCASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum DescriptorTypeWithPlain {
     Cwl,
     Wdl,
@@ -465,6 +469,36 @@ pub enum DescriptorTypeWithPlain {
     PlainGalaxy,
 }
 
+impl DescriptorTypeWithPlain {
+    /// The `PLAIN_*` type, whose descriptor endpoints return the raw content of the files.
+    pub fn new_plain(wf_type: &metadata::types::LanguageType) -> Option<Self> {
+        match wf_type {
+            metadata::types::LanguageType::Cwl => Some(DescriptorTypeWithPlain::PlainCwl),
+            metadata::types::LanguageType::Wdl => Some(DescriptorTypeWithPlain::PlainWdl),
+            metadata::types::LanguageType::Nfl => Some(DescriptorTypeWithPlain::PlainNfl),
+            metadata::types::LanguageType::Smk => Some(DescriptorTypeWithPlain::PlainSmk),
+            metadata::types::LanguageType::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for DescriptorTypeWithPlain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptorTypeWithPlain::Cwl => write!(f, "CWL"),
+            DescriptorTypeWithPlain::Wdl => write!(f, "WDL"),
+            DescriptorTypeWithPlain::Nfl => write!(f, "NFL"),
+            DescriptorTypeWithPlain::Smk => write!(f, "SMK"),
+            DescriptorTypeWithPlain::Galaxy => write!(f, "GALAXY"),
+            DescriptorTypeWithPlain::PlainCwl => write!(f, "PLAIN_CWL"),
+            DescriptorTypeWithPlain::PlainWdl => write!(f, "PLAIN_WDL"),
+            DescriptorTypeWithPlain::PlainNfl => write!(f, "PLAIN_NFL"),
+            DescriptorTypeWithPlain::PlainSmk => write!(f, "PLAIN_SMK"),
+            DescriptorTypeWithPlain::PlainGalaxy => write!(f, "PLAIN_GALAXY"),
+        }
+    }
+}
+
 /// One of url or content is required.
 #[skip_serializing_none]
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -511,6 +545,18 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_descriptor_type_with_plain() -> Result<()> {
+        let plain =
+            DescriptorTypeWithPlain::new_plain(&metadata::types::LanguageType::Cwl).unwrap();
+        assert_eq!(plain.to_string(), "PLAIN_CWL");
+        assert_eq!(serde_json::to_string(&plain)?, r#""PLAIN_CWL""#);
+        assert!(
+            DescriptorTypeWithPlain::new_plain(&metadata::types::LanguageType::Unknown).is_none()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_file_type_new_from_file_type() -> Result<()> {
         let file_type = FileType::new_from_file_type(&metadata::types::FileType::Primary);
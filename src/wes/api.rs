@@ -4,7 +4,9 @@ use anyhow::{anyhow, bail, ensure, Result};
 use log::info;
 use reqwest::blocking::multipart;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::thread;
@@ -54,30 +56,63 @@ pub fn get_supported_wes_versions(wes_loc: &Url) -> Result<Vec<String>> {
     Ok(supported_wes_versions)
 }
 
+/// `workflow_engine_versions` in the service-info: engine name -> versions.
+/// WES 1.0 and sapporo-service give a version string, and WES 1.1 gives `{workflow_engine_version: [...]}`.
+pub fn get_workflow_engine_versions(wes_loc: &Url) -> Result<HashMap<String, Vec<String>>> {
+    let res = get_service_info(wes_loc)?;
+    parse_workflow_engine_versions(&res)
+}
+
+fn parse_workflow_engine_versions(service_info: &Value) -> Result<HashMap<String, Vec<String>>> {
+    let err_msg = "Failed to parse `workflow_engine_versions` in the service-info";
+    let engine_versions = match service_info.get("workflow_engine_versions") {
+        Some(Value::Object(engine_versions)) => engine_versions,
+        Some(Value::Null) | None => return Ok(HashMap::new()),
+        Some(_) => bail!(err_msg),
+    };
+    engine_versions
+        .iter()
+        .map(|(name, versions)| {
+            let versions = match versions {
+                Value::String(version) => vec![version.clone()],
+                Value::Array(versions) => versions
+                    .iter()
+                    .filter_map(|v| v.as_str().map(|v| v.to_string()))
+                    .collect(),
+                Value::Object(_) => versions
+                    .get("workflow_engine_version")
+                    .and_then(|v| v.as_array())
+                    .ok_or_else(|| anyhow!(err_msg))?
+                    .iter()
+                    .filter_map(|v| v.as_str().map(|v| v.to_string()))
+                    .collect(),
+                _ => bail!(err_msg),
+            };
+            Ok((name.clone(), versions))
+        })
+        .collect()
+}
+
 pub fn test_case_to_form(
     meta: &metadata::types::Metadata,
     test_case: &metadata::types::Testing,
+    engine: &metadata::types::Engine,
 ) -> Result<multipart::Form> {
     let mut meta_cloned = meta.clone();
     meta_cloned.workflow.testing = vec![test_case.clone()];
 
-    let form = multipart::Form::new()
+    let mut form = multipart::Form::new()
         .text("workflow_type", meta.workflow.language.r#type.to_string())
         .text(
             "workflow_type_version",
             meta.workflow.language.version.clone(),
         )
         .text("workflow_url", wf_url(&meta.workflow)?)
-        .text(
-            "workflow_engine_name",
-            match meta.workflow.language.r#type {
-                metadata::types::LanguageType::Cwl => "cwltool",
-                metadata::types::LanguageType::Wdl => "cromwell",
-                metadata::types::LanguageType::Nfl => "nextflow",
-                metadata::types::LanguageType::Smk => "snakemake",
-                _ => bail!("Unsupported workflow language type"),
-            },
-        )
+        .text("workflow_engine_name", engine.name.clone());
+    if let Some(version) = &engine.version {
+        form = form.text("workflow_engine_version", version.clone());
+    }
+    let form = form
         .text("workflow_params", test_case.wf_params()?)
         .text("workflow_engine_parameters", test_case.wf_engine_params()?)
         .text(
@@ -88,6 +123,55 @@ pub fn test_case_to_form(
     Ok(form)
 }
 
+/// GA4GH WES 1.0/1.1: the workflow files and the `other` test files are uploaded as `workflow_attachment`,
+/// and `workflow_url` is the path of the primary workflow in the attachments.
+pub fn test_case_to_standard_form(
+    meta: &metadata::types::Metadata,
+    test_case: &metadata::types::Testing,
+) -> Result<multipart::Form> {
+    let primary_wf = meta.workflow.primary_wf()?;
+    let primary_wf_target = primary_wf
+        .target
+        .clone()
+        .ok_or_else(|| anyhow!("No target in the primary workflow"))?;
+    let mut form = multipart::Form::new()
+        .text("workflow_type", meta.workflow.language.r#type.to_string())
+        .text(
+            "workflow_type_version",
+            meta.workflow.language.version.clone(),
+        )
+        .text(
+            "workflow_url",
+            primary_wf_target.to_string_lossy().to_string(),
+        )
+        .text("workflow_params", test_case.wf_params()?)
+        .text("workflow_engine_parameters", test_case.wf_engine_params()?)
+        .text(
+            "tags",
+            json!({
+                "yevis_workflow_id": meta.id,
+                "yevis_version": meta.version,
+                "yevis_test_id": test_case.id,
+            })
+            .to_string(),
+        );
+    let mut attachments = vec![AttachedFile::new_from_file(&primary_wf)];
+    attachments.extend(
+        serde_json::from_str::<Vec<AttachedFile>>(&wf_attachment(&meta.workflow, test_case)?)?
+            .into_iter()
+            .filter(|f| f.file_name != primary_wf_target),
+    );
+    for attachment in attachments {
+        let content = download_file(&attachment.file_url)?;
+        form = form.part(
+            "workflow_attachment",
+            multipart::Part::bytes(content)
+                .file_name(attachment.file_name.to_string_lossy().to_string()),
+        );
+    }
+    Ok(form)
+}
+
 pub fn wf_url(wf: &metadata::types::Workflow) -> Result<String> {
     let primary_wf = wf.primary_wf()?;
     match wf.language.r#type {
@@ -184,6 +268,8 @@ pub enum RunStatus {
     Running,
     Complete,
     Failed,
+    /// Canceled by yevis after the timeout
+    TimedOut,
 }
 
 impl FromStr for RunStatus {
@@ -243,6 +329,26 @@ pub fn get_run_status(wes_loc: &Url, run_id: impl AsRef<str>) -> Result<RunStatu
     )
 }
 
+pub fn cancel_run(wes_loc: &Url, run_id: impl AsRef<str>) -> Result<()> {
+    let url = Url::parse(&format!(
+        "{}/runs/{}/cancel",
+        wes_loc.as_str().trim().trim_end_matches('/'),
+        run_id.as_ref()
+    ))?;
+    let client = reqwest::blocking::Client::new();
+    let response = client
+        .post(url.as_str())
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()?;
+    ensure!(
+        response.status().is_success(),
+        "Failed to cancel run with status: {} from {}",
+        response.status(),
+        url.as_str()
+    );
+    Ok(())
+}
+
 pub fn get_run_log(wes_loc: &Url, run_id: impl AsRef<str>) -> Result<Value> {
     let url = Url::parse(&format!(
         "{}/runs/{}",
@@ -264,6 +370,124 @@ pub fn get_run_log(wes_loc: &Url, run_id: impl AsRef<str>) -> Result<Value> {
     Ok(res_body)
 }
 
+/// Output files in the run log: (file_name, file_url).
+/// Sapporo lists them as `outputs: [{file_name, file_url}]`.
+/// Other WES return the outputs of the workflow engine as an object (e.g., CWL output object),
+/// in which the files are the objects with `location`.
+pub fn get_run_outputs(wes_loc: &Url, run_log: &Value) -> Result<Vec<(String, Url)>> {
+    let err_msg = "Failed to parse `outputs` in the run log";
+    let outputs = match run_log.get("outputs") {
+        Some(Value::Array(outputs)) => outputs,
+        Some(outputs @ Value::Object(_)) => {
+            let mut files = vec![];
+            collect_output_files(outputs, &mut files);
+            return Ok(files);
+        }
+        Some(Value::Null) | None => return Ok(vec![]),
+        Some(_) => bail!(err_msg),
+    };
+    outputs
+        .iter()
+        .map(|output| {
+            let file_name = output
+                .get("file_name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!(err_msg))?;
+            let file_url = output
+                .get("file_url")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!(err_msg))?;
+            // relative to the WES location if not an absolute URL
+            let file_url = match Url::parse(file_url) {
+                Ok(url) => url,
+                Err(_) => Url::parse(&format!(
+                    "{}/{}",
+                    wes_loc.as_str().trim().trim_end_matches('/'),
+                    file_url.trim_start_matches('/')
+                ))?,
+            };
+            Ok((file_name.to_string(), file_url))
+        })
+        .collect()
+}
+
+/// `{"class": "File", "location": "...", "basename": "..."}` in the CWL output object, or a URL string.
+fn collect_output_files(value: &Value, files: &mut Vec<(String, Url)>) {
+    match value {
+        Value::Object(object) => {
+            let location = object
+                .get("location")
+                .or_else(|| object.get("path"))
+                .and_then(|v| v.as_str())
+                .and_then(|v| Url::parse(v).ok());
+            match location {
+                Some(location) => {
+                    let file_name = match object.get("basename").and_then(|v| v.as_str()) {
+                        Some(basename) => basename.to_string(),
+                        None => location
+                            .path_segments()
+                            .and_then(|mut segments| segments.next_back())
+                            .unwrap_or_default()
+                            .to_string(),
+                    };
+                    files.push((file_name, location));
+                }
+                None => object
+                    .values()
+                    .for_each(|value| collect_output_files(value, files)),
+            }
+        }
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| collect_output_files(value, files)),
+        Value::String(location) => {
+            if let Ok(location) = Url::parse(location) {
+                if let Some(file_name) = location
+                    .path_segments()
+                    .and_then(|mut segments| segments.next_back())
+                    .filter(|file_name| !file_name.is_empty())
+                {
+                    files.push((file_name.to_string(), location.clone()));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Last `lines` lines of `run_log.stderr` in the run log.
+pub fn get_stderr_tail(run_log: &Value, lines: usize) -> Option<String> {
+    let stderr = run_log
+        .get("run_log")
+        .and_then(|v| v.get("stderr"))
+        .and_then(|v| v.as_str())?;
+    let stderr_lines = stderr.lines().collect::<Vec<_>>();
+    let tail = stderr_lines[stderr_lines.len().saturating_sub(lines)..].join("\n");
+    match tail.trim().is_empty() {
+        true => None,
+        false => Some(tail),
+    }
+}
+
+pub fn download_file(file_url: &Url) -> Result<Vec<u8>> {
+    // outputs of the local runner
+    if file_url.scheme() == "file" {
+        let path = file_url
+            .to_file_path()
+            .map_err(|_| anyhow!("Invalid file URL: {}", file_url.as_str()))?;
+        return Ok(fs::read(path)?);
+    }
+    let client = reqwest::blocking::Client::new();
+    let response = client.get(file_url.as_str()).send()?;
+    ensure!(
+        response.status().is_success(),
+        "Failed to download the file with status: {} from {}",
+        response.status(),
+        file_url.as_str()
+    );
+    Ok(response.bytes()?.to_vec())
+}
+
 pub fn fetch_ro_crate(wes_loc: &Url, run_id: impl AsRef<str>) -> Result<Value> {
     let url = Url::parse(&format!(
         "{}/runs/{}/data/ro-crate-metadata.json",
@@ -294,28 +518,107 @@ mod tests {
     use crate::env;
     use crate::wes;
 
+    #[test]
+    fn test_get_run_outputs() -> Result<()> {
+        let wes_loc = Url::parse("http://localhost:1122/")?;
+        let run_log = serde_json::json!({
+            "outputs": [
+                {"file_name": "qc/report.html", "file_url": "http://localhost:1122/runs/1/data/outputs/qc/report.html"},
+                {"file_name": "out.txt", "file_url": "/runs/1/data/outputs/out.txt"}
+            ]
+        });
+        let outputs = get_run_outputs(&wes_loc, &run_log)?;
+        assert_eq!(
+            outputs,
+            vec![
+                (
+                    "qc/report.html".to_string(),
+                    Url::parse("http://localhost:1122/runs/1/data/outputs/qc/report.html")?
+                ),
+                (
+                    "out.txt".to_string(),
+                    Url::parse("http://localhost:1122/runs/1/data/outputs/out.txt")?
+                ),
+            ]
+        );
+        assert!(get_run_outputs(&wes_loc, &serde_json::json!({"outputs": null}))?.is_empty());
+
+        let run_log = serde_json::json!({
+            "outputs": {
+                "report": {"class": "File", "location": "http://localhost:8000/files/abc/report.html", "basename": "report.html"},
+                "logs": [{"class": "File", "location": "http://localhost:8000/files/abc/run.log"}],
+                "count": 3
+            }
+        });
+        let mut outputs = get_run_outputs(&wes_loc, &run_log)?;
+        outputs.sort();
+        assert_eq!(
+            outputs,
+            vec![
+                (
+                    "report.html".to_string(),
+                    Url::parse("http://localhost:8000/files/abc/report.html")?
+                ),
+                (
+                    "run.log".to_string(),
+                    Url::parse("http://localhost:8000/files/abc/run.log")?
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_workflow_engine_versions() -> Result<()> {
+        let engine_versions = parse_workflow_engine_versions(&serde_json::json!({
+            "workflow_engine_versions": {
+                "cwltool": "3.1.20211107152837",
+                "toil": {"workflow_engine_version": ["5.6.0", "5.7.0"]}
+            }
+        }))?;
+        assert_eq!(
+            engine_versions.get("cwltool"),
+            Some(&vec!["3.1.20211107152837".to_string()])
+        );
+        assert_eq!(
+            engine_versions.get("toil"),
+            Some(&vec!["5.6.0".to_string(), "5.7.0".to_string()])
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_get_supported_wes_versions() -> Result<()> {
-        let docker_host = Url::parse("unix:///var/run/docker.sock")?;
-        wes::instance::start_wes(&docker_host)?;
-        let wes_loc = wes::instance::default_wes_location();
+        let runtime = wes::instance::ContainerRuntime::new(
+            &Some(wes::instance::RuntimeType::Docker),
+            &Some(Url::parse("unix:///var/run/docker.sock")?),
+        );
+        let config = wes::instance::SapporoConfig::default();
+        wes::instance::start_wes(&runtime, &config)?;
+        let wes_loc = config.wes_location();
         let supported_wes_versions = get_supported_wes_versions(&wes_loc)?;
         assert!(!supported_wes_versions.is_empty());
-        wes::instance::stop_wes(&docker_host)?;
+        wes::instance::stop_wes(&runtime, &config)?;
         Ok(())
     }
 
     #[test]
     fn test_post_run() -> Result<()> {
-        let docker_host = Url::parse("unix:///var/run/docker.sock")?;
-        wes::instance::start_wes(&docker_host)?;
-        let wes_loc = wes::instance::default_wes_location();
+        let runtime = wes::instance::ContainerRuntime::new(
+            &Some(wes::instance::RuntimeType::Docker),
+            &Some(Url::parse("unix:///var/run/docker.sock")?),
+        );
+        let config = wes::instance::SapporoConfig::default();
+        wes::instance::start_wes(&runtime, &config)?;
+        let wes_loc = config.wes_location();
         let gh_token = env::github_token(&None::<String>)?;
         let meta = metadata::io::read("./tests/test-metadata-CWL-validated.yml", &gh_token)?;
-        let form = test_case_to_form(&meta, &meta.workflow.testing[0])?;
+        let test_case = &meta.workflow.testing[0];
+        let engine = meta.workflow.language.r#type.default_engine()?;
+        let form = test_case_to_form(&meta, test_case, &engine)?;
         let run_id = post_run(&wes_loc, form)?;
         assert!(!run_id.is_empty());
-        wes::instance::stop_wes(&docker_host)?;
+        wes::instance::stop_wes(&runtime, &config)?;
         Ok(())
     }
 }
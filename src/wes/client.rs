@@ -0,0 +1,250 @@
+use crate::metadata;
+use crate::wes;
+
+use anyhow::{bail, ensure, Result};
+use log::debug;
+use serde_json::Value;
+use std::collections::HashMap;
+use url::Url;
+
+/// WES implementation that the tests are run on.
+/// The WES-specific parts are the request to post a run and the RO-Crate of the run,
+/// and the others (status, run log, cancel) are common to GA4GH WES 1.x.
+#[derive(Debug, PartialEq, Clone)]
+pub enum WesClient {
+    /// sapporo-service, which fetches the workflow attachments from the URLs by itself.
+    Sapporo { wes_loc: Url },
+    /// GA4GH WES 1.0/1.1 (e.g., Cromwell, Toil), to which the workflow attachments are uploaded.
+    Standard { wes_loc: Url, version: String },
+}
+
+/// Reported in `supported_wes_versions` of sapporo-service.
+pub const SAPPORO_WES_VERSION: &str = "sapporo-wes-1.0.1";
+
+impl WesClient {
+    /// Choose the implementation from `supported_wes_versions` in the `/service-info` response.
+    pub fn new(wes_loc: &Url) -> Result<Self> {
+        let supported_wes_versions = wes::api::get_supported_wes_versions(wes_loc)?;
+        Self::from_supported_wes_versions(wes_loc, &supported_wes_versions)
+    }
+
+    fn from_supported_wes_versions(
+        wes_loc: &Url,
+        supported_wes_versions: &[String],
+    ) -> Result<Self> {
+        if supported_wes_versions
+            .iter()
+            .any(|v| v == SAPPORO_WES_VERSION)
+        {
+            return Ok(Self::Sapporo {
+                wes_loc: wes_loc.clone(),
+            });
+        }
+        match supported_wes_versions
+            .iter()
+            .filter(|v| v.starts_with("1.0") || v.starts_with("1.1"))
+            .max()
+        {
+            Some(version) => Ok(Self::Standard {
+                wes_loc: wes_loc.clone(),
+                version: version.clone(),
+            }),
+            None => bail!(
+                "Yevis only supports WES version `{}` or GA4GH WES 1.0/1.1, but the WES supports: {}",
+                SAPPORO_WES_VERSION,
+                supported_wes_versions.join(", ")
+            ),
+        }
+    }
+
+    pub fn wes_loc(&self) -> &Url {
+        match self {
+            Self::Sapporo { wes_loc } => wes_loc,
+            Self::Standard { wes_loc, .. } => wes_loc,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Sapporo { .. } => "sapporo-service".to_string(),
+            Self::Standard { version, .. } => format!("GA4GH WES {}", version),
+        }
+    }
+
+    /// The default engines of the languages (e.g., `cwltool`) are named as in sapporo-service,
+    /// and a standard WES names its engines by itself (e.g., `toil`).
+    pub fn knows_default_engines(&self) -> bool {
+        matches!(self, Self::Sapporo { .. })
+    }
+
+    /// Check that the engines are listed in `workflow_engine_versions` of the service-info.
+    /// The names are compared case-insensitively, e.g., `Cromwell` and `cromwell`.
+    /// A standard WES may not report `workflow_engine_versions`, and then the engines are not checked.
+    pub fn check_engines<'a>(
+        &self,
+        engines: impl IntoIterator<Item = &'a metadata::types::Engine>,
+    ) -> Result<()> {
+        let engine_versions = wes::api::get_workflow_engine_versions(self.wes_loc())?;
+        if engine_versions.is_empty() && matches!(self, Self::Standard { .. }) {
+            debug!(
+                "{} does not report `workflow_engine_versions`, so the engines are not checked",
+                self.name()
+            );
+            return Ok(());
+        }
+        let unsupported = unsupported_engines(&engine_versions, engines);
+        ensure!(
+            unsupported.is_empty(),
+            "{} does not support the engines: {} (supported: {})",
+            self.name(),
+            unsupported.join(", "),
+            engine_versions
+                .iter()
+                .map(|(name, versions)| format!("{} {}", name, versions.join("/")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(())
+    }
+
+    /// Return: run_id
+    pub fn post_run(
+        &self,
+        meta: &metadata::types::Metadata,
+        test_case: &metadata::types::Testing,
+        engine: &metadata::types::Engine,
+    ) -> Result<String> {
+        let form = match self {
+            Self::Sapporo { .. } => wes::api::test_case_to_form(meta, test_case, engine)?,
+            Self::Standard { version, .. } => {
+                let form = wes::api::test_case_to_standard_form(meta, test_case)?;
+                // WES 1.0 has no field to choose the engine,
+                // and the default engine of the WES is used if no engine is set in the test case
+                match version.starts_with("1.0") || test_case.engines.is_empty() {
+                    true => form,
+                    false => {
+                        let form = form.text("workflow_engine", engine.name.clone());
+                        match &engine.version {
+                            Some(version) => form.text("workflow_engine_version", version.clone()),
+                            None => form,
+                        }
+                    }
+                }
+            }
+        };
+        wes::api::post_run(self.wes_loc(), form)
+    }
+
+    pub fn get_run_status(&self, run_id: impl AsRef<str>) -> Result<wes::api::RunStatus> {
+        wes::api::get_run_status(self.wes_loc(), run_id)
+    }
+
+    pub fn get_run_log(&self, run_id: impl AsRef<str>) -> Result<Value> {
+        wes::api::get_run_log(self.wes_loc(), run_id)
+    }
+
+    pub fn cancel_run(&self, run_id: impl AsRef<str>) -> Result<()> {
+        wes::api::cancel_run(self.wes_loc(), run_id)
+    }
+
+    pub fn get_run_outputs(&self, run_log: &Value) -> Result<Vec<(String, Url)>> {
+        wes::api::get_run_outputs(self.wes_loc(), run_log)
+    }
+
+    /// Only sapporo-service generates the RO-Crate of a run.
+    pub fn provides_ro_crate(&self) -> bool {
+        matches!(self, Self::Sapporo { .. })
+    }
+
+    pub fn fetch_ro_crate(&self, run_id: impl AsRef<str>) -> Result<Value> {
+        match self {
+            Self::Sapporo { wes_loc } => wes::api::fetch_ro_crate(wes_loc, run_id),
+            Self::Standard { .. } => {
+                bail!("{} does not provide the RO-Crate of a run", self.name())
+            }
+        }
+    }
+}
+
+/// `engine_versions`: `workflow_engine_versions` of the service-info
+fn unsupported_engines<'a>(
+    engine_versions: &HashMap<String, Vec<String>>,
+    engines: impl IntoIterator<Item = &'a metadata::types::Engine>,
+) -> Vec<String> {
+    engines
+        .into_iter()
+        .filter(|engine| {
+            match engine_versions
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&engine.name))
+            {
+                Some((_, versions)) => engine
+                    .version
+                    .as_ref()
+                    .is_some_and(|version| !versions.contains(version)),
+                None => true,
+            }
+        })
+        .map(|engine| engine.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_supported_wes_versions() -> Result<()> {
+        let wes_loc = Url::parse("http://localhost:1122")?;
+        assert_eq!(
+            WesClient::from_supported_wes_versions(&wes_loc, &["sapporo-wes-1.0.1".to_string()])?,
+            WesClient::Sapporo {
+                wes_loc: wes_loc.clone()
+            }
+        );
+        assert_eq!(
+            WesClient::from_supported_wes_versions(
+                &wes_loc,
+                &["1.0.0".to_string(), "1.1.0".to_string()]
+            )?,
+            WesClient::Standard {
+                wes_loc: wes_loc.clone(),
+                version: "1.1.0".to_string()
+            }
+        );
+        assert!(WesClient::from_supported_wes_versions(&wes_loc, &["0.3.0".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_engines() -> Result<()> {
+        let engine_versions = HashMap::from([(
+            "cromwell".to_string(),
+            vec!["80".to_string(), "84".to_string()],
+        )]);
+        let engines = vec![
+            metadata::types::Engine {
+                name: "Cromwell".to_string(),
+                version: Some("84".to_string()),
+            },
+            metadata::types::Engine {
+                name: "cromwell".to_string(),
+                version: None,
+            },
+            metadata::types::Engine {
+                name: "cromwell".to_string(),
+                version: Some("70".to_string()),
+            },
+            metadata::types::Engine {
+                name: "toil".to_string(),
+                version: None,
+            },
+        ];
+        assert_eq!(
+            unsupported_engines(&engine_versions, &engines),
+            vec!["cromwell 70".to_string(), "toil".to_string()]
+        );
+        Ok(())
+    }
+}
@@ -3,43 +3,261 @@ use crate::wes;
 
 use anyhow::{anyhow, bail, ensure, Context, Result};
 use colored::Colorize;
-use log::{error, info};
+use log::{error, info, warn};
+use serde_json::Value;
 use std::env as std_env;
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::{Command, Output, Stdio};
+use std::str::FromStr;
 use std::thread;
 use std::time;
 use url::Url;
 
-pub const SAPPORO_SERVICE_IMAGE: &str = "ghcr.io/sapporo-wes/sapporo-service:latest";
+pub const SAPPORO_SERVICE_IMAGE: &str = "ghcr.io/sapporo-wes/sapporo-service:1.4.0";
 pub const SAPPORO_SERVICE_NAME: &str = "yevis-sapporo-service";
+pub const SAPPORO_SERVICE_PORT: u16 = 1122;
+/// Where the socket is mounted in `sapporo-service`
+const DOCKER_SOCKET: &str = "/var/run/docker.sock";
 
-pub fn inside_docker_container() -> bool {
-    Path::new("/.dockerenv").exists()
+/// sapporo-service started by yevis, configured by the environment variables
+/// `SAPPORO_IMAGE`, `SAPPORO_PORT`, `SAPPORO_CONTAINER_NAME`, `SAPPORO_EXTRA_ARGS` and `SAPPORO_OFFLINE`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SapporoConfig {
+    /// Image with a tag or digest
+    pub image: String,
+    /// Port published on the host
+    pub port: u16,
+    pub name: String,
+    /// Extra arguments of the `sapporo` command
+    pub extra_args: Vec<String>,
+    /// Use the image in the local cache without pulling it
+    pub offline: bool,
 }
 
-pub fn default_wes_location() -> Url {
-    if inside_docker_container() {
-        Url::parse(&format!("http://{}:1122", SAPPORO_SERVICE_NAME)).unwrap()
-    } else {
-        Url::parse("http://localhost:1122").unwrap()
+impl Default for SapporoConfig {
+    fn default() -> Self {
+        Self {
+            image: SAPPORO_SERVICE_IMAGE.to_string(),
+            port: SAPPORO_SERVICE_PORT,
+            name: SAPPORO_SERVICE_NAME.to_string(),
+            extra_args: vec![],
+            offline: false,
+        }
+    }
+}
+
+impl SapporoConfig {
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        Ok(Self {
+            image: env::sapporo_image().unwrap_or(default.image),
+            port: env::sapporo_port()?.unwrap_or(default.port),
+            name: env::sapporo_container_name().unwrap_or(default.name),
+            extra_args: env::sapporo_extra_args(),
+            offline: env::sapporo_offline()?,
+        })
+    }
+
+    /// Inside a container, sapporo-service is reached through the `yevis-network` by the container name.
+    pub fn wes_location(&self) -> Url {
+        if inside_container() {
+            Url::parse(&format!("http://{}:{}", self.name, SAPPORO_SERVICE_PORT)).unwrap()
+        } else {
+            Url::parse(&format!("http://localhost:{}", self.port)).unwrap()
+        }
+    }
+}
+
+/// Container runtime CLI used to start `sapporo-service`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeType {
+    Docker,
+    Podman,
+    Nerdctl,
+}
+
+impl RuntimeType {
+    pub const VARIANTS: &'static [&'static str] = &["docker", "podman", "nerdctl"];
+
+    fn program(&self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+            Self::Nerdctl => "nerdctl",
+        }
+    }
+
+    /// The first of docker, podman and nerdctl present in PATH, or docker if none of them.
+    /// A podman socket as the host means podman, as docker may be an alias of podman.
+    /// Without the host, docker is taken as podman if it is an alias of podman,
+    /// or if only the rootless podman socket exists.
+    fn detect(host: &Option<Url>) -> Self {
+        if let Some(host) = host {
+            if host.path().contains("podman") {
+                return Self::Podman;
+            }
+        }
+        let r#type = [Self::Docker, Self::Podman, Self::Nerdctl]
+            .into_iter()
+            .find_map(|r#type| {
+                Command::new(r#type.program())
+                    .arg("--version")
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .map(|output| (r#type, output))
+            });
+        match r#type {
+            Some((Self::Docker, output)) if host.is_none() => {
+                let alias_of_podman = String::from_utf8_lossy(&output.stdout)
+                    .to_lowercase()
+                    .contains("podman");
+                let only_podman_socket =
+                    !Path::new(DOCKER_SOCKET).exists() && rootless_podman_socket().is_some();
+                match alias_of_podman || only_podman_socket {
+                    true => Self::Podman,
+                    false => Self::Docker,
+                }
+            }
+            Some((r#type, _)) => r#type,
+            None => Self::Docker,
+        }
+    }
+
+    /// nerdctl has no Docker-compatible socket, and the Docker socket is expected for it.
+    fn default_host(&self) -> Url {
+        let path = match self {
+            Self::Docker | Self::Nerdctl => DOCKER_SOCKET.to_string(),
+            Self::Podman => {
+                rootless_podman_socket().unwrap_or_else(|| "/run/podman/podman.sock".to_string())
+            }
+        };
+        Url::parse(&format!("unix://{}", path)).unwrap()
+    }
+}
+
+/// Rootless podman listens on `$XDG_RUNTIME_DIR/podman/podman.sock`.
+fn rootless_podman_socket() -> Option<String> {
+    match std_env::var("XDG_RUNTIME_DIR") {
+        Ok(runtime_dir) if Path::new(&runtime_dir).join("podman/podman.sock").exists() => Some(
+            format!("{}/podman/podman.sock", runtime_dir.trim_end_matches('/')),
+        ),
+        _ => None,
+    }
+}
+
+impl FromStr for RuntimeType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "docker" => Ok(RuntimeType::Docker),
+            "podman" => Ok(RuntimeType::Podman),
+            "nerdctl" => Ok(RuntimeType::Nerdctl),
+            _ => bail!("Unsupported container runtime: {}", s),
+        }
+    }
+}
+
+/// Container runtime and its socket.
+/// The socket is also mounted into `sapporo-service` to run the workflow engines,
+/// so it must serve the Docker API.
+/// nerdctl connects to containerd by itself (`CONTAINERD_ADDRESS`),
+/// and the socket is a separate Docker-compatible one (e.g., of rootless docker or podman).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ContainerRuntime {
+    pub r#type: RuntimeType,
+    pub host: Url,
+}
+
+impl ContainerRuntime {
+    /// The runtime is detected if not specified, and the host is the default socket of the runtime if not specified.
+    pub fn new(r#type: &Option<RuntimeType>, host: &Option<Url>) -> Self {
+        let r#type = match r#type {
+            Some(r#type) => r#type.clone(),
+            None => RuntimeType::detect(host),
+        };
+        let host = match host {
+            Some(host) => host.clone(),
+            None => r#type.default_host(),
+        };
+        Self { r#type, host }
+    }
+
+    fn name(&self) -> &'static str {
+        self.r#type.program()
+    }
+
+    /// Global options to connect to the socket.
+    fn host_args(&self) -> Vec<String> {
+        match self.r#type {
+            RuntimeType::Docker => vec!["-H".to_string(), self.host.to_string()],
+            RuntimeType::Podman => vec!["--url".to_string(), self.host.to_string()],
+            // the host is not containerd but the socket for sapporo-service
+            RuntimeType::Nerdctl => vec![],
+        }
+    }
+
+    /// Options of `run` specific to the runtime and to where yevis is running.
+    fn run_args(&self, config: &SapporoConfig) -> Vec<String> {
+        let mut args = match inside_container() {
+            true => vec!["--network".to_string(), "yevis-network".to_string()],
+            false => vec![
+                "-p".to_string(),
+                format!("{}:{}", config.port, SAPPORO_SERVICE_PORT),
+            ],
+        };
+        if self.r#type == RuntimeType::Podman {
+            // allow sapporo-service to access the socket with SELinux enabled
+            args.extend(["--security-opt".to_string(), "label=disable".to_string()]);
+        }
+        args
+    }
+
+    fn output(&self, args: &[&str]) -> Result<Output> {
+        let process = Command::new(self.r#type.program())
+            .args(self.host_args())
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Please make sure that the {} command is present in your PATH",
+                    self.name()
+                )
+            })?;
+        Ok(process.wait_with_output()?)
     }
 }
 
-pub fn start_wes(docker_host: &Url) -> Result<()> {
-    let status = check_wes_running(docker_host)?;
+/// Docker creates `/.dockerenv`, and podman creates `/run/.containerenv`.
+pub fn inside_container() -> bool {
+    Path::new("/.dockerenv").exists() || Path::new("/run/.containerenv").exists()
+}
+
+pub fn start_wes(runtime: &ContainerRuntime, config: &SapporoConfig) -> Result<()> {
+    let status = check_wes_running(runtime, config)?;
     if status {
         info!("sapporo-service is already running. So skip starting it.");
         return Ok(());
     }
 
     info!(
-        "Starting sapporo-service using docker_host: {}",
-        docker_host.as_str()
+        "Starting sapporo-service using {} with host: {}",
+        runtime.name(),
+        runtime.host.as_str()
     );
-    pull_latest_image(docker_host)?;
+    if runtime.r#type == RuntimeType::Nerdctl {
+        ensure!(
+            Path::new(runtime.host.path()).exists(),
+            "sapporo-service runs the workflow engines through a Docker-compatible socket, which nerdctl does not provide, and {} does not exist. Please specify a Docker-compatible socket (e.g., of rootless docker or podman) with --docker-host",
+            runtime.host.path()
+        );
+    }
+    prepare_image(runtime, config)?;
     let sapporo_run_dir = &env::sapporo_run_dir()?;
-    let arg_socket_val = &format!("{}:/var/run/docker.sock", docker_host.path());
+    let arg_socket_val = &format!("{}:{}", runtime.host.path(), DOCKER_SOCKET);
     let arg_tmp_val = &format!(
         "{}:/tmp",
         std_env::temp_dir()
@@ -47,52 +265,44 @@ pub fn start_wes(docker_host: &Url) -> Result<()> {
             .ok_or_else(|| anyhow!("Invalid path"))?
     );
     let arg_run_dir_val = &format!("{}:{}", sapporo_run_dir, sapporo_run_dir);
-    let (arg_network, arg_network_val) = if inside_docker_container() {
-        ("--network", "yevis-network")
-    } else {
-        ("-p", "1122:1122")
-    };
-    let process = Command::new("docker")
-        .args(&[
-            "-H",
-            docker_host.as_str(),
-            "run",
-            "-d",
-            "--rm",
-            "-v",
-            arg_socket_val,
-            "-v",
-            arg_tmp_val,
-            "-v",
-            arg_run_dir_val,
-            arg_network,
-            arg_network_val,
-            "--name",
-            SAPPORO_SERVICE_NAME,
-            SAPPORO_SERVICE_IMAGE,
-            "sapporo",
-            "--run-dir",
-            sapporo_run_dir,
-        ])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .context("Please make sure that the docker command is present in your PATH")?;
-    let output = process.wait_with_output()?;
+    let run_args = runtime.run_args(config);
+    let mut args = vec![
+        "run",
+        "-d",
+        "--rm",
+        "-v",
+        arg_socket_val,
+        "-v",
+        arg_tmp_val,
+        "-v",
+        arg_run_dir_val,
+    ];
+    args.extend(run_args.iter().map(|arg| arg.as_str()));
+    args.extend([
+        "--name",
+        &config.name,
+        &config.image,
+        "sapporo",
+        "--run-dir",
+        sapporo_run_dir,
+    ]);
+    args.extend(config.extra_args.iter().map(|arg| arg.as_str()));
+    let output = runtime.output(&args)?;
     ensure!(
         output.status.success(),
         "Failed to start sapporo-service:\n{}",
         String::from_utf8_lossy(&output.stderr)
     );
     info!(
-        "Stdout from docker:\n{}",
+        "Stdout from {}:\n{}",
+        runtime.name(),
         String::from_utf8_lossy(&output.stdout).trim()
     );
 
     // health check
     let mut retry = 0;
     while retry < 5 {
-        match wes::api::sapporo_health_check(&default_wes_location()) {
+        match wes::api::sapporo_health_check(&config.wes_location()) {
             Ok(_) => break,
             Err(_) => thread::sleep(time::Duration::from_secs(2)),
         }
@@ -107,36 +317,31 @@ pub fn start_wes(docker_host: &Url) -> Result<()> {
     Ok(())
 }
 
-pub fn stop_wes(docker_host: &Url) -> Result<()> {
-    let status = check_wes_running(docker_host)?;
+pub fn stop_wes(runtime: &ContainerRuntime, config: &SapporoConfig) -> Result<()> {
+    let status = check_wes_running(runtime, config)?;
     if !status {
         info!("sapporo-service is not running. So skip stopping it.");
         return Ok(());
     }
 
     info!("Stopping sapporo-service");
-    let process = Command::new("docker")
-        .args(&["-H", docker_host.as_str(), "kill", SAPPORO_SERVICE_NAME])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .context("Please make sure that the docker command is present in your PATH")?;
-    let output = process.wait_with_output()?;
+    let output = runtime.output(&["kill", &config.name])?;
     ensure!(
         output.status.success(),
         "Failed to stop the sapporo-service:\n{}",
         String::from_utf8_lossy(&output.stderr)
     );
     info!(
-        "Stdout from docker:\n{}",
+        "Stdout from {}:\n{}",
+        runtime.name(),
         String::from_utf8_lossy(&output.stdout).trim()
     );
     thread::sleep(time::Duration::from_secs(3));
     Ok(())
 }
 
-pub fn stop_wes_no_result(docker_host: &Url) {
-    match stop_wes(docker_host) {
+pub fn stop_wes_no_result(runtime: &ContainerRuntime, config: &SapporoConfig) {
+    match stop_wes(runtime, config) {
         Ok(_) => {}
         Err(e) => {
             error!("{} to stop WES instance with error: {}", "Failed".red(), e);
@@ -144,23 +349,11 @@ pub fn stop_wes_no_result(docker_host: &Url) {
     };
 }
 
-pub fn check_wes_running(docker_host: &Url) -> Result<bool> {
-    let process = Command::new("docker")
-        .args(&[
-            "-H",
-            docker_host.as_str(),
-            "ps",
-            "-f",
-            &format!("name={}", SAPPORO_SERVICE_NAME),
-        ])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .context("Please make sure that the docker command is present in your PATH")?;
-    let output = process.wait_with_output()?;
+pub fn check_wes_running(runtime: &ContainerRuntime, config: &SapporoConfig) -> Result<bool> {
+    let output = runtime.output(&["ps", "-f", &format!("name={}", config.name)])?;
     if output.status.success() {
         let stdout = String::from_utf8_lossy(&output.stdout);
-        if stdout.contains(SAPPORO_SERVICE_NAME) {
+        if stdout.contains(&config.name) {
             Ok(true)
         } else {
             Ok(false)
@@ -173,59 +366,136 @@ pub fn check_wes_running(docker_host: &Url) -> Result<bool> {
     }
 }
 
-pub fn pull_latest_image(docker_host: &Url) -> Result<()> {
-    let process = Command::new("docker")
-        .args(&["-H", docker_host.as_str(), "pull", SAPPORO_SERVICE_IMAGE])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .context("Please make sure that the docker command is present in your PATH")?;
-    let output = process.wait_with_output()?;
+/// Pull the image unless offline.
+/// If the pull fails (e.g., the registry is down), the image in the local cache is used if any.
+pub fn prepare_image(runtime: &ContainerRuntime, config: &SapporoConfig) -> Result<()> {
+    if config.offline {
+        ensure!(
+            image_cached(runtime, &config.image)?,
+            "The image {} is not in the local cache of {}. Please pull it before running in offline mode.",
+            config.image,
+            runtime.name()
+        );
+        info!("Use the cached image {} in offline mode", config.image);
+        return Ok(());
+    }
+    match pull_image(runtime, &config.image) {
+        Ok(()) => Ok(()),
+        Err(e) if image_cached(runtime, &config.image)? => {
+            warn!(
+                "{}: {}. Use the cached image {} instead.",
+                "Warning".yellow(),
+                e,
+                config.image
+            );
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub fn pull_image(runtime: &ContainerRuntime, image: impl AsRef<str>) -> Result<()> {
+    let output = runtime.output(&["pull", image.as_ref()])?;
     ensure!(
         output.status.success(),
-        "Failed to pull the latest sapporo-service image:\n{}",
+        "Failed to pull the sapporo-service image {}:\n{}",
+        image.as_ref(),
         String::from_utf8_lossy(&output.stderr)
     );
     info!(
-        "Stdout from docker:\n{}",
+        "Stdout from {}:\n{}",
+        runtime.name(),
         String::from_utf8_lossy(&output.stdout).trim()
     );
     Ok(())
 }
 
+fn image_cached(runtime: &ContainerRuntime, image: impl AsRef<str>) -> Result<bool> {
+    let output = runtime.output(&["image", "inspect", image.as_ref()])?;
+    Ok(output.status.success())
+}
+
+/// The repository digest of the image of the running sapporo-service container
+/// (e.g., `ghcr.io/sapporo-wes/sapporo-service@sha256:...`),
+/// or the image ID if the image has no repository digest (e.g., built locally).
+/// The container is inspected rather than `config.image`, as a container already running may use another image.
+pub fn image_digest(runtime: &ContainerRuntime, config: &SapporoConfig) -> Result<String> {
+    let output = runtime.output(&["inspect", "--format", "{{.Image}}", &config.name])?;
+    ensure!(
+        output.status.success(),
+        "Failed to inspect the container {}:\n{}",
+        config.name,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let image_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let output = runtime.output(&["image", "inspect", &image_id])?;
+    ensure!(
+        output.status.success(),
+        "Failed to inspect the image {}:\n{}",
+        image_id,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    parse_image_digest(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_image_digest(inspect_output: impl AsRef<str>) -> Result<String> {
+    let err_msg = "Failed to parse the result of the image inspection";
+    let inspect: Value = serde_json::from_str(inspect_output.as_ref())?;
+    let image = inspect.get(0).ok_or_else(|| anyhow!(err_msg))?;
+    match image
+        .get("RepoDigests")
+        .and_then(|v| v.get(0))
+        .and_then(|v| v.as_str())
+    {
+        Some(digest) => Ok(digest.to_string()),
+        None => Ok(image
+            .get("Id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!(err_msg))?
+            .to_string()),
+    }
+}
+
 #[cfg(test)]
 #[cfg(not(tarpaulin_include))]
 mod tests {
     use super::*;
 
+    fn docker_runtime(host: &str) -> Result<ContainerRuntime> {
+        Ok(ContainerRuntime::new(
+            &Some(RuntimeType::Docker),
+            &Some(Url::parse(host)?),
+        ))
+    }
+
     #[test]
     fn test_start_wes() -> Result<()> {
-        let docker_host = Url::parse("unix:///var/run/docker.sock")?;
-        assert!(start_wes(&docker_host).is_ok());
-        stop_wes(&docker_host)?;
+        let runtime = docker_runtime("unix:///var/run/docker.sock")?;
+        assert!(start_wes(&runtime, &SapporoConfig::default()).is_ok());
+        stop_wes(&runtime, &SapporoConfig::default())?;
         Ok(())
     }
 
     #[test]
     fn test_stop_wes() -> Result<()> {
-        let docker_host = Url::parse("unix:///var/run/docker.sock")?;
-        start_wes(&docker_host)?;
-        assert!(stop_wes(&docker_host).is_ok());
+        let runtime = docker_runtime("unix:///var/run/docker.sock")?;
+        start_wes(&runtime, &SapporoConfig::default())?;
+        assert!(stop_wes(&runtime, &SapporoConfig::default()).is_ok());
         Ok(())
     }
 
     #[test]
     fn test_check_wes_running() -> Result<()> {
-        let docker_host = Url::parse("unix:///var/run/docker.sock")?;
-        start_wes(&docker_host)?;
-        assert!(check_wes_running(&docker_host)?);
+        let runtime = docker_runtime("unix:///var/run/docker.sock")?;
+        start_wes(&runtime, &SapporoConfig::default())?;
+        assert!(check_wes_running(&runtime, &SapporoConfig::default())?);
         Ok(())
     }
 
     #[test]
     fn test_check_wes_running_with_invalid_docker_host() -> Result<()> {
-        let docker_host = Url::parse("unix:///var/run/invalid")?;
-        let result = check_wes_running(&docker_host);
+        let runtime = docker_runtime("unix:///var/run/invalid")?;
+        let result = check_wes_running(&runtime, &SapporoConfig::default());
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -233,4 +503,42 @@ mod tests {
             .contains("Cannot connect to the Docker daemon"));
         Ok(())
     }
+
+    #[test]
+    fn test_container_runtime_new() -> Result<()> {
+        let runtime = ContainerRuntime::new(&Some(RuntimeType::Podman), &None);
+        assert!(runtime.host.path().ends_with("podman/podman.sock"));
+        assert_eq!(runtime.host_args()[0], "--url");
+        assert!(runtime
+            .run_args(&SapporoConfig::default())
+            .windows(2)
+            .any(|args| args == ["--security-opt", "label=disable"]));
+
+        let runtime = ContainerRuntime::new(
+            &None,
+            &Some(Url::parse("unix:///run/user/1000/podman/podman.sock")?),
+        );
+        assert_eq!(runtime.r#type, RuntimeType::Podman);
+
+        let runtime = ContainerRuntime::new(&Some(RuntimeType::Nerdctl), &None);
+        assert!(runtime.host_args().is_empty());
+        assert_eq!(runtime.host.path(), "/var/run/docker.sock");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_image_digest() -> Result<()> {
+        assert_eq!(
+            parse_image_digest(
+                r#"[{"Id": "sha256:abc", "RepoDigests": ["ghcr.io/sapporo-wes/sapporo-service@sha256:def"]}]"#
+            )?,
+            "ghcr.io/sapporo-wes/sapporo-service@sha256:def"
+        );
+        assert_eq!(
+            parse_image_digest(r#"[{"Id": "sha256:abc", "RepoDigests": []}]"#)?,
+            "sha256:abc"
+        );
+        assert!(parse_image_digest("[]").is_err());
+        Ok(())
+    }
 }
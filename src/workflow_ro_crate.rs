@@ -0,0 +1,365 @@
+use crate::metadata;
+use crate::ro_crate;
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
+
+/// Packaged Workflow RO-Crate uploaded to Zenodo with the workflow files.
+pub const PACKAGE_FILE: &str = "workflow-ro-crate.zip";
+
+/// https://about.workflowhub.eu/Workflow-RO-Crate/
+pub const WORKFLOW_RO_CRATE_PROFILE: &str = "https://w3id.org/workflowhub/workflow-ro-crate/1.0";
+
+/// Context of the Workflow Testing RO-Crate terms (`TestSuite`, `TestDefinition`, ...).
+/// https://crs4.github.io/life_monitor/workflow_testing_ro_crate
+const TEST_TERMS_CONTEXT: &str = "https://w3id.org/ro/terms/test";
+
+const YEVIS_ID: &str = "https://github.com/sapporo-wes/yevis-cli";
+
+/// How the data entities are identified in the Workflow RO-Crate.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum DataEntityId {
+    /// By the file URLs, as the published RO-Crate is not placed with the files
+    Url,
+    /// By the paths in the packaged RO-Crate, relative to it
+    Path,
+}
+
+/// Build the published Workflow RO-Crate (`ro-crate-metadata.json`) of the workflow version:
+/// the primary workflow file as the main entity with its language, the secondary files and the README,
+/// the test files as `TestDefinition` of a `TestSuite` for each test case,
+/// and the authors and the license.
+/// The data entities are identified by their URLs.
+pub fn generate(meta: &metadata::types::Metadata) -> Result<Value> {
+    build(meta, DataEntityId::Url)
+}
+
+/// The data entities are identified by their URLs, or by their target paths with test files under `tests/{test_id}/`.
+fn build(meta: &metadata::types::Metadata, entity_id: DataEntityId) -> Result<Value> {
+    let primary_wf = meta.workflow.primary_wf()?;
+    let main_id = match entity_id {
+        DataEntityId::Url => primary_wf.url.to_string(),
+        DataEntityId::Path => target_id(&primary_wf.target)?,
+    };
+
+    let authors = meta
+        .authors
+        .iter()
+        .map(ro_crate::person)
+        .collect::<Vec<_>>();
+    let author_refs = authors
+        .iter()
+        .map(|person| json!({ "@id": person["@id"] }))
+        .collect::<Vec<_>>();
+    let license = ro_crate::license(&meta.license);
+    let license_ref = json!({ "@id": license["@id"] });
+    let language = language(&meta.workflow.language)?;
+
+    let mut graph = vec![json!({
+        "@id": ro_crate::METADATA_DESCRIPTOR_ID,
+        "@type": "CreativeWork",
+        "about": { "@id": "./" },
+        "conformsTo": [
+            { "@id": "https://w3id.org/ro/crate/1.1" },
+            { "@id": WORKFLOW_RO_CRATE_PROFILE },
+        ],
+    })];
+    let mut has_part = vec![];
+    let mut data_entities = vec![];
+    add_data_entity(
+        &mut data_entities,
+        &mut has_part,
+        json!({
+            "@id": match entity_id {
+                DataEntityId::Url => meta.workflow.readme.to_string(),
+                DataEntityId::Path => "README.md".to_string(),
+            },
+            "@type": "File",
+            "name": "README.md",
+            "url": meta.workflow.readme,
+        }),
+    );
+
+    for file in &meta.workflow.files {
+        let file_id = match entity_id {
+            DataEntityId::Url => file.url.to_string(),
+            DataEntityId::Path => target_id(&file.target)?,
+        };
+        if file.is_primary() {
+            add_data_entity(
+                &mut data_entities,
+                &mut has_part,
+                json!({
+                    "@id": file_id,
+                    "@type": ["File", "SoftwareSourceCode", "ComputationalWorkflow"],
+                    "name": meta.workflow.name,
+                    "programmingLanguage": { "@id": language["@id"] },
+                    "url": file.url,
+                    "version": meta.version,
+                    "author": author_refs,
+                    "license": license_ref,
+                }),
+            );
+        } else {
+            add_data_entity(
+                &mut data_entities,
+                &mut has_part,
+                json!({
+                    "@id": file_id,
+                    "@type": "File",
+                    "name": target_id(&file.target)?,
+                    "url": file.url,
+                }),
+            );
+        }
+    }
+
+    let mut suite_refs = vec![];
+    let mut test_entities = vec![];
+    for test_case in &meta.workflow.testing {
+        let mut definition_refs = vec![];
+        for file in &test_case.files {
+            let file_id = match entity_id {
+                DataEntityId::Url => file.url.to_string(),
+                DataEntityId::Path => test_file_id(&test_case.id, &file.target)?,
+            };
+            definition_refs.push(json!({ "@id": file_id }));
+            add_data_entity(
+                &mut data_entities,
+                &mut has_part,
+                json!({
+                    "@id": file_id,
+                    "@type": ["File", "TestDefinition"],
+                    "name": target_id(&file.target)?,
+                    "description": format!(
+                        "{} of the test case {}",
+                        serde_json::to_value(&file.r#type)?
+                            .as_str()
+                            .unwrap_or_default(),
+                        test_case.id
+                    ),
+                    "url": file.url,
+                    "conformsTo": { "@id": YEVIS_ID },
+                    "engineVersion": env!("CARGO_PKG_VERSION"),
+                }),
+            );
+        }
+        let suite_id = format!("#test_suite_{}", test_case.id);
+        suite_refs.push(json!({ "@id": suite_id }));
+        test_entities.push(json!({
+            "@id": suite_id,
+            "@type": "TestSuite",
+            "name": test_case.id,
+            "mainEntity": { "@id": main_id },
+            "definition": definition_refs,
+        }));
+    }
+
+    graph.push(json!({
+        "@id": "./",
+        "@type": "Dataset",
+        "name": meta.workflow.name,
+        "identifier": meta.id.to_string(),
+        "version": meta.version,
+        "mainEntity": { "@id": main_id },
+        "hasPart": has_part,
+        "mentions": suite_refs,
+        "author": author_refs,
+        "license": license_ref,
+    }));
+    graph.extend(data_entities);
+    graph.extend(test_entities);
+    if !meta.workflow.testing.is_empty() {
+        graph.push(json!({
+            "@id": YEVIS_ID,
+            "@type": "SoftwareApplication",
+            "name": env!("CARGO_PKG_NAME"),
+            "url": YEVIS_ID,
+            "version": env!("CARGO_PKG_VERSION"),
+        }));
+    }
+    graph.push(language);
+    graph.extend(authors);
+    graph.push(license);
+
+    Ok(json!({
+        "@context": [
+            "https://w3id.org/ro/crate/1.1/context",
+            TEST_TERMS_CONTEXT,
+        ],
+        "@graph": graph,
+    }))
+}
+
+/// Path of the test file in the RO-Crate, as test cases may share the same target.
+pub fn test_file_path(test_id: impl AsRef<str>, target: impl AsRef<Path>) -> PathBuf {
+    Path::new("tests").join(test_id.as_ref()).join(target)
+}
+
+/// Zip the Workflow RO-Crate with the files, where the data entities are identified by the paths in the zip.
+/// `files`: (path in the RO-Crate, content), i.e., the README, the workflow files, and the test files placed by `test_file_path`
+/// The entries are stored without compression and with the modification time fixed to 1980-01-01,
+/// so the same files always give the same archive and the same checksum on Zenodo.
+pub fn package(meta: &metadata::types::Metadata, files: &[(PathBuf, Vec<u8>)]) -> Result<Vec<u8>> {
+    let ro_crate = serde_json::to_string_pretty(&build(meta, DataEntityId::Path)?)?;
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .last_modified_time(DateTime::default());
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    zip.start_file(ro_crate::METADATA_DESCRIPTOR_ID, options)?;
+    zip.write_all(ro_crate.as_bytes())?;
+    for (path, content) in files {
+        zip.start_file(path.to_string_lossy(), options)?;
+        zip.write_all(content)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Add the data entity to the `@graph` and `hasPart` of the root data entity, unless it is already added
+/// (e.g., a test file shared by test cases).
+fn add_data_entity(data_entities: &mut Vec<Value>, has_part: &mut Vec<Value>, entity: Value) {
+    if data_entities
+        .iter()
+        .any(|existing| existing["@id"] == entity["@id"])
+    {
+        return;
+    }
+    has_part.push(json!({ "@id": entity["@id"] }));
+    data_entities.push(entity);
+}
+
+fn target_id(target: &Option<PathBuf>) -> Result<String> {
+    Ok(target
+        .as_ref()
+        .ok_or_else(|| anyhow!("No target in the metadata"))?
+        .to_string_lossy()
+        .to_string())
+}
+
+fn test_file_id(test_id: impl AsRef<str>, target: &Option<PathBuf>) -> Result<String> {
+    Ok(test_file_path(test_id, target_id(target)?)
+        .to_string_lossy()
+        .to_string())
+}
+
+/// `ComputerLanguage` entity, identified as in the Workflow RO-Crate profile.
+fn language(language: &metadata::types::Language) -> Result<Value> {
+    let (key, name, alternate_name, url) = match language.r#type {
+        metadata::types::LanguageType::Cwl => (
+            "cwl",
+            "Common Workflow Language",
+            "CWL",
+            "https://www.commonwl.org/",
+        ),
+        metadata::types::LanguageType::Wdl => (
+            "wdl",
+            "Workflow Description Language",
+            "WDL",
+            "https://openwdl.org/",
+        ),
+        metadata::types::LanguageType::Nfl => {
+            ("nextflow", "Nextflow", "NFL", "https://www.nextflow.io/")
+        }
+        metadata::types::LanguageType::Smk => (
+            "snakemake",
+            "Snakemake",
+            "SMK",
+            "https://snakemake.readthedocs.io/",
+        ),
+        metadata::types::LanguageType::Unknown => bail!("Unsupported workflow language type"),
+    };
+    Ok(json!({
+        "@id": format!("https://w3id.org/workflowhub/workflow-ro-crate#{}", key),
+        "@type": "ComputerLanguage",
+        "name": name,
+        "alternateName": alternate_name,
+        "url": { "@id": url },
+        "version": language.version,
+    }))
+}
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    fn find<'a>(ro_crate: &'a Value, id: &str) -> Option<&'a Value> {
+        ro_crate["@graph"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entity| entity["@id"] == id)
+    }
+
+    #[test]
+    fn test_generate() -> Result<()> {
+        let meta = metadata::io::test_metadata()?;
+        let ro_crate = build(&meta, DataEntityId::Path)?;
+
+        let root = find(&ro_crate, "./").unwrap();
+        assert_eq!(root["mainEntity"]["@id"], "trimming_and_qc.cwl");
+        assert_eq!(root["license"]["@id"], "https://spdx.org/licenses/CC0-1.0");
+        assert_eq!(
+            root["author"][0]["@id"],
+            "https://orcid.org/0000-0003-2765-0049"
+        );
+        assert_eq!(
+            root["hasPart"].as_array().unwrap().len(),
+            1 + meta.workflow.files.len()
+                + meta
+                    .workflow
+                    .testing
+                    .iter()
+                    .map(|t| t.files.len())
+                    .sum::<usize>()
+        );
+
+        let main = find(&ro_crate, "trimming_and_qc.cwl").unwrap();
+        assert_eq!(
+            main["programmingLanguage"]["@id"],
+            "https://w3id.org/workflowhub/workflow-ro-crate#cwl"
+        );
+        assert!(find(&ro_crate, "fastqc.cwl").is_some());
+
+        let suite = find(&ro_crate, "#test_suite_test_1").unwrap();
+        assert_eq!(suite["@type"], "TestSuite");
+        let definition = suite["definition"][0]["@id"].as_str().unwrap();
+        assert!(definition.starts_with("tests/test_1/"));
+        assert_eq!(
+            find(&ro_crate, definition).unwrap()["@type"],
+            json!(["File", "TestDefinition"])
+        );
+
+        let ro_crate = generate(&meta)?;
+        let primary_wf = meta.workflow.primary_wf()?;
+        let root = find(&ro_crate, "./").unwrap();
+        assert_eq!(root["mainEntity"]["@id"], primary_wf.url.as_str());
+        assert!(find(&ro_crate, primary_wf.url.as_str()).is_some());
+        assert!(find(&ro_crate, meta.workflow.readme.as_str()).is_some());
+        for file in &meta.workflow.testing[0].files {
+            assert!(find(&ro_crate, file.url.as_str()).is_some());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_package() -> Result<()> {
+        let meta = metadata::io::test_metadata()?;
+        let files = vec![(PathBuf::from("README.md"), b"# README\n".to_vec())];
+        let archive = package(&meta, &files)?;
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive.clone()))?;
+        assert_eq!(zip.len(), 2);
+        let ro_crate: Value = serde_json::from_reader(zip.by_name("ro-crate-metadata.json")?)?;
+        assert!(find(&ro_crate, "trimming_and_qc.cwl").is_some());
+        assert_eq!(
+            zip.by_name("README.md")?.compression(),
+            CompressionMethod::Stored
+        );
+        assert_eq!(archive, package(&meta, &files)?);
+        Ok(())
+    }
+}
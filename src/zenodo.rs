@@ -4,10 +4,12 @@ pub mod types;
 use crate::env;
 use crate::gh;
 use crate::metadata;
+use crate::workflow_ro_crate;
 
 use anyhow::{anyhow, ensure, Result};
 use log::info;
 use std::collections::HashMap;
+use std::fs;
 use std::path::PathBuf;
 use url::Url;
 
@@ -131,28 +133,40 @@ fn delete_unpublished_depositions(
 
 fn metadata_to_files(meta: &metadata::types::Metadata) -> Result<Vec<types::MetaFile>> {
     let mut files = vec![];
+    // (path in the Workflow RO-Crate, downloaded file)
+    let mut crate_files = vec![];
     files.push(types::MetaFile::new_from_str(
         serde_yaml::to_string(&meta)?,
         PathBuf::from(format!("yevis-metadata-{}.yml", meta.version)),
     )?);
-    files.push(types::MetaFile::new_from_url(
-        &meta.workflow.readme,
-        PathBuf::from("README.md"),
-    )?);
+    let readme = types::MetaFile::new_from_url(&meta.workflow.readme, PathBuf::from("README.md"))?;
+    crate_files.push((PathBuf::from("README.md"), readme.file_path.clone()));
+    files.push(readme);
     for file in &meta.workflow.files {
-        files.push(types::MetaFile::new_from_url(
-            &file.url,
-            file.target.as_ref().unwrap(),
-        )?); // validated
+        let target = file.target.as_ref().unwrap(); // validated
+        let meta_file = types::MetaFile::new_from_url(&file.url, target)?;
+        crate_files.push((target.clone(), meta_file.file_path.clone()));
+        files.push(meta_file);
     }
     for testing in &meta.workflow.testing {
         for file in &testing.files {
-            files.push(types::MetaFile::new_from_url(
-                &file.url,
-                file.target.as_ref().unwrap(),
-            )?); // validated
+            let target = file.target.as_ref().unwrap(); // validated
+            let meta_file = types::MetaFile::new_from_url(&file.url, target)?;
+            crate_files.push((
+                workflow_ro_crate::test_file_path(&testing.id, target),
+                meta_file.file_path.clone(),
+            ));
+            files.push(meta_file);
         }
     }
+    let crate_contents = crate_files
+        .into_iter()
+        .map(|(path, file_path)| Ok((path, fs::read(file_path)?)))
+        .collect::<Result<Vec<_>>>()?;
+    files.push(types::MetaFile::new_from_bytes(
+        workflow_ro_crate::package(meta, &crate_contents)?,
+        PathBuf::from(workflow_ro_crate::PACKAGE_FILE),
+    )?);
     Ok(files)
 }
 
@@ -234,7 +248,15 @@ pub fn update_metadata(
         .id;
     let files_map: HashMap<String, Url> =
         api::get_files_download_urls(&host, &token, &deposition_id)?;
+    update_file_urls(meta, &files_map)
+}
 
+/// Replace the URLs in the metadata with the download URLs of the files uploaded by `upload_zenodo`.
+/// `files_map`: (filename, download_url)
+pub fn update_file_urls(
+    meta: &mut metadata::types::Metadata,
+    files_map: &HashMap<String, Url>,
+) -> Result<()> {
     let err_msg = "Failed to update workflow metadata files.";
     meta.workflow.readme = files_map
         .get("README.md")
@@ -9,19 +9,20 @@ use std::path::Path;
 use std::time;
 use url::Url;
 
+/// Public resources (e.g., published records) can be read with an empty token.
 fn get_request(zenodo_token: impl AsRef<str>, url: &Url, query: &[(&str, &str)]) -> Result<Value> {
     // timeout is set to 10 minutes
     let client = reqwest::blocking::Client::builder()
         .timeout(time::Duration::from_secs(600))
         .build()?;
-    let response = client
-        .get(url.as_str())
-        .header(
+    let mut request = client.get(url.as_str()).query(query);
+    if !zenodo_token.as_ref().is_empty() {
+        request = request.header(
             reqwest::header::AUTHORIZATION,
             format!("Bearer {}", zenodo_token.as_ref()),
-        )
-        .query(query)
-        .send()?;
+        );
+    }
+    let response = request.send()?;
     let status = response.status();
     let res_body = response.json::<Value>()?;
     ensure!(
@@ -340,6 +341,19 @@ pub fn retrieve_record(
     token: impl AsRef<str>,
     record_id: &u64,
 ) -> Result<(metadata::types::Zenodo, String)> {
+    let record = get_record(host, token, record_id)?;
+    let version = record
+        .version
+        .ok_or_else(|| anyhow!("Failed to parse the response when retrieving a deposition"))?;
+    Ok((record.zenodo, version))
+}
+
+/// https://developers.zenodo.org/?shell#retrieve
+pub fn get_record(
+    host: impl AsRef<str>,
+    token: impl AsRef<str>,
+    record_id: &u64,
+) -> Result<zenodo::types::Record> {
     let url = Url::parse(&format!(
         "https://{}/api/records/{}",
         host.as_ref(),
@@ -364,39 +378,42 @@ pub fn retrieve_record(
         .as_str()
         .ok_or_else(|| anyhow!(err_msg))?;
     let url = Url::parse(&format!("https://{}/record/{}", host.as_ref(), &id))?;
-    let version = res_obj
+    let res_metadata = res_obj
         .get("metadata")
         .ok_or_else(|| anyhow!(err_msg))?
         .as_object()
-        .ok_or_else(|| anyhow!(err_msg))?
-        .get("version")
-        .ok_or_else(|| anyhow!(err_msg))?
-        .as_str()
         .ok_or_else(|| anyhow!(err_msg))?;
+    let version = res_metadata
+        .get("version")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+    let license = res_metadata
+        .get("license")
+        .and_then(|v| v.get("id"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+    let creators: Vec<zenodo::types::Creator> = match res_metadata.get("creators") {
+        Some(creators) => serde_json::from_value(creators.clone())?,
+        None => vec![],
+    };
+    let files = parse_files(&res)?;
 
-    Ok((
-        metadata::types::Zenodo {
+    Ok(zenodo::types::Record {
+        zenodo: metadata::types::Zenodo {
             url,
             id,
             doi: doi.to_string(),
             concept_doi: concept_doi.to_string(),
         },
-        version.to_string(),
-    ))
+        version,
+        license,
+        creators,
+        files,
+    })
 }
 
-/// https://github.com/zenodo/zenodo/issues/2246
-pub fn get_files_download_urls(
-    host: impl AsRef<str>,
-    token: impl AsRef<str>,
-    record_id: &u64,
-) -> Result<HashMap<String, Url>> {
-    let url = Url::parse(&format!(
-        "https://{}/api/records/{}",
-        host.as_ref(),
-        record_id
-    ))?;
-    let res = get_request(&token, &url, &[])?;
+/// Return: (filename, download_url) of the files in the record
+fn parse_files(res: &Value) -> Result<Vec<(String, Url)>> {
     let err_msg = "Failed to parse the response when retrieving a deposition";
     let files_arr = res
         .as_object()
@@ -405,7 +422,7 @@ pub fn get_files_download_urls(
         .ok_or_else(|| anyhow!(err_msg))?
         .as_array()
         .ok_or_else(|| anyhow!(err_msg))?;
-    let mut files_map: HashMap<String, Url> = HashMap::new();
+    let mut files = vec![];
     for file_obj in files_arr {
         let filename = file_obj
             .as_object()
@@ -425,10 +442,24 @@ pub fn get_files_download_urls(
             .ok_or_else(|| anyhow!(err_msg))?
             .as_str()
             .ok_or_else(|| anyhow!(err_msg))?;
-        files_map.insert(filename.to_string(), Url::parse(download_url)?);
+        files.push((filename.to_string(), Url::parse(download_url)?));
     }
+    Ok(files)
+}
 
-    Ok(files_map)
+/// https://github.com/zenodo/zenodo/issues/2246
+pub fn get_files_download_urls(
+    host: impl AsRef<str>,
+    token: impl AsRef<str>,
+    record_id: &u64,
+) -> Result<HashMap<String, Url>> {
+    let url = Url::parse(&format!(
+        "https://{}/api/records/{}",
+        host.as_ref(),
+        record_id
+    ))?;
+    let res = get_request(&token, &url, &[])?;
+    Ok(parse_files(&res)?.into_iter().collect())
 }
 
 /// https://developers.zenodo.org/?shell#list23
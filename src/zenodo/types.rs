@@ -1,6 +1,6 @@
 use crate::metadata;
 
-use anyhow::{ensure, Result};
+use anyhow::{anyhow, ensure, Result};
 use crypto::digest::Digest;
 use crypto::md5::Md5;
 use serde::{Deserialize, Serialize};
@@ -71,6 +71,106 @@ impl Creator {
     }
 }
 
+/// A published record, used as a workflow source by `make-template`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Record {
+    pub zenodo: metadata::types::Zenodo,
+    pub version: Option<String>,
+    /// License id in Zenodo, e.g., `cc-by-4.0`
+    pub license: Option<String>,
+    pub creators: Vec<Creator>,
+    /// (filename, download_url)
+    pub files: Vec<(String, Url)>,
+}
+
+impl Record {
+    /// The metadata file uploaded by `yevis publish`, i.e., `yevis-metadata-<version>.yml`.
+    /// The one for the version of the record is preferred.
+    pub fn metadata_file(&self) -> Option<&Url> {
+        let mut meta_files = self
+            .files
+            .iter()
+            .filter(|(filename, _)| is_metadata_file(filename))
+            .collect::<Vec<_>>();
+        meta_files.sort_by_key(|(filename, _)| {
+            (
+                self.version
+                    .as_ref()
+                    .map(|v| format!("yevis-metadata-{}.yml", v))
+                    != Some(filename.to_string()),
+                filename.to_string(),
+            )
+        });
+        meta_files.first().map(|(_, url)| url)
+    }
+
+    pub fn readme(&self) -> Option<&Url> {
+        self.files
+            .iter()
+            .find(|(filename, _)| is_readme(filename))
+            .map(|(_, url)| url)
+    }
+
+    /// Return: (filename, download_url) of the primary workflow.
+    /// If `filename` is not specified, the record must contain exactly one workflow file.
+    pub fn primary_wf(&self, filename: &Option<impl AsRef<str>>) -> Result<(String, Url)> {
+        match filename {
+            Some(filename) => self
+                .files
+                .iter()
+                .find(|(f, _)| f == filename.as_ref())
+                .cloned()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "File {} is not found in Zenodo record {}",
+                        filename.as_ref(),
+                        self.zenodo.url
+                    )
+                }),
+            None => {
+                let candidates = self
+                    .files
+                    .iter()
+                    .filter(|(f, _)| is_wf_file(f))
+                    .collect::<Vec<_>>();
+                ensure!(
+                    candidates.len() == 1,
+                    "Zenodo record {} has {} workflow files, please specify the primary workflow as {}/files/<filename>",
+                    self.zenodo.url,
+                    candidates.len(),
+                    self.zenodo.url
+                );
+                Ok(candidates[0].clone())
+            }
+        }
+    }
+
+    /// Files other than the README and the metadata files.
+    pub fn wf_files(&self) -> Vec<&(String, Url)> {
+        self.files
+            .iter()
+            .filter(|(filename, _)| !is_readme(filename) && !is_metadata_file(filename))
+            .collect()
+    }
+}
+
+fn is_metadata_file(filename: impl AsRef<str>) -> bool {
+    let filename = filename.as_ref();
+    filename.starts_with("yevis-metadata-") && filename.ends_with(".yml")
+}
+
+fn is_readme(filename: impl AsRef<str>) -> bool {
+    filename.as_ref().to_lowercase().starts_with("readme")
+}
+
+fn is_wf_file(filename: impl AsRef<str>) -> bool {
+    let path = Path::new(filename.as_ref());
+    path.file_name().is_some_and(|name| name == "Snakefile")
+        || path.extension().is_some_and(|ext| {
+            ["cwl", "wdl", "nf", "smk"].contains(&ext.to_string_lossy().as_ref())
+        })
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Community {
     pub identifier: String,
@@ -141,7 +241,11 @@ impl MetaFile {
     }
 
     pub fn new_from_str(content: impl AsRef<str>, target: impl AsRef<Path>) -> Result<Self> {
-        let content_bytes = content.as_ref().as_bytes();
+        Self::new_from_bytes(content.as_ref().as_bytes(), target)
+    }
+
+    pub fn new_from_bytes(content: impl AsRef<[u8]>, target: impl AsRef<Path>) -> Result<Self> {
+        let content_bytes = content.as_ref();
 
         let (mut file, file_path) = tempfile::NamedTempFile::new()?.keep()?;
         file.write_all(content_bytes)?;
@@ -162,3 +266,78 @@ impl MetaFile {
         })
     }
 }
+
+#[cfg(test)]
+#[cfg(not(tarpaulin_include))]
+mod tests {
+    use super::*;
+
+    fn record(filenames: &[&str]) -> Result<Record> {
+        Ok(Record {
+            zenodo: metadata::types::Zenodo {
+                url: Url::parse("https://zenodo.org/record/1234")?,
+                id: 1234,
+                doi: "10.5281/zenodo.1234".to_string(),
+                concept_doi: "10.5281/zenodo.1233".to_string(),
+            },
+            version: Some("1.0.1".to_string()),
+            license: Some("cc-by-4.0".to_string()),
+            creators: vec![],
+            files: filenames
+                .iter()
+                .map(|f| -> Result<(String, Url)> {
+                    Ok((
+                        f.to_string(),
+                        Url::parse(&format!(
+                            "https://zenodo.org/api/records/1234/files/{}/content",
+                            f
+                        ))?,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+
+    #[test]
+    fn test_record() -> Result<()> {
+        let record = record(&[
+            "README.md",
+            "main.cwl",
+            "tools_fastqc.cwl.yml",
+            "yevis-metadata-1.0.0.yml",
+            "yevis-metadata-1.0.1.yml",
+        ])?;
+        assert_eq!(
+            record.metadata_file().map(|u| u.as_str()),
+            Some("https://zenodo.org/api/records/1234/files/yevis-metadata-1.0.1.yml/content")
+        );
+        assert_eq!(
+            record.readme().map(|u| u.as_str()),
+            Some("https://zenodo.org/api/records/1234/files/README.md/content")
+        );
+        assert_eq!(record.primary_wf(&None::<String>)?.0, "main.cwl");
+        assert_eq!(
+            record.primary_wf(&Some("tools_fastqc.cwl.yml"))?.0,
+            "tools_fastqc.cwl.yml"
+        );
+        assert!(record.primary_wf(&Some("missing.cwl")).is_err());
+        assert_eq!(
+            record
+                .wf_files()
+                .iter()
+                .map(|(f, _)| f.as_str())
+                .collect::<Vec<_>>(),
+            vec!["main.cwl", "tools_fastqc.cwl.yml"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_multiple_wf_files() -> Result<()> {
+        let record = record(&["main.cwl", "sub.cwl"])?;
+        assert!(record.primary_wf(&None::<String>).is_err());
+        assert!(record.metadata_file().is_none());
+        assert!(record.readme().is_none());
+        Ok(())
+    }
+}